@@ -1,5 +1,11 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
 use pact_matching::models::*;
 use pact_matching::models::v4::SynchronousHttp;
+use pact_mock_server::matching::InvocationCount;
+use pact_mock_server::streaming::StreamedBody;
 use pact_models::provider_states::ProviderState;
 
 use super::request_builder::RequestBuilder;
@@ -12,6 +18,8 @@ pub struct InteractionBuilder {
     provider_states: Vec<ProviderState>,
     comments: Vec<String>,
     test_name: Option<String>,
+    invocation_count: Option<InvocationCount>,
+    streamed_body: Option<StreamedBody>,
 
     /// A builder for this interaction's `Request`.
     pub request: RequestBuilder,
@@ -28,6 +36,8 @@ impl InteractionBuilder {
       provider_states: vec![],
       comments: vec![],
       test_name: None,
+      invocation_count: None,
+      streamed_body: None,
       request: RequestBuilder::default(),
       response: ResponseBuilder::default(),
     }
@@ -56,6 +66,43 @@ impl InteractionBuilder {
     self
   }
 
+  /// Sets the number of times this interaction is expected to be received by the mock server.
+  /// The mock server will report a mismatch if it is received a different number of times.
+  pub fn times(&mut self, count: usize) -> &mut Self {
+    self.invocation_count = Some(InvocationCount::Times(count));
+    self
+  }
+
+  /// Sets the minimum number of times this interaction is expected to be received by the mock server.
+  pub fn at_least(&mut self, count: usize) -> &mut Self {
+    self.invocation_count = Some(InvocationCount::AtLeast(count));
+    self
+  }
+
+  /// Sets the maximum number of times this interaction is expected to be received by the mock server.
+  pub fn at_most(&mut self, count: usize) -> &mut Self {
+    self.invocation_count = Some(InvocationCount::AtMost(count));
+    self
+  }
+
+  /// The number of times this interaction is expected to be received, if it has been set
+  pub fn invocation_count(&self) -> Option<InvocationCount> {
+    self.invocation_count
+  }
+
+  /// Sets the response body to be streamed by the mock server as a sequence of chunks or
+  /// Server-Sent Events, using chunked transfer encoding. The response body of the interaction
+  /// should still describe the complete body, as that is what the provider is verified against.
+  pub fn streamed_response(&mut self, body: StreamedBody) -> &mut Self {
+    self.streamed_body = Some(body);
+    self
+  }
+
+  /// The body to be streamed by the mock server, if it has been set
+  pub fn streamed_body(&self) -> Option<StreamedBody> {
+    self.streamed_body.clone()
+  }
+
   /// The interaction we've built.
   pub fn build(&self) -> RequestResponseInteraction {
     RequestResponseInteraction {
@@ -76,8 +123,19 @@ impl InteractionBuilder {
       provider_states: self.provider_states.clone(),
       request: self.request.build().as_v4_request(),
       response: self.response.build().as_v4_response(),
-      comments: Default::default(),
+      comments: self.build_comments(),
       pending: false
     }
   }
+
+  fn build_comments(&self) -> HashMap<String, Value> {
+    let mut comments = HashMap::new();
+    if let Some(ref invocation_count) = self.invocation_count {
+      comments.insert("invocationCount".to_string(), invocation_count.to_json());
+    }
+    if let Some(ref streamed_body) = self.streamed_body {
+      comments.insert("streamedBody".to_string(), streamed_body.to_json());
+    }
+    comments
+  }
 }
@@ -1,4 +1,11 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use pact_matching::models::*;
+use pact_mock_server::matching::InvocationCount;
+use pact_mock_server::streaming::StreamedBody;
+use pact_mock_server::mock_server::MockServerConfig;
+use pact_mock_server::observer::MockServerObserver;
 use pact_models::{Consumer, Provider};
 
 use crate::prelude::*;
@@ -27,6 +34,9 @@ use pact_matching::models::v4::V4Pact;
 /// ```
 pub struct PactBuilder {
   pact: Box<dyn Pact>,
+  invocation_counts: HashMap<String, InvocationCount>,
+  streamed_bodies: HashMap<String, StreamedBody>,
+  observers: Vec<Arc<dyn MockServerObserver>>
 }
 
 impl PactBuilder {
@@ -44,7 +54,7 @@ impl PactBuilder {
         pact.provider = Provider {
             name: provider.into(),
         };
-        PactBuilder { pact: pact.boxed() }
+        PactBuilder { pact: pact.boxed(), invocation_counts: HashMap::new(), streamed_bodies: HashMap::new(), observers: vec![] }
     }
 
     /// Create a new `PactBuilder` for a V4 specification Pact, specifying the names of the service
@@ -59,7 +69,7 @@ impl PactBuilder {
         provider: Provider { name: provider.into() },
         .. V4Pact::default()
       };
-      PactBuilder { pact: pact.boxed() }
+      PactBuilder { pact: pact.boxed(), invocation_counts: HashMap::new(), streamed_bodies: HashMap::new(), observers: vec![] }
     }
 
     /// Add a new HTTP `Interaction` to the `Pact`.
@@ -68,8 +78,15 @@ impl PactBuilder {
         D: Into<String>,
         F: FnOnce(&mut InteractionBuilder),
     {
-        let mut interaction = InteractionBuilder::new(description.into());
+        let description = description.into();
+        let mut interaction = InteractionBuilder::new(description.clone());
         build_fn(&mut interaction);
+        if let Some(invocation_count) = interaction.invocation_count() {
+          self.invocation_counts.insert(description.clone(), invocation_count);
+        }
+        if let Some(streamed_body) = interaction.streamed_body() {
+          self.streamed_bodies.insert(description, streamed_body);
+        }
         self.push_interaction(&interaction.build())
     }
 
@@ -80,6 +97,13 @@ impl PactBuilder {
       self
     }
 
+    /// Register an observer that is notified of the requests received by any mock server started
+    /// from this builder, as they are matched.
+    pub fn observer(&mut self, observer: Arc<dyn MockServerObserver>) -> &mut Self {
+      self.observers.push(observer);
+      self
+    }
+
     /// Return the `Pact` we've built.
     pub fn build(&self) -> Box<dyn Pact + Send> {
       self.pact.boxed()
@@ -88,6 +112,11 @@ impl PactBuilder {
 
 impl StartMockServer for PactBuilder {
     fn start_mock_server(&self) -> ValidatingMockServer {
-        ValidatingMockServer::start(self.build())
+        ValidatingMockServer::start_with_config(self.build(), MockServerConfig {
+          invocation_counts: self.invocation_counts.clone(),
+          streamed_bodies: self.streamed_bodies.clone(),
+          observers: self.observers.clone(),
+          .. MockServerConfig::default()
+        })
     }
 }
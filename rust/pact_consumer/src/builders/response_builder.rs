@@ -3,7 +3,8 @@ use std::collections::HashMap;
 use maplit::*;
 
 use pact_models::bodies::OptionalBody;
-use pact_models::generators::Generators;
+use pact_models::expression_parser::DataType;
+use pact_models::generators::{Generator, GeneratorCategory, Generators};
 use pact_models::matchingrules::MatchingRules;
 use pact_models::response::Response;
 
@@ -62,6 +63,53 @@ impl ResponseBuilder {
         self.status(404)
     }
 
+    /// Set a header whose value is taken from the request received by the mock server, looked up
+    /// with a path expression (i.e. `$.path[1]`, `$.query.page`, `$.headers['x-id']` or
+    /// `$.body.orderId`). The example value is used in the pact file.
+    ///
+    /// ```
+    /// use pact_consumer::builders::ResponseBuilder;
+    /// use pact_consumer::prelude::*;
+    ///
+    /// ResponseBuilder::default()
+    ///     .header_from_request("X-Request-Id", "$.headers['x-request-id']", "1234");
+    /// ```
+    pub fn header_from_request<N, E, V>(&mut self, name: N, expression: E, value: V) -> &mut Self
+      where
+        N: Into<String>,
+        E: Into<String>,
+        V: Into<StringPattern>,
+    {
+      let name = name.into();
+      self.header(&name, value);
+      self.response.generators.add_generator_with_subcategory(&GeneratorCategory::HEADER, name,
+        Generator::RequestValue(expression.into(), Some(DataType::STRING)));
+      self
+    }
+
+    /// Take the value of a field of the JSON body from the request received by the mock server,
+    /// looked up with a path expression (as for `header_from_request`). The field is given as a
+    /// path expression into the response body, which provides the example value.
+    ///
+    /// ```
+    /// use pact_consumer::builders::ResponseBuilder;
+    /// use pact_consumer::prelude::*;
+    /// use pact_consumer::*;
+    ///
+    /// ResponseBuilder::default()
+    ///     .json_body(json_pattern!({ "orderId": 100 }))
+    ///     .body_field_from_request("$.orderId", "$.body.orderId");
+    /// ```
+    pub fn body_field_from_request<P, E>(&mut self, path: P, expression: E) -> &mut Self
+      where
+        P: Into<String>,
+        E: Into<String>,
+    {
+      self.response.generators.add_generator_with_subcategory(&GeneratorCategory::BODY, path,
+        Generator::RequestValue(expression.into(), None));
+      self
+    }
+
     /// Build the specified `Response` object.
     pub fn build(&self) -> Response {
         self.response.clone()
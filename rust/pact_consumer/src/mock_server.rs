@@ -1,6 +1,9 @@
 //! Support for mock HTTP servers that verify pacts.
 
+use lazy_static::*;
 use pact_matching::models::*;
+use pact_matching::models::v4::V4Pact;
+use pact_mock_server::aggregator::PactAggregator;
 use pact_mock_server::matching::MatchResult;
 use pact_mock_server::*;
 use std::{
@@ -13,6 +16,11 @@ use url::Url;
 use pact_mock_server::mock_server::{MockServerConfig, MockServerMetrics};
 use std::sync::{Mutex, Arc};
 
+lazy_static! {
+  /// Collects the interactions of all the mock servers when `PACT_AGGREGATE` is set
+  static ref AGGREGATOR: Mutex<PactAggregator> = Mutex::new(PactAggregator::new());
+}
+
 /// This trait is implemented by types which allow us to start a mock server.
 pub trait StartMockServer {
     /// Start a mock server running in a background thread.
@@ -25,6 +33,12 @@ impl StartMockServer for RequestResponsePact {
   }
 }
 
+impl StartMockServer for V4Pact {
+  fn start_mock_server(&self) -> ValidatingMockServer {
+    ValidatingMockServer::start(self.boxed())
+  }
+}
+
 /// A mock HTTP server that handles the requests described in a `Pact`, intended
 /// for use in tests, and validates that the requests made to that server are
 /// correct.
@@ -46,6 +60,12 @@ impl ValidatingMockServer {
   /// Create a new mock server which handles requests as described in the
   /// pact, and runs in a background thread
   pub fn start(pact: Box<dyn Pact + Send>) -> ValidatingMockServer {
+    ValidatingMockServer::start_with_config(pact, MockServerConfig::default())
+  }
+
+  /// Create a new mock server with the given config which handles requests as described in the
+  /// pact, and runs in a background thread
+  pub fn start_with_config(pact: Box<dyn Pact + Send>, config: MockServerConfig) -> ValidatingMockServer {
     // Spawn new runtime in thread to prevent reactor execution context conflict
     let (pact_tx, pact_rx) = std::sync::mpsc::channel::<Box<dyn Pact + Send>>();
     pact_tx.send(pact).expect("INTERNAL ERROR: Could not pass pact into mock server thread");
@@ -57,7 +77,7 @@ impl ValidatingMockServer {
 
       let (mock_server, server_future) = runtime.block_on(async move {
         mock_server::MockServer::new("".into(), pact_rx.recv().unwrap(), ([0, 0, 0, 0], 0 as u16).into(),
-          MockServerConfig::default())
+          config)
           .await
           .unwrap()
       });
@@ -124,8 +144,8 @@ impl ValidatingMockServer {
     /// flow control in `drop` ultra-complex.
     fn drop_helper(&mut self) -> Result<(), String> {
         // Kill the server
-        let mut ms = self.mock_server.lock().unwrap();
-        ms.shutdown()?;
+        mock_server::MockServer::shutdown_and_notify(&self.mock_server)?;
+        let ms = self.mock_server.lock().unwrap();
 
         if ::std::thread::panicking() {
             return Ok(());
@@ -141,11 +161,18 @@ impl ValidatingMockServer {
 
         if mismatches.is_empty() {
             // Success! Write out the generated pact file.
-            ms.write_pact(
-              &Some(
-                env::var("PACT_OUTPUT_DIR").unwrap_or_else(|_| "target/pacts".to_owned())),
-              env::var("PACT_OVERWRITE").unwrap_or_else(|_| "false".to_owned()) == "true")
-            .map_err(|err| format!("error writing pact: {}", err))?;
+            let output_dir = Some(
+              env::var("PACT_OUTPUT_DIR").unwrap_or_else(|_| "target/pacts".to_owned()));
+            if env::var("PACT_AGGREGATE").unwrap_or_else(|_| "false".to_owned()) == "true" {
+              let mut aggregator = AGGREGATOR.lock().unwrap();
+              aggregator.add_mock_server(&ms)?;
+              aggregator.write_pacts(&output_dir, &None)
+                .map_err(|err| format!("error writing pact: {}", err))?;
+            } else {
+              ms.write_pact(&output_dir,
+                env::var("PACT_OVERWRITE").unwrap_or_else(|_| "false".to_owned()) == "true")
+                .map_err(|err| format!("error writing pact: {}", err))?;
+            }
             Ok(())
         } else {
             // Failure. Format our errors.
@@ -172,13 +199,28 @@ impl ValidatingMockServer {
                         );
                         let _ = writeln!(&mut msg, "{:#?}", request);
                     }
+                    MatchResult::InvocationCountMismatch(request, expected, actual) => {
+                        let _ = writeln!(
+                            &mut msg,
+                            "- request {} expected {}, but occurred {} times", request, expected, actual,
+                        );
+                    }
+                    MatchResult::RequestOutOfOrder(request, expected, actual) => {
+                        let _ = writeln!(
+                            &mut msg,
+                            "- request {} received out of order (expected position {}, but was {})",
+                            request, expected, actual,
+                        );
+                    }
                 }
             }
             Err(msg)
         }
     }
 
-  /// Returns the metrics collected by the mock server
+  /// Returns the metrics collected by the mock server. These include the number of requests
+  /// that matched each interaction, the number of mismatches, a histogram of the request durations
+  /// and the number of bytes received and sent.
   pub fn metrics(&self) -> MockServerMetrics {
     self.mock_server.lock().unwrap().metrics.clone()
   }
@@ -28,7 +28,7 @@ pub(crate) fn check_requests_match(
     // Next, check each interaction to see if it matches.
     for (e, a) in expected.interactions().iter().zip(actual.interactions()) {
         let actual_request = a.as_request_response().unwrap().request.clone();
-        let generated_request = generate_request(&actual_request, &GeneratorTestMode::Provider, context);
+        let generated_request = generate_request(&actual_request, &GeneratorTestMode::Provider, context, None);
         let mismatches = match_request(e.as_request_response().unwrap().request.clone(),
                                        generated_request);
         if !mismatches.all_matched() {
@@ -13,6 +13,7 @@ use pact_consumer::{json_pattern, json_pattern_internal};
 use pact_consumer::prelude::*;
 use pact_matching::models::ReadWritePact;
 use pact_matching::models::RequestResponsePact;
+use pact_mock_server::streaming::{ServerSentEvent, StreamedBody};
 
 /// This is supposed to be a doctest in mod, but it's breaking there, so
 /// we have an executable copy here.
@@ -83,6 +84,71 @@ fn mock_server_failing_validation() {
     let _ = reqwest::blocking::get(url);
 }
 
+#[test]
+#[should_panic]
+fn mock_server_failing_validation_when_interaction_called_too_many_times() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let hello_service = PactBuilder::new("Hello CLI", "Hello Server")
+        .interaction("request a greeting once", |i| {
+            i.times(1);
+            i.request.path("/hello");
+            i.response.body("Hello!");
+        })
+        .start_mock_server();
+    // Calling the interaction twice should lead to a panic at the end of the function.
+    let url = hello_service.path("/hello");
+    let _ = reqwest::blocking::get(url.clone());
+    let _ = reqwest::blocking::get(url);
+}
+
+#[test]
+fn mock_server_streams_server_sent_events() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let events = StreamedBody::EventStream(vec![
+      ServerSentEvent { event: Some("greeting".to_string()), data: "Hello".to_string(), .. ServerSentEvent::default() },
+      ServerSentEvent { event: Some("greeting".to_string()), data: "World".to_string(), .. ServerSentEvent::default() }
+    ]);
+    let body = String::from_utf8(events.content().to_vec()).unwrap();
+    let event_service = PactBuilder::new_v4("Event Consumer", "Event Server")
+        .interaction("subscribe to greetings", |i| {
+            i.request.path("/greetings");
+            i.response
+              .header("Content-Type", "text/event-stream")
+              .body(body.clone());
+            i.streamed_response(events.clone());
+        })
+        .start_mock_server();
+
+    let response = reqwest::blocking::get(event_service.path("/greetings")).unwrap();
+    expect!(response.headers().get("transfer-encoding").unwrap().to_str().unwrap()).to(be_equal_to("chunked"));
+    expect!(response.text().unwrap()).to(be_equal_to(body));
+}
+
+#[test]
+fn mock_server_collects_metrics() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let hello_service = PactBuilder::new("Hello CLI", "Hello Server")
+        .interaction("request a greeting", |i| {
+            i.request.path("/hello");
+            i.response.body("Hello!");
+        })
+        .start_mock_server();
+
+    let url = hello_service.path("/hello");
+    let _ = reqwest::blocking::get(url.clone());
+    let _ = reqwest::blocking::get(url);
+
+    let metrics = hello_service.metrics();
+    expect!(metrics.requests).to(be_equal_to(2));
+    expect!(metrics.interaction_hits.get("request a greeting")).to(be_some().value(&2));
+    expect!(metrics.mismatches.is_empty()).to(be_true());
+    expect!(metrics.request_durations.count).to(be_equal_to(2));
+    expect!(metrics.response_body_bytes).to(be_equal_to(12));
+}
+
 #[tokio::test]
 async fn duplicate_interactions() {
   let _ = env_logger::builder().is_test(true).try_init();
@@ -49,7 +49,7 @@ use std::collections::BTreeMap;
 use std::ffi::CStr;
 use std::ffi::CString;
 use std::panic::catch_unwind;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::ptr::null_mut;
 use std::str::from_utf8;
 
@@ -68,7 +68,8 @@ use uuid::Uuid;
 use pact_matching::logging::fetch_buffer_contents;
 use pact_matching::models::{Pact, RequestResponseInteraction};
 use pact_matching::models::message::Message;
-use pact_mock_server::{MANAGER, MockServerError, tls::TlsConfigBuilder, WritePactFileErr};
+use pact_mock_server::{MANAGER, MockServerError, WritePactFileErr};
+use pact_mock_server::tls::{process_certificate_authority, TlsConfigBuilder};
 use pact_mock_server::server_manager::ServerManager;
 use pact_models::bodies::OptionalBody::{Null, Present};
 use pact_models::bodies::OptionalBody;
@@ -102,7 +103,8 @@ pub mod bodies;
 ///
 /// * `pact_str` - Pact JSON
 /// * `addr_str` - Address to bind to in the form name:port (i.e. 127.0.0.1:0)
-/// * `tls` - boolean flag to indicate of the mock server should use TLS (using a self-signed certificate)
+/// * `tls` - boolean flag to indicate of the mock server should use TLS (using a generated certificate
+///   signed by the CA returned by `pactffi_get_tls_ca_certificate`)
 ///
 /// # Errors
 ///
@@ -115,7 +117,7 @@ pub mod bodies;
 /// | -3 | The mock server could not be started |
 /// | -4 | The method panicked |
 /// | -5 | The address is not valid |
-/// | -6 | Could not create the TLS configuration with the self-signed certificate |
+/// | -6 | Could not create the TLS configuration with the generated certificate |
 ///
 #[no_mangle]
 pub extern fn pactffi_create_mock_server(pact_str: *const c_char, addr_str: *const c_char, tls: bool) -> i32 {
@@ -136,24 +138,19 @@ pub extern fn pactffi_create_mock_server(pact_str: *const c_char, addr_str: *con
       CStr::from_ptr(addr_str)
     };
 
-    let tls_config = if tls {
-      let key = include_str!("self-signed.key");
-      let cert = include_str!("self-signed.crt");
-      match TlsConfigBuilder::new()
-        .key(key.as_bytes())
-        .cert(cert.as_bytes())
-        .build() {
-        Ok(tls_config) => Some(tls_config),
-        Err(err) => {
-          error!("Failed to build TLS configuration - {}", err);
-          return -6;
+    if let Ok(Ok(addr)) = str::from_utf8(addr_c_str.to_bytes()).map(|s| s.parse::<std::net::SocketAddr>()) {
+      let tls_config = if tls {
+        match generated_tls_config(&addr).build() {
+          Ok(tls_config) => Some(tls_config),
+          Err(err) => {
+            error!("Failed to build TLS configuration - {}", err);
+            return -6;
+          }
         }
-      }
-    } else {
-      None
-    };
+      } else {
+        None
+      };
 
-    if let Ok(Ok(addr)) = str::from_utf8(addr_c_str.to_bytes()).map(|s| s.parse::<std::net::SocketAddr>()) {
       let server_result = match tls_config {
         Some(tls_config) => pact_mock_server::create_tls_mock_server(str::from_utf8(c_str.to_bytes()).unwrap(), addr, &tls_config),
         None => pact_mock_server::create_mock_server(str::from_utf8(c_str.to_bytes()).unwrap(), addr)
@@ -183,29 +180,48 @@ pub extern fn pactffi_create_mock_server(pact_str: *const c_char, addr_str: *con
   }
 }
 
-/// Fetch the CA Certificate used to generate the self-signed certificate for the TLS mock server.
+/// Fetch the CA Certificate (in PEM format) that signs the certificates generated for the TLS mock
+/// servers. The CA is generated for the process, so clients need to be configured to trust it.
 ///
 /// **NOTE:** The string for the result is allocated on the heap, and will have to be freed
 /// by the caller using free_string
 ///
 /// # Errors
 ///
-/// An empty string indicates an error reading the pem file
+/// An empty string indicates an error generating the CA
 #[no_mangle]
 pub extern fn pactffi_get_tls_ca_certificate() -> *mut c_char  {
-  let cert_file = include_str!("ca.pem");
-  let cert_str = CString::new(cert_file).unwrap_or_default();
+  let cert_pem = match process_certificate_authority() {
+    Ok(ca) => ca.certificate_pem(),
+    Err(err) => {
+      error!("Failed to generate the CA certificate - {}", err);
+      String::default()
+    }
+  };
+  let cert_str = CString::new(cert_pem).unwrap_or_default();
 
   cert_str.into_raw()
 }
 
+/// Returns the TLS configuration builder for a mock server listening on the address. The server
+/// certificate is generated for localhost and the address, and signed by the CA of the process.
+fn generated_tls_config(addr: &std::net::SocketAddr) -> TlsConfigBuilder {
+  let mut subject_alt_names = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+  let ip = addr.ip().to_string();
+  if !addr.ip().is_unspecified() && !subject_alt_names.contains(&ip) {
+    subject_alt_names.push(ip);
+  }
+  TlsConfigBuilder::new().generate_cert(subject_alt_names)
+}
+
 /// External interface to create a mock server. A Pact handle is passed in,
 /// as well as the port for the mock server to run on. A value of 0 for the port will result in a
 /// port being allocated by the operating system. The port of the mock server is returned.
 ///
 /// * `pact` - Handle to a Pact model
 /// * `addr_str` - Address to bind to in the form name:port (i.e. 127.0.0.1:0)
-/// * `tls` - boolean flag to indicate of the mock server should use TLS (using a self-signed certificate)
+/// * `tls` - boolean flag to indicate of the mock server should use TLS (using a generated certificate
+///   signed by the CA returned by `pactffi_get_tls_ca_certificate`)
 ///
 /// # Errors
 ///
@@ -217,7 +233,7 @@ pub extern fn pactffi_get_tls_ca_certificate() -> *mut c_char  {
 /// | -3 | The mock server could not be started |
 /// | -4 | The method panicked |
 /// | -5 | The address is not valid |
-/// | -6 | Could not create the TLS configuration with the self-signed certificate |
+/// | -6 | Could not create the TLS configuration with the generated certificate |
 ///
 #[no_mangle]
 pub extern fn pactffi_create_mock_server_for_pact(pact: handles::PactHandle, addr_str: *const c_char, tls: bool) -> i32 {
@@ -230,24 +246,19 @@ pub extern fn pactffi_create_mock_server_for_pact(pact: handles::PactHandle, add
       CStr::from_ptr(addr_str)
     };
 
-    let tls_config = if tls {
-      let key = include_str!("self-signed.key");
-      let cert = include_str!("self-signed.crt");
-      match TlsConfigBuilder::new()
-        .key(key.as_bytes())
-        .cert(cert.as_bytes())
-        .build() {
-        Ok(tls_config) => Some(tls_config),
-        Err(err) => {
-          error!("Failed to build TLS configuration - {}", err);
-          return -6;
+    if let Ok(Ok(addr)) = str::from_utf8(addr_c_str.to_bytes()).map(|s| s.parse::<std::net::SocketAddr>()) {
+      let tls_config = if tls {
+        match generated_tls_config(&addr).build() {
+          Ok(tls_config) => Some(tls_config),
+          Err(err) => {
+            error!("Failed to build TLS configuration - {}", err);
+            return -6;
+          }
         }
-      }
-    } else {
-      None
-    };
+      } else {
+        None
+      };
 
-    if let Ok(Ok(addr)) = str::from_utf8(addr_c_str.to_bytes()).map(|s| s.parse::<std::net::SocketAddr>()) {
       pact.with_pact(&move |_, inner| {
         let server_result = match &tls_config {
           Some(tls_config) => pact_mock_server::start_tls_mock_server(
@@ -342,6 +353,235 @@ pub extern fn pactffi_mock_server_mismatches(mock_server_port: i32) -> *mut c_ch
   }
 }
 
+/// External interface to export all the requests received by a mock server, and the responses that
+/// were sent back, as an HTTP Archive (HAR 1.2) document. The port number of the mock server is
+/// passed in, and a pointer to a C string with the HAR document in JSON format is returned.
+/// Requests that did not match an interaction are annotated with a `_matchResult` attribute.
+///
+/// **NOTE:** The JSON string for the result is allocated on the heap, and will have to be freed
+/// once the code using the mock server is complete. The [`cleanup_mock_server`](fn.cleanup_mock_server.html) function is
+/// provided for this purpose.
+///
+/// # Errors
+///
+/// If there is no mock server with the provided port number, or the function panics, a NULL
+/// pointer will be returned. Don't try to dereference it, it will not end well for you.
+///
+#[no_mangle]
+pub extern fn pactffi_mock_server_har(mock_server_port: i32) -> *mut c_char {
+  let result = catch_unwind(|| {
+    let result = MANAGER.lock().unwrap()
+      .get_or_insert_with(ServerManager::new)
+      .find_mock_server_by_port_mut(mock_server_port as u16, &|ref mut mock_server| {
+        let s = CString::new(mock_server.to_har().to_string()).unwrap();
+        let p = s.as_ptr();
+        mock_server.resources.push(s);
+        p
+      });
+    match result {
+      Some(p) => p as *mut _,
+      None => std::ptr::null_mut()
+    }
+  });
+
+  match result {
+    Ok(val) => val,
+    Err(cause) => {
+      error!("{}", error_message(cause, "mock_server_har"));
+      std::ptr::null_mut()
+    }
+  }
+}
+
+/// External interface to activate a provider state on a running mock server. Once the state is
+/// active, only the interactions with that provider state will be matched against the received
+/// requests. Passing a NULL pointer for the state clears it, and all the interactions will be
+/// matched again.
+///
+/// * `mock_server_port` - Port of the mock server
+/// * `provider_state` - Name of the provider state to activate, or NULL to clear it
+///
+/// Returns true if the provider state was set. False is returned if there is no mock server on
+/// the given port, the provider state is not valid UTF-8 or the function panics.
+#[no_mangle]
+pub extern fn pactffi_mock_server_set_provider_state(mock_server_port: i32, provider_state: *const c_char) -> bool {
+  let result = catch_unwind(|| {
+    let provider_state = if provider_state.is_null() {
+      None
+    } else {
+      match unsafe { CStr::from_ptr(provider_state) }.to_str() {
+        Ok(state) => Some(state.to_string()),
+        Err(err) => {
+          error!("Provider state is not valid UTF-8 - {}", err);
+          return false;
+        }
+      }
+    };
+    pact_mock_server::set_mock_server_provider_state(mock_server_port, provider_state)
+  });
+
+  match result {
+    Ok(val) => val,
+    Err(cause) => {
+      error!("Caught a general panic: {:?}", cause);
+      false
+    }
+  }
+}
+
+/// External interface to add an interaction to a running mock server, without restarting it.
+/// The interaction is passed in JSON format. If the mock server already has an interaction with
+/// the same description, it is replaced.
+///
+/// * `mock_server_port` - Port of the mock server
+/// * `interaction_json` - Interaction in JSON format. Interactions with a `type` attribute are
+/// loaded as V4 interactions.
+///
+/// Returns 0 if the interaction was added.
+///
+/// # Errors
+///
+/// Errors are returned as positive values.
+///
+/// | Error | Description |
+/// |-------|-------------|
+/// | 1 | A general panic was caught |
+/// | 2 | The interaction JSON is NULL or not valid UTF-8 |
+/// | 3 | The interaction is not valid, or there is no mock server running on the port |
+#[no_mangle]
+pub extern fn pactffi_mock_server_upsert_interaction(mock_server_port: i32, interaction_json: *const c_char) -> i32 {
+  let result = catch_unwind(|| {
+    match convert_cstr("interaction_json", interaction_json) {
+      Some(interaction_json) => match pact_mock_server::upsert_mock_server_interaction(mock_server_port, interaction_json) {
+        Ok(_) => 0,
+        Err(err) => {
+          error!("Failed to add the interaction to the mock server - {}", err);
+          3
+        }
+      },
+      None => 2
+    }
+  });
+
+  match result {
+    Ok(val) => val,
+    Err(cause) => {
+      error!("Caught a general panic: {:?}", cause);
+      1
+    }
+  }
+}
+
+/// External interface to remove an interaction from a running mock server, without restarting
+/// it. The interaction is identified by its description.
+///
+/// Returns true if the interaction was removed. False is returned if there is no mock server on
+/// the given port, the mock server has no interaction with the description, the description is
+/// not valid UTF-8 or the function panics.
+#[no_mangle]
+pub extern fn pactffi_mock_server_remove_interaction(mock_server_port: i32, description: *const c_char) -> bool {
+  let result = catch_unwind(|| {
+    match convert_cstr("description", description) {
+      Some(description) => match pact_mock_server::remove_mock_server_interaction(mock_server_port, description) {
+        Ok(removed) => removed,
+        Err(err) => {
+          error!("Failed to remove the interaction from the mock server - {}", err);
+          false
+        }
+      },
+      None => false
+    }
+  });
+
+  match result {
+    Ok(val) => val,
+    Err(cause) => {
+      error!("Caught a general panic: {:?}", cause);
+      false
+    }
+  }
+}
+
+/// External interface to replace all the interactions of a running mock server with the ones
+/// from a pact, without restarting it. The pact is passed in JSON format.
+///
+/// Returns 0 if the interactions were replaced.
+///
+/// # Errors
+///
+/// Errors are returned as positive values.
+///
+/// | Error | Description |
+/// |-------|-------------|
+/// | 1 | A general panic was caught |
+/// | 2 | The pact JSON is NULL or not valid UTF-8 |
+/// | 3 | The pact is not valid, or there is no mock server running on the port |
+#[no_mangle]
+pub extern fn pactffi_mock_server_replace_interactions(mock_server_port: i32, pact_json: *const c_char) -> i32 {
+  let result = catch_unwind(|| {
+    match convert_cstr("pact_json", pact_json) {
+      Some(pact_json) => match pact_mock_server::replace_mock_server_interactions(mock_server_port, pact_json) {
+        Ok(_) => 0,
+        Err(err) => {
+          error!("Failed to replace the interactions of the mock server - {}", err);
+          3
+        }
+      },
+      None => 2
+    }
+  });
+
+  match result {
+    Ok(val) => val,
+    Err(cause) => {
+      error!("Caught a general panic: {:?}", cause);
+      1
+    }
+  }
+}
+
+/// External interface to watch a pact file, and reload the interactions of a running mock server
+/// whenever the file is modified. The file is checked every `interval_ms` milliseconds until the
+/// mock server is cleaned up.
+///
+/// Returns 0 if the pact file is being watched.
+///
+/// # Errors
+///
+/// Errors are returned as positive values.
+///
+/// | Error | Description |
+/// |-------|-------------|
+/// | 1 | A general panic was caught |
+/// | 2 | The path is NULL or not valid UTF-8 |
+/// | 3 | The pact file could not be watched, or there is no mock server running on the port |
+#[no_mangle]
+pub extern fn pactffi_mock_server_watch_pact_file(mock_server_port: i32, pact_file: *const c_char, interval_ms: u32) -> i32 {
+  let result = catch_unwind(|| {
+    match convert_cstr("pact_file", pact_file) {
+      Some(pact_file) => {
+        let interval = std::time::Duration::from_millis(interval_ms.max(1) as u64);
+        match pact_mock_server::watch_mock_server_pact_file(mock_server_port, Path::new(pact_file), interval) {
+          Ok(_) => 0,
+          Err(err) => {
+            error!("Failed to watch the pact file - {}", err);
+            3
+          }
+        }
+      },
+      None => 2
+    }
+  });
+
+  match result {
+    Ok(val) => val,
+    Err(cause) => {
+      error!("Caught a general panic: {:?}", cause);
+      1
+    }
+  }
+}
+
 /// External interface to cleanup a mock server. This function will try terminate the mock server
 /// with the given port number and cleanup any memory allocated for it. Returns true, unless a
 /// mock server with the given port number does not exist, or the function panics.
@@ -403,6 +643,240 @@ pub extern fn pactffi_write_pact_file(mock_server_port: i32, directory: *const c
   }
 }
 
+/// External interface to write out one pact file for each consumer and provider, with the
+/// interactions exercised by all the mock servers started in this process. This should be called
+/// once all the consumer tests have passed, instead of writing the pact file for each mock server.
+/// Interactions that were not exercised are not included.
+///
+/// The directory to write the files to is passed in. If a NULL pointer is passed, the current
+/// working directory is used. The owner identifies the interactions written by this process: the
+/// interactions previously written by the same owner are replaced, and the ones written by other
+/// owners are kept. If a NULL pointer is passed, the name of the current executable is used.
+///
+/// Returns 0 if the pact files were successfully written.
+///
+/// # Errors
+///
+/// Errors are returned as positive values.
+///
+/// | Error | Description |
+/// |-------|-------------|
+/// | 1 | A general panic was caught |
+/// | 2 | A pact file was not able to be written |
+#[no_mangle]
+pub extern fn pactffi_write_aggregated_pact_files(directory: *const c_char, owner: *const c_char) -> i32 {
+  let result = catch_unwind(|| {
+    let dir = path_from_dir(directory, None);
+    let path = dir.map(|path| path.into_os_string().into_string().unwrap_or_default());
+    let owner = if owner.is_null() {
+      None
+    } else {
+      convert_cstr("owner", owner).map(|owner| owner.to_string())
+    };
+
+    pact_mock_server::write_aggregated_pact_files(path, owner)
+  });
+
+  match result {
+    Ok(Ok(_)) => 0,
+    Ok(Err(_)) => 2,
+    Err(cause) => {
+      log::error!("Caught a general panic: {:?}", cause);
+      1
+    }
+  }
+}
+
+/// External interface to create a mock server bound to a Unix domain socket instead of a TCP
+/// port. A Pact handle is passed in, as well as the path of the socket to bind to. Any existing
+/// socket file at the path (i.e. left behind by a process that was killed) will be replaced, and
+/// the socket file is removed when the mock server is cleaned up with
+/// `pactffi_cleanup_unix_socket_mock_server`. If the path exists and is not a socket, the mock
+/// server is not started.
+///
+/// This function is only supported on Unix platforms.
+///
+/// # Errors
+///
+/// Errors are returned as negative values.
+///
+/// | Error | Description |
+/// |-------|-------------|
+/// | -1 | An invalid handle was received. Handles should be created with `pactffi_new_pact` |
+/// | -3 | The mock server could not be started (i.e. the path exists and is not a socket) |
+/// | -4 | The method panicked |
+/// | -5 | The socket path is not valid |
+///
+#[no_mangle]
+pub extern fn pactffi_create_mock_server_for_unix_socket(pact: handles::PactHandle, socket_path: *const c_char) -> i32 {
+  let result = catch_unwind(|| {
+    let socket_path = match convert_cstr("socket_path", socket_path) {
+      Some(path) if !path.is_empty() => PathBuf::from(path),
+      _ => return -5
+    };
+    start_unix_socket_mock_server(pact, socket_path)
+  });
+
+  match result {
+    Ok(val) => val,
+    Err(cause) => {
+      log::error!("Caught a general panic: {:?}", cause);
+      -4
+    }
+  }
+}
+
+#[cfg(unix)]
+fn start_unix_socket_mock_server(pact: handles::PactHandle, socket_path: PathBuf) -> i32 {
+  pact.with_pact(&move |_, inner| {
+    match pact_mock_server::start_mock_server_with_unix_socket(Uuid::new_v4().to_string(),
+      inner.pact.boxed(), &socket_path, Default::default()) {
+      Ok(_) => {
+        inner.mock_server_started = true;
+        0
+      },
+      Err(err) => {
+        error!("Failed to start mock server - {}", err);
+        -3
+      }
+    }
+  }).unwrap_or(-1)
+}
+
+#[cfg(not(unix))]
+fn start_unix_socket_mock_server(_pact: handles::PactHandle, _socket_path: PathBuf) -> i32 {
+  error!("Unix domain sockets are not supported on this platform");
+  -3
+}
+
+/// External interface to check if a mock server bound to a Unix domain socket has matched all
+/// its requests. False is returned if there is no mock server bound to the given path, or if any
+/// request has not been successfully matched, or the method panics.
+#[no_mangle]
+pub extern fn pactffi_unix_socket_mock_server_matched(socket_path: *const c_char) -> bool {
+  let result = catch_unwind(|| {
+    match convert_cstr("socket_path", socket_path) {
+      Some(path) => pact_mock_server::unix_socket_mock_server_matched(Path::new(path)),
+      None => false
+    }
+  });
+
+  match result {
+    Ok(val) => val,
+    Err(cause) => {
+      error!("Caught a general panic: {:?}", cause);
+      false
+    }
+  }
+}
+
+/// External interface to get all the mismatches from a mock server bound to a Unix domain socket.
+/// A pointer to a C string with the mismatches in JSON format is returned.
+///
+/// **NOTE:** The JSON string for the result is allocated on the heap, and will be freed when the
+/// mock server is cleaned up with `pactffi_cleanup_unix_socket_mock_server`.
+///
+/// # Errors
+///
+/// If there is no mock server bound to the provided path, or the function panics, a NULL
+/// pointer will be returned.
+///
+#[no_mangle]
+pub extern fn pactffi_unix_socket_mock_server_mismatches(socket_path: *const c_char) -> *mut c_char {
+  let result = catch_unwind(|| {
+    let path = match convert_cstr("socket_path", socket_path) {
+      Some(path) => Path::new(path),
+      None => return std::ptr::null_mut()
+    };
+    let result = MANAGER.lock().unwrap()
+      .get_or_insert_with(ServerManager::new)
+      .find_mock_server_by_unix_socket_mut(path, &|ref mut mock_server| {
+        let mismatches = mock_server.mismatches().iter()
+          .map(|mismatch| mismatch.to_json() )
+          .collect::<Vec<serde_json::Value>>();
+        let json = json!(mismatches);
+        let s = CString::new(json.to_string()).unwrap();
+        let p = s.as_ptr();
+        mock_server.resources.push(s);
+        p
+      });
+    match result {
+      Some(p) => p as *mut _,
+      None => std::ptr::null_mut()
+    }
+  });
+
+  match result {
+    Ok(val) => val,
+    Err(cause) => {
+      error!("{}", error_message(cause, "unix_socket_mock_server_mismatches"));
+      std::ptr::null_mut()
+    }
+  }
+}
+
+/// External interface to trigger a mock server bound to a Unix domain socket to write out its
+/// pact file. Behaves the same as `pactffi_write_pact_file`, and returns the same error codes.
+///
+/// # Errors
+///
+/// Errors are returned as positive values.
+///
+/// | Error | Description |
+/// |-------|-------------|
+/// | 1 | A general panic was caught |
+/// | 2 | The pact file was not able to be written |
+/// | 3 | A mock server bound to the provided path was not found |
+#[no_mangle]
+pub extern fn pactffi_write_unix_socket_pact_file(socket_path: *const c_char, directory: *const c_char, overwrite: bool) -> i32 {
+  let result = catch_unwind(|| {
+    let socket_path = match convert_cstr("socket_path", socket_path) {
+      Some(path) => Path::new(path),
+      None => return Err(WritePactFileErr::NoMockServer)
+    };
+    let dir = path_from_dir(directory, None);
+    let path = dir.map(|path| path.into_os_string().into_string().unwrap_or_default());
+
+    pact_mock_server::write_unix_socket_pact_file(socket_path, path, overwrite)
+  });
+
+  match result {
+    Ok(val) => match val {
+      Ok(_) => 0,
+      Err(err) => match err {
+        WritePactFileErr::IOError => 2,
+        WritePactFileErr::NoMockServer => 3
+      }
+    },
+    Err(cause) => {
+      log::error!("Caught a general panic: {:?}", cause);
+      1
+    }
+  }
+}
+
+/// External interface to cleanup a mock server bound to a Unix domain socket. This function will
+/// try terminate the mock server, remove the socket file and cleanup any memory allocated for it.
+/// Returns true, unless a mock server bound to the given path does not exist, or the function
+/// panics.
+#[no_mangle]
+pub extern fn pactffi_cleanup_unix_socket_mock_server(socket_path: *const c_char) -> bool {
+  let result = catch_unwind(|| {
+    match convert_cstr("socket_path", socket_path) {
+      Some(path) => pact_mock_server::shutdown_unix_socket_mock_server(Path::new(path)),
+      None => false
+    }
+  });
+
+  match result {
+    Ok(val) => val,
+    Err(cause) => {
+      log::error!("Caught a general panic: {:?}", cause);
+      false
+    }
+  }
+}
+
 
 /// Fetch the logs for the mock server. This needs the memory buffer log sink to be setup before
 /// the mock server is started. Returned string will be freed with the `cleanup_mock_server`
@@ -0,0 +1,184 @@
+//! Support for HTTP bodies compressed with a content encoding (the `Content-Encoding` header)
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use flate2::Compression;
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+
+use pact_models::bodies::OptionalBody;
+
+/// Content encoding of an HTTP body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+  /// Body is not encoded
+  Identity,
+  /// GZip compressed body
+  Gzip,
+  /// Deflate (zlib) compressed body
+  Deflate,
+  /// Brotli compressed body
+  Brotli
+}
+
+impl ContentEncoding {
+  /// Parses a content encoding from its name in the `Content-Encoding` header
+  pub fn parse(name: &str) -> anyhow::Result<ContentEncoding> {
+    match name.trim().to_lowercase().as_str() {
+      "" | "identity" => Ok(ContentEncoding::Identity),
+      "gzip" | "x-gzip" => Ok(ContentEncoding::Gzip),
+      "deflate" => Ok(ContentEncoding::Deflate),
+      "br" => Ok(ContentEncoding::Brotli),
+      _ => Err(anyhow!("'{}' is not a supported content encoding", name.trim()))
+    }
+  }
+
+  /// Decodes the data that was encoded with this content encoding
+  pub fn decode(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut decoded = vec![];
+    match self {
+      ContentEncoding::Identity => decoded.extend_from_slice(data),
+      ContentEncoding::Gzip => {
+        GzDecoder::new(data).read_to_end(&mut decoded)?;
+      },
+      ContentEncoding::Deflate => {
+        // Some clients send raw deflate data without the zlib wrapper, so fall back to that
+        if ZlibDecoder::new(data).read_to_end(&mut decoded).is_err() {
+          decoded.clear();
+          DeflateDecoder::new(data).read_to_end(&mut decoded)?;
+        }
+      },
+      ContentEncoding::Brotli => {
+        brotli::Decompressor::new(data, 4096).read_to_end(&mut decoded)?;
+      }
+    }
+    Ok(decoded)
+  }
+
+  /// Encodes the data with this content encoding
+  pub fn encode(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    match self {
+      ContentEncoding::Identity => Ok(data.to_vec()),
+      ContentEncoding::Gzip => {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(data)?;
+        Ok(encoder.finish()?)
+      },
+      ContentEncoding::Deflate => {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(data)?;
+        Ok(encoder.finish()?)
+      },
+      ContentEncoding::Brotli => {
+        let mut encoded = vec![];
+        {
+          let mut encoder = brotli::CompressorWriter::new(&mut encoded, 4096, 5, 22);
+          encoder.write_all(data)?;
+        }
+        Ok(encoded)
+      }
+    }
+  }
+}
+
+/// Returns the content encodings from the `Content-Encoding` header, in the order they were
+/// applied to the body. Identity encodings are ignored.
+pub fn content_encodings(headers: &Option<HashMap<String, Vec<String>>>) -> anyhow::Result<Vec<ContentEncoding>> {
+  let mut encodings = vec![];
+  if let Some(headers) = headers {
+    for (_, values) in headers.iter().filter(|(name, _)| name.eq_ignore_ascii_case("content-encoding")) {
+      for name in values.iter().flat_map(|value| value.split(',')) {
+        let encoding = ContentEncoding::parse(name)?;
+        if encoding != ContentEncoding::Identity {
+          encodings.push(encoding);
+        }
+      }
+    }
+  }
+  Ok(encodings)
+}
+
+/// Decodes the body according to the `Content-Encoding` header. Bodies without a content
+/// encoding are returned unchanged.
+pub fn decode_body(body: &OptionalBody, headers: &Option<HashMap<String, Vec<String>>>) -> anyhow::Result<OptionalBody> {
+  match body {
+    OptionalBody::Present(data, content_type) => {
+      let encodings = content_encodings(headers)?;
+      if encodings.is_empty() {
+        return Ok(body.clone());
+      }
+      let mut decoded = data.to_vec();
+      for encoding in encodings.iter().rev() {
+        decoded = encoding.decode(&decoded)?;
+      }
+      Ok(OptionalBody::Present(Bytes::from(decoded), content_type.clone()))
+    },
+    _ => Ok(body.clone())
+  }
+}
+
+/// Encodes the body according to the `Content-Encoding` header. Bodies without a content
+/// encoding are returned unchanged.
+pub fn encode_body(body: &OptionalBody, headers: &Option<HashMap<String, Vec<String>>>) -> anyhow::Result<OptionalBody> {
+  match body {
+    OptionalBody::Present(data, content_type) => {
+      let encodings = content_encodings(headers)?;
+      if encodings.is_empty() {
+        return Ok(body.clone());
+      }
+      let mut encoded = data.to_vec();
+      for encoding in &encodings {
+        encoded = encoding.encode(&encoded)?;
+      }
+      Ok(OptionalBody::Present(Bytes::from(encoded), content_type.clone()))
+    },
+    _ => Ok(body.clone())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::*;
+
+  use super::*;
+
+  #[test]
+  fn content_encoding_round_trip() {
+    let data = "{\"id\": 1, \"name\": \"Fred\"}".as_bytes();
+    for encoding in &[ContentEncoding::Identity, ContentEncoding::Gzip, ContentEncoding::Deflate, ContentEncoding::Brotli] {
+      let encoded = encoding.encode(data).unwrap();
+      expect!(encoding.decode(&encoded).unwrap()).to(be_equal_to(data.to_vec()));
+    }
+  }
+
+  #[test]
+  fn deflate_decodes_raw_deflate_data() {
+    let mut encoder = flate2::write::DeflateEncoder::new(vec![], Compression::default());
+    encoder.write_all(b"raw deflate").unwrap();
+    let encoded = encoder.finish().unwrap();
+    expect!(ContentEncoding::Deflate.decode(&encoded).unwrap()).to(be_equal_to(b"raw deflate".to_vec()));
+  }
+
+  #[test]
+  fn content_encodings_are_taken_from_the_header() {
+    let headers = Some(hashmap!{ "Content-Encoding".to_string() => vec!["gzip".to_string(), "identity, br".to_string()] });
+    expect!(content_encodings(&headers).unwrap()).to(be_equal_to(vec![ContentEncoding::Gzip, ContentEncoding::Brotli]));
+    expect!(content_encodings(&None).unwrap().is_empty()).to(be_true());
+    let headers = Some(hashmap!{ "content-encoding".to_string() => vec!["compress".to_string()] });
+    expect!(content_encodings(&headers)).to(be_err());
+  }
+
+  #[test]
+  fn encode_and_decode_body_apply_the_encodings_in_order() {
+    let headers = Some(hashmap!{ "Content-Encoding".to_string() => vec!["deflate".to_string(), "gzip".to_string()] });
+    let body = OptionalBody::Present(Bytes::from("body"), None);
+    let encoded = encode_body(&body, &headers).unwrap();
+    expect!(encoded.clone()).to_not(be_equal_to(body.clone()));
+    expect!(decode_body(&encoded, &headers).unwrap()).to(be_equal_to(body.clone()));
+    expect!(decode_body(&body, &None).unwrap()).to(be_equal_to(body));
+  }
+}
@@ -16,7 +16,7 @@ use super::*;
 #[test]
 fn returns_original_response_if_there_are_no_generators() {
   let response = Response::default();
-  expect!(generate_response(&response, &GeneratorTestMode::Provider, &hashmap!{})).to(be_equal_to(response));
+  expect!(generate_response(&response, &GeneratorTestMode::Provider, &hashmap!{}, None)).to(be_equal_to(response));
 }
 
 #[test]
@@ -24,7 +24,60 @@ fn applies_status_generator_for_status_to_the_copy_of_the_response() {
   let response = Response { status: 200, generators: generators! {
     "STATUS" => Generator::RandomInt(400, 499)
   }, .. Response::default() };
-  expect!(generate_response(&response, &GeneratorTestMode::Provider, &hashmap!{}).status).to(be_greater_or_equal_to(400));
+  expect!(generate_response(&response, &GeneratorTestMode::Provider, &hashmap!{}, None).status).to(be_greater_or_equal_to(400));
+}
+
+#[test]
+fn applies_the_same_generated_values_to_the_response_when_seeded() {
+  let response = Response { headers: Some(hashmap!{
+      s!("A") => vec![s!("a")]
+    }), generators: generators! {
+      "HEADER" => {
+        "A" => Generator::Uuid(None)
+      }
+    }, .. Response::default()
+  };
+  let first = generate_response(&response, &GeneratorTestMode::Provider, &hashmap!{}, Some(100));
+  let second = generate_response(&response, &GeneratorTestMode::Provider, &hashmap!{}, Some(100));
+  expect!(first.headers.clone().unwrap().get("A").unwrap().first().unwrap()).to_not(be_equal_to("a"));
+  expect!(first).to(be_equal_to(second));
+}
+
+#[test]
+fn applies_the_same_generated_values_to_each_field_of_the_response_when_seeded() {
+  // Each response has its own hash maps, so the generators are not iterated in the same order
+  let response = || Response {
+    headers: Some(hashmap!{
+      s!("A") => vec![s!("a")],
+      s!("B") => vec![s!("b")],
+      s!("C") => vec![s!("c")]
+    }),
+    body: OptionalBody::Present("{\"a\": 1, \"b\": 2, \"c\": 3, \"d\": 4, \"e\": 5, \"f\": 6}".into(), None),
+    generators: generators! {
+      "HEADER" => {
+        "A" => Generator::RandomInt(0, 1000000),
+        "B" => Generator::RandomInt(0, 1000000),
+        "C" => Generator::RandomInt(0, 1000000)
+      },
+      "BODY" => {
+        "$.a" => Generator::RandomInt(0, 1000000),
+        "$.b" => Generator::RandomInt(0, 1000000),
+        "$.c" => Generator::RandomInt(0, 1000000),
+        "$.d" => Generator::RandomInt(0, 1000000),
+        "$.e" => Generator::RandomInt(0, 1000000),
+        "$.f" => Generator::RandomInt(0, 1000000)
+      }
+    },
+    .. Response::default()
+  };
+  let first = generate_response(&response(), &GeneratorTestMode::Provider, &hashmap!{}, Some(100));
+  for _ in 0..10 {
+    let next = generate_response(&response(), &GeneratorTestMode::Provider, &hashmap!{}, Some(100));
+    expect!(next.headers).to(be_equal_to(first.headers.clone()));
+    let body: Value = serde_json::from_slice(&next.body.value().unwrap()).unwrap();
+    let first_body: Value = serde_json::from_slice(&first.body.value().unwrap()).unwrap();
+    expect!(body).to(be_equal_to(first_body));
+  }
 }
 
 #[test]
@@ -38,14 +91,14 @@ fn applies_header_generator_for_headers_to_the_copy_of_the_response() {
       }
     }, .. Response::default()
   };
-  let headers = generate_response(&response, &GeneratorTestMode::Provider, &hashmap!{}).headers.unwrap().clone();
+  let headers = generate_response(&response, &GeneratorTestMode::Provider, &hashmap!{}, None).headers.unwrap().clone();
   expect!(headers.get("A").unwrap().first().unwrap()).to_not(be_equal_to("a"));
 }
 
 #[test]
 fn returns_original_request_if_there_are_no_generators() {
   let request = Request::default();
-  expect!(generate_request(&request, &GeneratorTestMode::Provider, &hashmap!{})).to(be_equal_to(request));
+  expect!(generate_request(&request, &GeneratorTestMode::Provider, &hashmap!{}, None)).to(be_equal_to(request));
 }
 
 #[test]
@@ -53,7 +106,7 @@ fn applies_path_generator_for_the_path_to_the_copy_of_the_request() {
   let request = Request { path: s!("/path"), generators: generators! {
     "PATH" => Generator::RandomInt(1, 10)
   }, .. Request::default() };
-  expect!(generate_request(&request, &GeneratorTestMode::Provider, &hashmap!{}).path).to_not(be_equal_to("/path"));
+  expect!(generate_request(&request, &GeneratorTestMode::Provider, &hashmap!{}, None).path).to_not(be_equal_to("/path"));
 }
 
 #[test]
@@ -67,7 +120,7 @@ fn applies_header_generator_for_headers_to_the_copy_of_the_request() {
       }
     }, .. Request::default()
   };
-  let headers = generate_request(&request, &GeneratorTestMode::Provider, &hashmap!{}).headers.unwrap().clone();
+  let headers = generate_request(&request, &GeneratorTestMode::Provider, &hashmap!{}, None).headers.unwrap().clone();
   expect!(headers.get("A").unwrap().first().unwrap()).to_not(be_equal_to("a"));
 }
 
@@ -82,7 +135,7 @@ fn applies_query_generator_for_query_parameters_to_the_copy_of_the_request() {
       }
     }, .. Request::default()
   };
-  let query = generate_request(&request, &GeneratorTestMode::Provider, &hashmap!{}).query.unwrap().clone();
+  let query = generate_request(&request, &GeneratorTestMode::Provider, &hashmap!{}, None).query.unwrap().clone();
   let query_val = &query.get("A").unwrap()[0];
   expect!(query_val).to_not(be_equal_to("a"));
 }
@@ -121,7 +174,7 @@ fn applies_body_generator_to_the_copy_of_the_request() {
       }
     }, .. Request::default()
   };
-  let generated_request = generate_request(&request, &GeneratorTestMode::Provider, &hashmap!{});
+  let generated_request = generate_request(&request, &GeneratorTestMode::Provider, &hashmap!{}, None);
   let body: Value = serde_json::from_str(generated_request.body.str_value()).unwrap();
   expect!(&body["a"]).to_not(be_equal_to(&json!(100)));
   expect!(&body["b"]).to(be_equal_to(&json!("B")));
@@ -136,7 +189,7 @@ fn applies_body_generator_to_the_copy_of_the_response() {
       }
     }, .. Response::default()
   };
-  let body: Value = serde_json::from_str(generate_response(&response, &GeneratorTestMode::Provider, &hashmap!{}).body.str_value()).unwrap();
+  let body: Value = serde_json::from_str(generate_response(&response, &GeneratorTestMode::Provider, &hashmap!{}, None).body.str_value()).unwrap();
   expect!(&body["a"]).to_not(be_equal_to(&json!(100)));
   expect!(&body["b"]).to(be_equal_to(&json!("B")));
 }
@@ -5,6 +5,7 @@ use std::iter::FromIterator;
 
 use maplit::*;
 
+use pact_models::headers::{is_list_valued_header, parse_header};
 use pact_models::matchingrules::MatchingRule;
 
 use crate::{matchers, MatchingContext, Mismatch};
@@ -87,13 +88,26 @@ fn find_entry<T>(map: &HashMap<String, T>, key: &String) -> Option<(String, T)>
   }
 }
 
+// Header values are kept as they are written or received. The values of list-valued headers can be
+// sent as one comma separated value or as several headers, so they are split before comparing them.
+fn header_values(key: &str, values: &[String]) -> Vec<String> {
+  if is_list_valued_header(key) {
+    values.iter().flat_map(|value| parse_header(key, value)).collect()
+  } else {
+    values.to_vec()
+  }
+}
+
 fn match_header_maps(expected: HashMap<String, Vec<String>>, actual: HashMap<String, Vec<String>>, context: &MatchingContext) -> HashMap<String, Vec<Mismatch>> {
   let mut result = hashmap!{};
   for (key, value) in &expected {
     match find_entry(&actual, key) {
-      Some((_, actual_value)) => for (index, val) in value.iter().enumerate() {
-        result.insert(key.clone(), match_header_value(key, val,
-                                                      actual_value.get(index).unwrap_or(&String::default()), context).err().unwrap_or_default());
+      Some((_, actual_value)) => {
+        let actual_value = header_values(key, &actual_value);
+        for (index, val) in header_values(key, value).iter().enumerate() {
+          result.insert(key.clone(), match_header_value(key, val,
+                                                        actual_value.get(index).unwrap_or(&String::default()), context).err().unwrap_or_default());
+        }
       },
       None => {
         result.insert(key.clone(), vec![Mismatch::HeaderMismatch { key: key.clone(),
@@ -334,4 +348,30 @@ mod tests {
       mismatch: s!(""),
     } ]));
   }
+
+  #[test]
+  fn match_headers_splits_the_values_of_list_valued_headers() {
+    let expected = hashmap! {
+      s!("Accept") => vec![s!("application/json, text/plain")],
+      s!("Date") => vec![s!("Wed, 21 Oct 2015 07:28:00 GMT")]
+    };
+    let actual = hashmap! {
+      s!("accept") => vec![s!("application/json"), s!("text/plain")],
+      s!("date") => vec![s!("Wed, 21 Oct 2015 07:28:00 GMT")]
+    };
+    let result = match_headers(Some(expected), Some(actual), &MatchingContext::default());
+    expect!(result.values().flatten()).to(be_empty());
+  }
+
+  #[test]
+  fn match_headers_does_not_split_the_values_of_other_headers() {
+    let expected = hashmap! {
+      s!("Set-Cookie") => vec![s!("a=1; Expires=Wed, 21 Oct 2015 07:28:00 GMT")]
+    };
+    let actual = hashmap! {
+      s!("set-cookie") => vec![s!("a=1; Expires=Wed"), s!("21 Oct 2015 07:28:00 GMT")]
+    };
+    let result = match_headers(Some(expected), Some(actual), &MatchingContext::default());
+    expect!(result.values().flatten()).to_not(be_empty());
+  }
 }
@@ -351,7 +351,14 @@ use serde_json::{json, Value};
 
 use pact_models::bodies::OptionalBody;
 use pact_models::content_types::ContentType;
-use pact_models::generators::{apply_generators, GenerateValue, GeneratorCategory, GeneratorTestMode, VariantMatcher};
+use pact_models::generators::{
+  apply_generators,
+  GenerateValue,
+  GeneratorCategory,
+  GeneratorTestMode,
+  VariantMatcher,
+  with_generator_seed
+};
 use pact_models::http_parts::HttpPart;
 use pact_models::json_utils::json_to_string;
 use pact_models::matchingrules::{calc_path_weight, Category, MatchingRule, MatchingRuleCategory, path_length, RuleList};
@@ -378,6 +385,7 @@ mod xml;
 mod binary_utils;
 mod headers;
 pub mod logging;
+pub mod content_encoding;
 
 #[derive(Debug, Clone)]
 /// Context used to apply matching logic
@@ -1496,8 +1504,18 @@ pub fn match_message(expected: &Box<dyn Interaction + Send>, actual: &Box<dyn In
   mismatches
 }
 
-/// Generates the request by applying any defined generators
-pub fn generate_request(request: &Request, mode: &GeneratorTestMode, context: &HashMap<&str, Value>) -> Request {
+/// Generates the request by applying any defined generators. If a seed is given, the generators
+/// will generate the same values each time.
+pub fn generate_request(
+  request: &Request,
+  mode: &GeneratorTestMode,
+  context: &HashMap<&str, Value>,
+  seed: Option<u64>
+) -> Request {
+  with_generator_seed(seed, || apply_request_generators(request, mode, context))
+}
+
+fn apply_request_generators(request: &Request, mode: &GeneratorTestMode, context: &HashMap<&str, Value>) -> Request {
   let mut request = request.clone();
 
   let generators = request.build_generators(&GeneratorCategory::PATH);
@@ -1552,8 +1570,18 @@ pub fn generate_request(request: &Request, mode: &GeneratorTestMode, context: &H
   request
 }
 
-/// Generates the response by applying any defined generators
-pub fn generate_response(response: &Response, mode: &GeneratorTestMode, context: &HashMap<&str, Value>) -> Response {
+/// Generates the response by applying any defined generators. If a seed is given, the generators
+/// will generate the same values each time.
+pub fn generate_response(
+  response: &Response,
+  mode: &GeneratorTestMode,
+  context: &HashMap<&str, Value>,
+  seed: Option<u64>
+) -> Response {
+  with_generator_seed(seed, || apply_response_generators(response, mode, context))
+}
+
+fn apply_response_generators(response: &Response, mode: &GeneratorTestMode, context: &HashMap<&str, Value>) -> Response {
   let mut response = response.clone();
   let generators = response.build_generators(&GeneratorCategory::STATUS);
   if !generators.is_empty() {
@@ -0,0 +1,254 @@
+//!
+//! The aggregator module collects the interactions from all the mock servers of a session that
+//! are for the same consumer and provider, so that one pact file can be written for each pair.
+//!
+//! The consumer tests for the same consumer and provider can be split over several processes
+//! (i.e. test binaries). Each process writes its interactions under an owner (by default, the name
+//! of the test binary), which is recorded in the pact file metadata. When the pact file is written,
+//! only the interactions that the same owner wrote before are replaced, and the interactions of
+//! other owners are kept.
+//!
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use log::*;
+use serde_json::{json, Value};
+
+use pact_matching::models::{load_pact_from_json, Pact, RequestResponsePact};
+use pact_matching::models::v4::{V4Interaction, V4Pact};
+use pact_models::PactSpecification;
+use pact_models::file_utils::with_write_lock;
+use pact_models::v4::V4InteractionType;
+
+use crate::matching::interaction_key;
+use crate::mock_server::MockServer;
+
+/// Metadata key used to record the interactions written by each owner in the pact file
+const OWNERS_METADATA_KEY: &str = "pactRustAggregator";
+
+/// Interactions are identified by their description and provider states
+type AggregationKey = (String, Vec<String>);
+
+fn aggregation_key(interaction: &dyn V4Interaction) -> AggregationKey {
+  let states = interaction.provider_states().iter().map(|state| state.name.clone()).collect();
+  (interaction.description(), states)
+}
+
+/// Interactions collected for one consumer and provider
+#[derive(Debug, Clone)]
+struct AggregatedPact {
+  /// Pact of the first mock server for the pair. Only its consumer, provider and metadata are used.
+  pact: V4Pact,
+  /// Highest specification version of the collected pacts
+  specification: PactSpecification,
+  /// Collected interactions, keyed by their description and provider states
+  interactions: BTreeMap<AggregationKey, Box<dyn V4Interaction>>
+}
+
+impl AggregatedPact {
+  fn to_pact(&self) -> Result<Box<dyn Pact + Send>, String> {
+    self.pact_with(self.interactions.values().cloned().collect(), self.specification)
+  }
+
+  fn pact_with(
+    &self,
+    interactions: Vec<Box<dyn V4Interaction>>,
+    specification: PactSpecification
+  ) -> Result<Box<dyn Pact + Send>, String> {
+    let pact = V4Pact { interactions, .. self.pact.clone() };
+    if specification >= PactSpecification::V4 {
+      Ok(pact.boxed())
+    } else {
+      let pact = pact.as_request_response_pact()
+        .map_err(|err| format!("Could not convert the pact into V3 format - {}", err))?;
+      Ok(RequestResponsePact { specification_version: specification, .. pact }.boxed())
+    }
+  }
+
+  /// Merges the collected interactions with the contents of an existing pact file, and returns
+  /// the JSON to write out. The interactions the owner wrote before are replaced with the collected
+  /// ones, and the interactions of other owners are kept.
+  fn merge(&self, path: &Path, owner: &str, existing: Option<Value>) -> anyhow::Result<Value> {
+    let mut owners = existing.as_ref()
+      .and_then(|json| json.pointer(&format!("/metadata/{}", OWNERS_METADATA_KEY)))
+      .map(owners_from_json)
+      .unwrap_or_default();
+    let previous = owners.remove(owner).unwrap_or_default();
+
+    let mut interactions = BTreeMap::new();
+    let mut specification = self.specification;
+    if let Some(json) = existing {
+      let pact = load_pact_from_json(&path.to_string_lossy(), &json)?;
+      if pact.specification_version() > specification {
+        specification = pact.specification_version();
+      }
+      for interaction in pact.as_v4_pact()?.interactions {
+        let key = aggregation_key(interaction.as_ref());
+        if previous.contains(&key) {
+          debug!("Removing interaction '{}' previously written by '{}'", key.0, owner);
+        } else {
+          interactions.insert(key, interaction);
+        }
+      }
+    }
+    for (key, interaction) in &self.interactions {
+      interactions.insert(key.clone(), interaction.clone());
+    }
+
+    for keys in owners.values_mut() {
+      keys.retain(|key| !self.interactions.contains_key(key) && interactions.contains_key(key));
+    }
+    owners.retain(|_, keys| !keys.is_empty());
+    if !self.interactions.is_empty() {
+      owners.insert(owner.to_string(), self.interactions.keys().cloned().collect());
+    }
+
+    let pact = self.pact_with(interactions.into_iter().map(|(_, i)| i).collect(), specification)
+      .map_err(|err| anyhow::anyhow!(err))?;
+    let mut json = pact.to_json(specification)?;
+    if let Some(metadata) = json.get_mut("metadata").and_then(|metadata| metadata.as_object_mut()) {
+      metadata.insert(OWNERS_METADATA_KEY.to_string(), owners_to_json(&owners));
+    }
+    Ok(json)
+  }
+}
+
+fn owners_from_json(json: &Value) -> BTreeMap<String, BTreeSet<AggregationKey>> {
+  json.as_object().map(|owners| owners.iter().map(|(owner, keys)| {
+    let keys = keys.as_array().map(|keys| keys.iter().filter_map(|key| {
+      let description = key.get("description")?.as_str()?.to_string();
+      let states = key.get("providerStates").and_then(|states| states.as_array())
+        .map(|states| states.iter().filter_map(|state| state.as_str().map(|s| s.to_string())).collect())
+        .unwrap_or_default();
+      Some((description, states))
+    }).collect()).unwrap_or_default();
+    (owner.clone(), keys)
+  }).collect()).unwrap_or_default()
+}
+
+fn owners_to_json(owners: &BTreeMap<String, BTreeSet<AggregationKey>>) -> Value {
+  Value::Object(owners.iter().map(|(owner, keys)| {
+    (owner.clone(), Value::Array(keys.iter()
+      .map(|(description, states)| json!({ "description": description, "providerStates": states }))
+      .collect()))
+  }).collect())
+}
+
+/// The default owner of the written interactions, which is the name of the current executable.
+/// The hash that Cargo appends to the names of test binaries is removed, so that it stays the same
+/// when the binary is rebuilt.
+pub fn default_owner() -> String {
+  let name = std::env::current_exe().ok()
+    .and_then(|path| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+    .unwrap_or_else(|| "pact_mock_server".to_string());
+  match name.rsplit_once('-') {
+    Some((name, hash)) if hash.len() == 16 && hash.chars().all(|ch| ch.is_ascii_hexdigit()) => name.to_string(),
+    _ => name
+  }
+}
+
+/// Collects the interactions exercised by mock servers, grouped by consumer and provider.
+///
+/// Only HTTP interactions that received at least one matching request are collected, so
+/// interactions that were not exercised are not written out. Interactions are ordered by their
+/// description and provider states, so the same session always results in the same pact file.
+#[derive(Debug, Default, Clone)]
+pub struct PactAggregator {
+  pacts: BTreeMap<(String, String), AggregatedPact>
+}
+
+impl PactAggregator {
+  /// Creates an empty aggregator
+  pub fn new() -> PactAggregator {
+    PactAggregator::default()
+  }
+
+  /// Collects the interactions of the mock server. Interactions with the same description and
+  /// provider states as already collected ones replace them. Interactions that are not HTTP
+  /// interactions are always collected, as the mock server can not exercise them.
+  pub fn add_mock_server(&mut self, mock_server: &MockServer) -> Result<(), String> {
+    let exercised = mock_server.journal().iter()
+      .filter(|entry| entry.matched())
+      .filter_map(|entry| entry.interaction_key.clone())
+      .collect::<HashSet<String>>();
+    let (v4_pact, specification) = {
+      let pact = mock_server.pact.lock().unwrap();
+      let v4_pact = pact.as_v4_pact()
+        .map_err(|err| format!("Could not convert the pact into V4 format - {}", err))?;
+      (v4_pact, pact.specification_version())
+    };
+
+    let key = (v4_pact.consumer.name.clone(), v4_pact.provider.name.clone());
+    let aggregated = self.pacts.entry(key).or_insert_with(|| AggregatedPact {
+      pact: V4Pact { interactions: vec![], .. v4_pact.clone() },
+      specification,
+      interactions: BTreeMap::new()
+    });
+    if specification > aggregated.specification {
+      aggregated.specification = specification;
+    }
+
+    for interaction in &v4_pact.interactions {
+      let collect = interaction.v4_type() != V4InteractionType::Synchronous_HTTP ||
+        interaction_key(interaction.to_super()).map(|key| exercised.contains(&key)).unwrap_or(false);
+      if collect {
+        aggregated.interactions.insert(aggregation_key(interaction.as_ref()), interaction.clone());
+      } else {
+        debug!("Mock server {} - not collecting interaction '{}' as it was not exercised",
+          mock_server.id, interaction.description());
+      }
+    }
+    Ok(())
+  }
+
+  /// Returns the aggregated pacts, ordered by consumer and provider
+  pub fn pacts(&self) -> Result<Vec<Box<dyn Pact + Send>>, String> {
+    self.pacts.values().map(|pact| pact.to_pact()).collect()
+  }
+
+  /// Writes out a pact file for each consumer and provider to the output directory. If `None`
+  /// is passed in, the current working directory is used. The interactions written before by the
+  /// same owner are replaced, so interactions from previous sessions are removed, while the
+  /// interactions written by other owners are kept. If no owner is given, the default owner is used
+  /// (see `default_owner`). The pact files are locked while they are updated. Returns the paths of
+  /// the files written.
+  pub fn write_pacts(&self, output_path: &Option<String>, owner: &Option<String>) -> anyhow::Result<Vec<PathBuf>> {
+    let owner = owner.clone().unwrap_or_else(default_owner);
+    let mut files = vec![];
+    for aggregated in self.pacts.values() {
+      let pact = aggregated.to_pact().map_err(|err| anyhow::anyhow!(err))?;
+      let mut filename = output_path.as_ref().map(PathBuf::from).unwrap_or_default();
+      filename.push(pact.default_file_name());
+
+      info!("Writing aggregated pact for '{}' out to '{}'", owner, filename.display());
+      if let Some(parent) = filename.parent() {
+        fs::create_dir_all(parent)?;
+      }
+      let mut file = OpenOptions::new().read(true).write(true).create(true).open(&filename)?;
+      with_write_lock(&filename, &mut file, 3, &mut |file| {
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        let existing = if contents.trim().is_empty() {
+          None
+        } else {
+          Some(serde_json::from_str(&contents)?)
+        };
+        let json = aggregated.merge(&filename, &owner, existing)?;
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(serde_json::to_string_pretty(&json)?.as_bytes())?;
+        Ok(())
+      })?;
+      files.push(filename);
+    }
+    Ok(files)
+  }
+
+  /// Removes all the collected interactions
+  pub fn clear(&mut self) {
+    self.pacts.clear();
+  }
+}
@@ -0,0 +1,213 @@
+//!
+//! The cors module defines the CORS policy of the mock server, which controls the CORS headers
+//! that are added to the responses and how CORS pre-flight requests are responded to.
+//!
+
+use std::collections::HashMap;
+
+use pact_models::request::Request;
+
+/// Methods that are allowed by default
+const CORS_ALLOWED_METHODS: [&str; 9] = [
+  "GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH"
+];
+
+/// CORS policy for the mock server
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorsPolicy {
+  /// If CORS headers should be added to the responses at all
+  pub enabled: bool,
+  /// Origins that are allowed to make requests. An empty list allows any origin.
+  pub allowed_origins: Vec<String>,
+  /// Methods to return in pre-flight responses
+  pub allowed_methods: Vec<String>,
+  /// If credentialed requests are allowed. The origin of the request is then returned instead
+  /// of the `*` wildcard, as browsers reject the wildcard for credentialed requests.
+  pub allow_credentials: bool,
+  /// Response headers that the browser is allowed to expose
+  pub exposed_headers: Vec<String>,
+  /// Number of seconds the pre-flight response can be cached for
+  pub max_age: Option<u64>
+}
+
+impl Default for CorsPolicy {
+  fn default() -> Self {
+    CorsPolicy {
+      enabled: true,
+      allowed_origins: vec![],
+      allowed_methods: CORS_ALLOWED_METHODS.iter().map(|method| method.to_string()).collect(),
+      allow_credentials: false,
+      exposed_headers: vec!["Location".to_string(), "Link".to_string()],
+      max_age: None
+    }
+  }
+}
+
+impl CorsPolicy {
+  /// Policy that does not add any CORS headers to the responses
+  pub fn disabled() -> Self {
+    CorsPolicy {
+      enabled: false,
+      .. CorsPolicy::default()
+    }
+  }
+
+  /// Returns the value for the `Access-Control-Allow-Origin` header for the request. `None` is
+  /// returned if the origin of the request is not allowed.
+  pub fn allowed_origin(&self, request: &Request) -> Option<String> {
+    let origin = request_header(request, "origin");
+    if self.allowed_origins.is_empty() {
+      if self.allow_credentials {
+        origin
+      } else {
+        Some("*".to_string())
+      }
+    } else {
+      origin.filter(|origin| self.allowed_origins.contains(origin))
+    }
+  }
+
+  /// CORS headers to add to the response for the request
+  pub fn response_headers(&self, request: &Request) -> HashMap<String, Vec<String>> {
+    let mut headers = HashMap::new();
+    if !self.enabled {
+      return headers;
+    }
+
+    if let Some(origin) = self.allowed_origin(request) {
+      if origin != "*" {
+        headers.insert("Vary".to_string(), vec!["Origin".to_string()]);
+      }
+      headers.insert("Access-Control-Allow-Origin".to_string(), vec![origin]);
+      if self.allow_credentials {
+        headers.insert("Access-Control-Allow-Credentials".to_string(), vec!["true".to_string()]);
+      }
+      if !self.exposed_headers.is_empty() {
+        headers.insert("Access-Control-Expose-Headers".to_string(), vec![self.exposed_headers.join(", ")]);
+      }
+    }
+    headers
+  }
+
+  /// CORS headers to add to the response for a pre-flight request
+  pub fn preflight_headers(&self, request: &Request) -> HashMap<String, Vec<String>> {
+    let mut headers = self.response_headers(request);
+    if !headers.contains_key("Access-Control-Allow-Origin") {
+      return headers;
+    }
+
+    headers.insert("Access-Control-Allow-Methods".to_string(), vec![self.allowed_methods.join(", ")]);
+    let allowed_headers = match request_header(request, "access-control-request-headers") {
+      Some(requested) if self.allow_credentials => Some(requested),
+      Some(requested) => Some(requested + ", *"),
+      None if self.allow_credentials => None,
+      None => Some("*".to_string())
+    };
+    if let Some(allowed_headers) = allowed_headers {
+      headers.insert("Access-Control-Allow-Headers".to_string(), vec![allowed_headers]);
+    }
+    if let Some(max_age) = self.max_age {
+      headers.insert("Access-Control-Max-Age".to_string(), vec![max_age.to_string()]);
+    }
+    headers
+  }
+}
+
+/// Adds the CORS headers to the response headers. Headers already defined for the response are
+/// never overridden.
+pub(crate) fn merge_cors_headers(
+  cors_headers: HashMap<String, Vec<String>>,
+  response_headers: &Option<HashMap<String, Vec<String>>>
+) -> Option<HashMap<String, Vec<String>>> {
+  let mut headers = response_headers.clone().unwrap_or_default();
+  for (name, values) in cors_headers {
+    if !headers.keys().any(|key| key.eq_ignore_ascii_case(name.as_str())) {
+      headers.insert(name, values);
+    }
+  }
+  if headers.is_empty() { None } else { Some(headers) }
+}
+
+fn request_header(request: &Request, name: &str) -> Option<String> {
+  request.headers.as_ref().and_then(|headers| headers.iter()
+    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+    .map(|(_, values)| values.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::*;
+
+  use super::*;
+
+  fn request_from(origin: &str) -> Request {
+    Request {
+      headers: Some(hashmap!{ "Origin".to_string() => vec![origin.to_string()] }),
+      .. Request::default()
+    }
+  }
+
+  #[test]
+  fn default_policy_allows_any_origin_with_the_wildcard() {
+    let headers = CorsPolicy::default().response_headers(&request_from("http://localhost:8080"));
+    expect!(headers.get("Access-Control-Allow-Origin")).to(be_some().value(&vec!["*".to_string()]));
+    expect!(headers.get("Access-Control-Allow-Credentials")).to(be_none());
+    expect!(headers.get("Vary")).to(be_none());
+  }
+
+  #[test]
+  fn credentialed_policy_echoes_the_request_origin() {
+    let policy = CorsPolicy {
+      allow_credentials: true,
+      .. CorsPolicy::default()
+    };
+    let headers = policy.response_headers(&request_from("http://localhost:8080"));
+    expect!(headers.get("Access-Control-Allow-Origin")).to(be_some().value(&vec!["http://localhost:8080".to_string()]));
+    expect!(headers.get("Access-Control-Allow-Credentials")).to(be_some().value(&vec!["true".to_string()]));
+    expect!(headers.get("Vary")).to(be_some().value(&vec!["Origin".to_string()]));
+  }
+
+  #[test]
+  fn policy_with_allowed_origins_rejects_other_origins() {
+    let policy = CorsPolicy {
+      allowed_origins: vec!["http://localhost:8080".to_string()],
+      .. CorsPolicy::default()
+    };
+    expect!(policy.allowed_origin(&request_from("http://localhost:8080"))).to(be_some().value("http://localhost:8080".to_string()));
+    expect!(policy.response_headers(&request_from("http://evil.example")).is_empty()).to(be_true());
+  }
+
+  #[test]
+  fn disabled_policy_adds_no_headers() {
+    let policy = CorsPolicy::disabled();
+    expect!(policy.response_headers(&request_from("http://localhost:8080")).is_empty()).to(be_true());
+    expect!(policy.preflight_headers(&request_from("http://localhost:8080")).is_empty()).to(be_true());
+  }
+
+  #[test]
+  fn preflight_headers_include_the_max_age() {
+    let policy = CorsPolicy {
+      max_age: Some(600),
+      .. CorsPolicy::default()
+    };
+    let headers = policy.preflight_headers(&request_from("http://localhost:8080"));
+    expect!(headers.get("Access-Control-Max-Age")).to(be_some().value(&vec!["600".to_string()]));
+    expect!(headers.get("Access-Control-Allow-Headers")).to(be_some().value(&vec!["*".to_string()]));
+  }
+
+  #[test]
+  fn merge_cors_headers_does_not_override_the_response_headers() {
+    let cors_headers = hashmap!{
+      "Access-Control-Allow-Origin".to_string() => vec!["*".to_string()],
+      "Vary".to_string() => vec!["Origin".to_string()]
+    };
+    let response_headers = Some(hashmap!{
+      "access-control-allow-origin".to_string() => vec!["http://localhost:8080".to_string()]
+    });
+    let headers = merge_cors_headers(cors_headers, &response_headers).unwrap();
+    expect!(headers.len()).to(be_equal_to(2));
+    expect!(headers.get("access-control-allow-origin")).to(be_some().value(&vec!["http://localhost:8080".to_string()]));
+    expect!(headers.get("Access-Control-Allow-Origin")).to(be_none());
+  }
+}
@@ -0,0 +1,195 @@
+//!
+//! The faults module defines the latency and faults that can be injected into the responses
+//! of the mock server, to be able to test client timeouts and retry logic.
+//!
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::stream::{self, Stream, StreamExt};
+use hyper::Body;
+use rand::Rng;
+
+use pact_models::bodies::OptionalBody;
+use pact_models::response::Response;
+
+use crate::streaming::StreamedBody;
+
+/// Delay to apply before a response is sent
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResponseDelay {
+  /// Fixed delay
+  Fixed(Duration),
+  /// Random delay between the minimum and maximum values (inclusive)
+  Random(Duration, Duration)
+}
+
+impl ResponseDelay {
+  /// Returns the actual duration of the delay to apply
+  pub fn duration(&self) -> Duration {
+    match self {
+      ResponseDelay::Fixed(delay) => *delay,
+      ResponseDelay::Random(min, max) => if min < max {
+        rand::thread_rng().gen_range(*min..=*max)
+      } else {
+        *min
+      }
+    }
+  }
+}
+
+impl FromStr for ResponseDelay {
+  type Err = String;
+
+  /// Parses a delay in milliseconds, either as a fixed value (`500`) or a range (`100-500`)
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let parse_millis = |v: &str| v.trim().parse::<u64>()
+      .map(Duration::from_millis)
+      .map_err(|err| format!("'{}' is not a valid delay in milliseconds - {}", v, err));
+    match s.split_once('-') {
+      Some((min, max)) => {
+        let min = parse_millis(min)?;
+        let max = parse_millis(max)?;
+        if min <= max {
+          Ok(ResponseDelay::Random(min, max))
+        } else {
+          Err(format!("'{}' is not a valid delay range, the minimum is greater than the maximum", s))
+        }
+      },
+      None => parse_millis(s).map(ResponseDelay::Fixed)
+    }
+  }
+}
+
+/// Settings for sending the response body slowly, in chunks
+#[derive(Debug, Clone, PartialEq)]
+pub struct DripFeed {
+  /// Number of bytes to send in each chunk
+  pub chunk_size: usize,
+  /// Time to wait before sending each chunk
+  pub interval: Duration
+}
+
+impl Default for DripFeed {
+  fn default() -> Self {
+    DripFeed {
+      chunk_size: 1,
+      interval: Duration::from_millis(100)
+    }
+  }
+}
+
+/// Faults to inject into the responses of the mock server
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FaultConfig {
+  /// Delay before the response is sent
+  pub delay: Option<ResponseDelay>,
+  /// Send the response body slowly, in chunks
+  pub drip_feed: Option<DripFeed>,
+  /// Abruptly reset the connection instead of sending a response
+  pub reset_connection: bool,
+  /// Truncate the response body to this number of bytes
+  pub truncate_body: Option<usize>,
+  /// Override the status of the response
+  pub status: Option<u16>
+}
+
+impl FaultConfig {
+  /// Applies the status override and body truncation to the response
+  pub fn apply(&self, response: &Response) -> Response {
+    let mut response = response.clone();
+    if let Some(status) = self.status {
+      response.status = status;
+    }
+    if let Some(length) = self.truncate_body {
+      if let OptionalBody::Present(body, content_type) = &response.body {
+        if body.len() > length {
+          response.body = OptionalBody::Present(body.slice(0..length), content_type.clone());
+        }
+      }
+    }
+    response
+  }
+
+  /// Creates the body to send back for the response, drip-feeding it if required
+  pub fn body(&self, body: &OptionalBody) -> Body {
+    match body {
+      OptionalBody::Present(bytes, _) => match &self.drip_feed {
+        Some(drip_feed) => Body::wrap_stream(drip_feed_stream(bytes.clone(), drip_feed.clone())),
+        None => Body::from(bytes.clone())
+      },
+      _ => Body::empty()
+    }
+  }
+
+  /// Creates the body to send back for a streamed response, truncating it and drip-feeding its
+  /// chunks if required
+  pub fn streamed_body(&self, streamed_body: &StreamedBody) -> Body {
+    let streamed_body = match self.truncate_body {
+      Some(length) => streamed_body.truncated(length),
+      None => streamed_body.clone()
+    };
+    match &self.drip_feed {
+      Some(drip_feed) => streamed_body.rechunked(drip_feed.chunk_size, drip_feed.interval).body(),
+      None => streamed_body.body()
+    }
+  }
+}
+
+fn drip_feed_stream(bytes: Bytes, drip_feed: DripFeed) -> impl Stream<Item = Result<Bytes, std::io::Error>> {
+  let chunk_size = drip_feed.chunk_size.max(1);
+  let chunks = (0..bytes.len()).step_by(chunk_size)
+    .map(|start| bytes.slice(start..(start + chunk_size).min(bytes.len())))
+    .collect::<Vec<Bytes>>();
+  stream::iter(chunks).then(move |chunk| {
+    let interval = drip_feed.interval;
+    async move {
+      tokio::time::sleep(interval).await;
+      Ok(chunk)
+    }
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use super::*;
+
+  #[test]
+  fn parse_response_delay() {
+    expect!("500".parse::<ResponseDelay>()).to(be_ok().value(ResponseDelay::Fixed(Duration::from_millis(500))));
+    expect!("100-500".parse::<ResponseDelay>()).to(be_ok().value(
+      ResponseDelay::Random(Duration::from_millis(100), Duration::from_millis(500))));
+    expect!("500-100".parse::<ResponseDelay>()).to(be_err());
+    expect!("soon".parse::<ResponseDelay>()).to(be_err());
+  }
+
+  #[test]
+  fn random_delay_is_within_the_range() {
+    let delay = ResponseDelay::Random(Duration::from_millis(100), Duration::from_millis(200));
+    for _ in 0..10 {
+      let duration = delay.duration();
+      expect!(duration >= Duration::from_millis(100) && duration <= Duration::from_millis(200)).to(be_true());
+    }
+  }
+
+  #[test]
+  fn apply_overrides_the_status_and_truncates_the_body() {
+    let response = Response {
+      status: 200,
+      body: OptionalBody::Present("0123456789".into(), None),
+      .. Response::default()
+    };
+    let faults = FaultConfig {
+      status: Some(503),
+      truncate_body: Some(4),
+      .. FaultConfig::default()
+    };
+
+    let result = faults.apply(&response);
+    expect!(result.status).to(be_equal_to(503));
+    expect!(result.body.str_value()).to(be_equal_to("0123"));
+  }
+}
@@ -0,0 +1,160 @@
+//!
+//! The handle module provides an owned handle to a running mock server. Mock servers started
+//! with a handle run on the tokio runtime of the caller, and are not registered with the global
+//! server manager.
+//!
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use log::*;
+use rustls::ServerConfig;
+use uuid::Uuid;
+
+use pact_matching::models::Pact;
+
+use crate::journal::RequestJournalEntry;
+use crate::matching::MatchResult;
+use crate::mock_server::{MockServer, MockServerConfig};
+
+/// Handle to a running mock server. The mock server is shut down when the handle is dropped, but
+/// `shutdown` should be used to wait for it to finish.
+pub struct MockServerHandle {
+  mock_server: Arc<Mutex<MockServer>>,
+  join_handle: Option<tokio::task::JoinHandle<()>>
+}
+
+impl MockServerHandle {
+  /// Starts a mock server for the pact on the given address, running on the tokio runtime of
+  /// the caller. A random ID is assigned to the mock server.
+  pub async fn start(
+    pact: Box<dyn Pact>,
+    addr: SocketAddr,
+    config: MockServerConfig
+  ) -> Result<MockServerHandle, String> {
+    MockServerHandle::start_with_id(Uuid::new_v4().to_string(), pact, addr, config).await
+  }
+
+  /// Starts a mock server with the given ID for the pact on the given address, running on the
+  /// tokio runtime of the caller
+  pub async fn start_with_id(
+    id: String,
+    pact: Box<dyn Pact>,
+    addr: SocketAddr,
+    config: MockServerConfig
+  ) -> Result<MockServerHandle, String> {
+    let (mock_server, future) = MockServer::new(id, pact, addr, config).await?;
+    Ok(MockServerHandle::from_parts(mock_server, tokio::spawn(future)))
+  }
+
+  /// Starts a TLS mock server for the pact on the given address, running on the tokio runtime
+  /// of the caller. A random ID is assigned to the mock server.
+  pub async fn start_tls(
+    pact: Box<dyn Pact>,
+    addr: SocketAddr,
+    tls: &ServerConfig,
+    config: MockServerConfig
+  ) -> Result<MockServerHandle, String> {
+    MockServerHandle::start_tls_with_id(Uuid::new_v4().to_string(), pact, addr, tls, config).await
+  }
+
+  /// Starts a TLS mock server with the given ID for the pact on the given address, running on
+  /// the tokio runtime of the caller
+  pub async fn start_tls_with_id(
+    id: String,
+    pact: Box<dyn Pact>,
+    addr: SocketAddr,
+    tls: &ServerConfig,
+    config: MockServerConfig
+  ) -> Result<MockServerHandle, String> {
+    let (mock_server, future) = MockServer::new_tls(id, pact, addr, tls, config).await?;
+    Ok(MockServerHandle::from_parts(mock_server, tokio::spawn(future)))
+  }
+
+  /// Starts a mock server with the given ID for the pact bound to a Unix domain socket, running
+  /// on the tokio runtime of the caller
+  #[cfg(unix)]
+  pub async fn start_with_unix_socket(
+    id: String,
+    pact: Box<dyn Pact>,
+    path: &std::path::Path,
+    config: MockServerConfig
+  ) -> Result<MockServerHandle, String> {
+    let (mock_server, future) = MockServer::new_unix_socket(id, pact, path.to_path_buf(), config).await?;
+    Ok(MockServerHandle::from_parts(mock_server, tokio::spawn(future)))
+  }
+
+  /// Creates a handle for a mock server whose future has already been spawned
+  pub(crate) fn from_parts(
+    mock_server: Arc<Mutex<MockServer>>,
+    join_handle: tokio::task::JoinHandle<()>
+  ) -> MockServerHandle {
+    MockServerHandle { mock_server, join_handle: Some(join_handle) }
+  }
+
+  /// The mock server this is a handle to
+  pub(crate) fn mock_server(&self) -> &Arc<Mutex<MockServer>> {
+    &self.mock_server
+  }
+
+  /// Applies the function to the mock server and returns the result
+  pub fn with_mock_server<R, F: FnOnce(&mut MockServer) -> R>(&self, f: F) -> R {
+    f(&mut self.mock_server.lock().unwrap())
+  }
+
+  /// ID of the mock server
+  pub fn id(&self) -> String {
+    self.mock_server.lock().unwrap().id.clone()
+  }
+
+  /// Port the mock server is running on. Mock servers bound to a Unix domain socket do not
+  /// have a port.
+  pub fn port(&self) -> Option<u16> {
+    self.mock_server.lock().unwrap().port
+  }
+
+  /// URL of the mock server
+  pub fn url(&self) -> String {
+    self.mock_server.lock().unwrap().url()
+  }
+
+  /// If all the requests to the mock server have been successfully matched
+  pub fn matched(&self) -> bool {
+    self.mismatches().is_empty()
+  }
+
+  /// All the mismatches that have occurred with the mock server
+  pub fn mismatches(&self) -> Vec<MatchResult> {
+    self.mock_server.lock().unwrap().mismatches()
+  }
+
+  /// All the requests received by the mock server
+  pub fn journal(&self) -> Vec<RequestJournalEntry> {
+    self.mock_server.lock().unwrap().journal()
+  }
+
+  /// Writes out the pact file of the mock server to the output directory. If `None` is passed
+  /// in, the current working directory is used. If overwrite is true, any existing file is
+  /// overwritten, otherwise the pact is merged into it.
+  pub fn write_pact(&self, output_path: &Option<String>, overwrite: bool) -> anyhow::Result<()> {
+    self.mock_server.lock().unwrap().write_pact(output_path, overwrite)
+  }
+
+  /// Shuts down the mock server, and waits for it to finish
+  pub async fn shutdown(mut self) -> Result<(), String> {
+    MockServer::shutdown_and_notify(&self.mock_server)?;
+    match self.join_handle.take() {
+      Some(join_handle) => join_handle.await
+        .map_err(|err| format!("Mock server did not shut down cleanly - {}", err)),
+      None => Ok(())
+    }
+  }
+}
+
+impl Drop for MockServerHandle {
+  fn drop(&mut self) {
+    if self.join_handle.is_some() && MockServer::shutdown_and_notify(&self.mock_server).is_ok() {
+      debug!("Mock server shut down as its handle was dropped");
+    }
+  }
+}
@@ -0,0 +1,255 @@
+//!
+//! The har module exports the journal of a mock server as an HTTP Archive (HAR 1.2) document,
+//! which can be opened with the developer tools of most browsers.
+//!
+
+use std::collections::HashMap;
+
+use chrono::SecondsFormat;
+use serde_json::{json, Value};
+
+use pact_models::bodies::OptionalBody;
+use pact_models::http_parts::HttpPart;
+use pact_models::query_strings::build_query_string;
+
+use crate::journal::RequestJournalEntry;
+
+/// Version of the HAR format that is generated
+pub const HAR_VERSION: &str = "1.2";
+
+/// Converts the journal entries of a mock server to a HAR document. `base_url` is the URL of the
+/// mock server, which is used to build the absolute URLs of the requests. Entries for requests
+/// that did not match an interaction have a `_matchResult` attribute with the key of the
+/// match result (i.e. `Request-Mismatch` or `Unexpected-Request`).
+///
+/// Binary request bodies are Base64 encoded in the `text` attribute of the `postData`, which then
+/// has an `_encoding` attribute set to `base64`. HAR 1.2 only defines the `encoding` attribute
+/// for the content of responses, so this custom attribute is needed to tell them apart from text.
+pub fn journal_to_har(journal: &[RequestJournalEntry], base_url: &str) -> Value {
+  json!({
+    "log": {
+      "version": HAR_VERSION,
+      "creator": {
+        "name": "pact_mock_server",
+        "version": env!("CARGO_PKG_VERSION")
+      },
+      "entries": journal.iter().map(|entry| har_entry(entry, base_url)).collect::<Vec<Value>>()
+    }
+  })
+}
+
+fn har_entry(entry: &RequestJournalEntry, base_url: &str) -> Value {
+  let time = entry.duration.as_secs_f64() * 1000.0;
+  let http_version = entry.protocol_version.clone()
+    .unwrap_or_else(|| "HTTP/1.1".to_string());
+  let mut json = json!({
+    "startedDateTime": entry.received.to_rfc3339_opts(SecondsFormat::Millis, false),
+    "time": time,
+    "request": har_request(entry, base_url, &http_version),
+    "response": har_response(entry, &http_version),
+    "cache": {},
+    "timings": {
+      "send": 0,
+      "wait": time,
+      "receive": 0
+    }
+  });
+
+  if !entry.matched() {
+    let map = json.as_object_mut().unwrap();
+    map.insert("_matchResult".to_string(), json!(entry.match_key));
+  }
+
+  json
+}
+
+fn har_request(entry: &RequestJournalEntry, base_url: &str, http_version: &str) -> Value {
+  let request = &entry.request;
+  let url = match &request.query {
+    Some(query) if !query.is_empty() =>
+      format!("{}{}?{}", base_url, request.path, build_query_string(query.clone())),
+    _ => format!("{}{}", base_url, request.path)
+  };
+  let query_string = request.query.iter()
+    .flat_map(name_value_pairs)
+    .collect::<Vec<Value>>();
+
+  let mut json = json!({
+    "method": request.method.to_uppercase(),
+    "url": url,
+    "httpVersion": http_version,
+    "cookies": [],
+    "headers": request.headers.iter().flat_map(name_value_pairs).collect::<Vec<Value>>(),
+    "queryString": query_string,
+    "headersSize": -1,
+    "bodySize": body_size(&request.body)
+  });
+
+  if let Some(body) = request.body.value() {
+    let map = json.as_object_mut().unwrap();
+    let mime_type = request.content_type().map(|ct| ct.to_string()).unwrap_or_default();
+    let mut post_data = json!({
+      "mimeType": mime_type,
+      "params": []
+    });
+    let post_data_map = post_data.as_object_mut().unwrap();
+    if request.has_text_body() {
+      post_data_map.insert("text".to_string(), json!(request.body.str_value()));
+    } else {
+      post_data_map.insert("text".to_string(), json!(base64::encode(&body)));
+      post_data_map.insert("_encoding".to_string(), json!("base64"));
+    }
+    map.insert("postData".to_string(), post_data);
+  }
+
+  json
+}
+
+fn har_response(entry: &RequestJournalEntry, http_version: &str) -> Value {
+  let response = &entry.response;
+  let status_text = hyper::StatusCode::from_u16(response.status).ok()
+    .and_then(|status| status.canonical_reason())
+    .unwrap_or_default();
+
+  let mut content = json!({
+    "size": body_size(&response.body),
+    "mimeType": response.content_type().map(|ct| ct.to_string()).unwrap_or_default()
+  });
+  if let Some(body) = response.body.value() {
+    let map = content.as_object_mut().unwrap();
+    if response.has_text_body() {
+      map.insert("text".to_string(), json!(response.body.str_value()));
+    } else {
+      map.insert("text".to_string(), json!(base64::encode(&body)));
+      map.insert("encoding".to_string(), json!("base64"));
+    }
+  }
+
+  json!({
+    "status": response.status,
+    "statusText": status_text,
+    "httpVersion": http_version,
+    "cookies": [],
+    "headers": response.headers.iter().flat_map(name_value_pairs).collect::<Vec<Value>>(),
+    "content": content,
+    "redirectURL": response.lookup_header_value("location").unwrap_or_default(),
+    "headersSize": -1,
+    "bodySize": body_size(&response.body)
+  })
+}
+
+fn name_value_pairs(values: &HashMap<String, Vec<String>>) -> Vec<Value> {
+  let mut names = values.keys().collect::<Vec<&String>>();
+  names.sort();
+  names.iter()
+    .flat_map(|name| values[*name].iter().map(move |value| json!({ "name": name, "value": value })))
+    .collect()
+}
+
+fn body_size(body: &OptionalBody) -> i64 {
+  match body {
+    OptionalBody::Present(bytes, _) => bytes.len() as i64,
+    OptionalBody::Empty => 0,
+    _ => -1
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use bytes::Bytes;
+  use chrono::Local;
+  use expectest::prelude::*;
+  use maplit::*;
+  use serde_json::json;
+
+  use pact_models::bodies::OptionalBody;
+  use pact_models::request::Request;
+  use pact_models::response::Response;
+
+  use crate::journal::RequestJournalEntry;
+
+  use super::journal_to_har;
+
+  #[test]
+  fn journal_to_har_converts_the_entries() {
+    let matched = RequestJournalEntry {
+      request: Request {
+        method: "post".to_string(),
+        path: "/users".to_string(),
+        query: Some(hashmap!{ "page".to_string() => vec!["1".to_string()] }),
+        headers: Some(hashmap!{ "Content-Type".to_string() => vec!["application/json".to_string()] }),
+        body: OptionalBody::from(r#"{"name":"Fred"}"#),
+        .. Request::default()
+      },
+      protocol_version: None,
+      received: Local::now(),
+      duration: Duration::from_millis(25),
+      interaction_description: Some("a request to create a user".to_string()),
+      interaction_key: Some("1234".to_string()),
+      match_key: "Request-Matched".to_string(),
+      response: Response { status: 201, .. Response::default() },
+      client_certificate_subject: None
+    };
+    let unmatched = RequestJournalEntry {
+      request: Request { path: "/unknown".to_string(), .. Request::default() },
+      protocol_version: None,
+      received: Local::now(),
+      duration: Duration::from_millis(2),
+      interaction_description: None,
+      interaction_key: None,
+      match_key: "Unexpected-Request".to_string(),
+      response: Response { status: 500, .. Response::default() },
+      client_certificate_subject: None
+    };
+
+    let har = journal_to_har(&[matched, unmatched], "http://127.0.0.1:1234");
+
+    expect!(&har["log"]["version"]).to(be_equal_to(&json!("1.2")));
+    let entries = har["log"]["entries"].as_array().unwrap();
+    expect!(entries.len()).to(be_equal_to(2));
+
+    expect!(&entries[0]["time"]).to(be_equal_to(&json!(25.0)));
+    expect!(&entries[0]["request"]["method"]).to(be_equal_to(&json!("POST")));
+    expect!(&entries[0]["request"]["url"]).to(be_equal_to(&json!("http://127.0.0.1:1234/users?page=1")));
+    expect!(&entries[0]["request"]["queryString"]).to(be_equal_to(&json!([{ "name": "page", "value": "1" }])));
+    expect!(&entries[0]["request"]["postData"]["text"]).to(be_equal_to(&json!(r#"{"name":"Fred"}"#)));
+    expect!(&entries[0]["response"]["status"]).to(be_equal_to(&json!(201)));
+    expect!(&entries[0]["response"]["statusText"]).to(be_equal_to(&json!("Created")));
+    expect!(entries[0]["request"]["postData"].get("_encoding")).to(be_none());
+    expect!(entries[0].get("_matchResult")).to(be_none());
+
+    expect!(&entries[1]["request"]["method"]).to(be_equal_to(&json!("GET")));
+    expect!(&entries[1]["response"]["status"]).to(be_equal_to(&json!(500)));
+    expect!(&entries[1]["_matchResult"]).to(be_equal_to(&json!("Unexpected-Request")));
+  }
+
+  #[test]
+  fn journal_to_har_marks_binary_request_bodies_as_base64_encoded() {
+    let entry = RequestJournalEntry {
+      request: Request {
+        method: "put".to_string(),
+        path: "/image".to_string(),
+        headers: Some(hashmap!{ "Content-Type".to_string() => vec!["image/png".to_string()] }),
+        body: OptionalBody::Present(Bytes::from_static(&[0x89, 0x50, 0x4e, 0x47, 0x00, 0xff]), None),
+        .. Request::default()
+      },
+      protocol_version: None,
+      received: Local::now(),
+      duration: Duration::from_millis(5),
+      interaction_description: Some("a request to upload an image".to_string()),
+      interaction_key: None,
+      match_key: "Request-Matched".to_string(),
+      response: Response { status: 204, .. Response::default() },
+      client_certificate_subject: None
+    };
+
+    let har = journal_to_har(&[entry], "http://127.0.0.1:1234");
+
+    let post_data = &har["log"]["entries"][0]["request"]["postData"];
+    expect!(&post_data["mimeType"]).to(be_equal_to(&json!("image/png")));
+    expect!(&post_data["text"]).to(be_equal_to(&json!("iVBORwD/")));
+    expect!(&post_data["_encoding"]).to(be_equal_to(&json!("base64")));
+  }
+}
@@ -4,11 +4,13 @@ use std::io;
 use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
+use chrono::Local;
 use futures::prelude::*;
 use futures::StreamExt;
 use futures::task::{Context, Poll};
-use hyper::{Body, Error, Response, Server};
+use hyper::{Body, Response, Server};
 use hyper::http::header::{HeaderName, HeaderValue};
 use hyper::http::response::Builder as ResponseBuilder;
 use hyper::service::make_service_fn;
@@ -16,21 +18,41 @@ use hyper::service::service_fn;
 use log::*;
 use maplit::*;
 use rustls::ServerConfig;
-use serde_json::json;
+use serde_json::{json, Value};
 use tokio::net::{TcpListener, TcpStream};
 use tokio_rustls::server::TlsStream;
 use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::Session;
+use x509_parser::parse_x509_certificate;
 
+use pact_matching::content_encoding::{content_encodings, decode_body, encode_body};
 use pact_matching::logging::LOG_ID;
-use pact_matching::models::{Pact, RequestResponsePact};
+use pact_matching::models::{Interaction, Pact};
+use pact_models::PactSpecification;
 use pact_models::bodies::OptionalBody;
-use pact_models::generators::GeneratorTestMode;
+use pact_models::generators::{GeneratorTestMode, request_context};
 use pact_models::http_parts::HttpPart;
 use pact_models::query_strings::parse_query_string;
 use pact_models::request::Request;
-
-use crate::matching::{match_request, MatchResult};
+use pact_models::response::Response as PactResponse;
+
+use crate::cors::merge_cors_headers;
+use crate::faults::FaultConfig;
+use crate::journal::RequestJournalEntry;
+use crate::matching::{
+  closest_candidates,
+  interaction_key,
+  interactions_for_provider_state,
+  match_request_in_order,
+  match_request_loosely,
+  match_request_with_interaction,
+  MatchCandidate,
+  MatchResult
+};
 use crate::mock_server::MockServer;
+use crate::observer::notify_request_handled;
+use crate::proxy;
+use crate::streaming::StreamedBody;
 
 #[derive(Debug, Clone)]
 enum InteractionError {
@@ -40,6 +62,13 @@ enum InteractionError {
     ResponseBodyError
 }
 
+// Outcome of handling a request, which is either a response to send back or, when the connection
+// reset fault is configured, the connection must be reset instead
+enum MockResponse {
+    Send(Response<Body>),
+    ResetConnection
+}
+
 fn extract_path(uri: &hyper::Uri) -> String {
     uri.path_and_query()
         .map(|path_and_query| path_and_query.path())
@@ -75,8 +104,6 @@ fn extract_headers(headers: &hyper::HeaderMap) -> Result<Option<HashMap<String,
         } else {
           Ok((name.as_str().into(), parsed_vals.iter().cloned()
             .map(|val| val.unwrap_or_default())
-            .flat_map(|val| val.split(",").map(|v| v.to_string()).collect::<Vec<String>>())
-            .map(|val| val.trim().to_string())
             .collect()))
         }
       })
@@ -105,6 +132,7 @@ async fn hyper_request_to_pact_request(req: hyper::Request<Body>) -> Result<Requ
     let body_bytes = hyper::body::to_bytes(req.into_body())
         .await
         .map_err(|_| InteractionError::RequestBodyError)?;
+    let body_bytes = decode_request_body(body_bytes, &headers);
 
     let request = Request {
       method,
@@ -120,125 +148,215 @@ async fn hyper_request_to_pact_request(req: hyper::Request<Body>) -> Result<Requ
     })
 }
 
+/// Decodes the request body according to its Content-Encoding header, so that the decoded body
+/// is matched. If the body can not be decoded, it is matched as it was received.
+fn decode_request_body(body: bytes::Bytes, headers: &Option<HashMap<String, Vec<String>>>) -> bytes::Bytes {
+  if body.is_empty() {
+    return body;
+  }
+  match decode_body(&OptionalBody::Present(body.clone(), None), headers) {
+    Ok(OptionalBody::Present(decoded, _)) => decoded,
+    Ok(_) => body,
+    Err(err) => {
+      warn!("Could not decode the request body, it will be matched as it was received - {}", err);
+      body
+    }
+  }
+}
+
 fn set_hyper_headers(builder: &mut ResponseBuilder, headers: &Option<HashMap<String, Vec<String>>>) -> Result<(), InteractionError> {
-    let hyper_headers = builder.headers_mut().unwrap();
-    match headers {
-        Some(header_map) => {
-            for (k, v) in header_map {
-                for val in v {
-                    // FIXME?: Headers are not sent in "raw" mode.
-                    // Names are converted to lower case and values are parsed.
-                    hyper_headers.append(
-                        HeaderName::from_bytes(k.as_bytes())
-                            .map_err(|err| {
-                                error!("Invalid header name '{}' ({})", k, err);
-                                InteractionError::ResponseHeaderEncodingError
-                            })?,
-                        val.parse::<HeaderValue>()
-                            .map_err(|err| {
-                                error!("Invalid header value '{}': '{}' ({})", k, val, err);
-                                InteractionError::ResponseHeaderEncodingError
-                            })?
-                    );
-                }
-            }
-        },
-        _ => {}
+  let hyper_headers = builder.headers_mut().unwrap();
+  if let Some(header_map) = headers {
+    for (k, v) in header_map {
+      let name = HeaderName::from_bytes(k.as_bytes())
+        .map_err(|err| {
+          error!("Invalid header name '{}' ({})", k, err);
+          InteractionError::ResponseHeaderEncodingError
+        })?;
+      // Header values are sent as they are written in the pact, with one header for each value.
+      // Header names are normalised by hyper, which can not write them with their original case.
+      // HTTP/1 responses are written with title case names (i.e. Content-Type), which is how they
+      // are normally written in pacts, and HTTP/2 responses with lower case names.
+      for val in v {
+        hyper_headers.append(name.clone(),
+          HeaderValue::from_str(val.as_str())
+            .map_err(|err| {
+              error!("Invalid header value '{}': '{}' ({})", k, val, err);
+              InteractionError::ResponseHeaderEncodingError
+            })?
+        );
+      }
     }
-    Ok(())
+  }
+  Ok(())
 }
 
-fn error_body(request: &Request, error: &String) -> String {
-    let body = json!({ "error" : format!("{} : {:?}", error, request) });
-    body.to_string()
+/// Maximum number of candidate interactions to include in the error response
+const MAX_CANDIDATES: usize = 3;
+
+/// JSON body of the error response for a request that did not match. This lists the closest
+/// candidate interactions with their mismatches, and a diff against the received request.
+fn error_body(request: &Request, match_result: &MatchResult, candidates: &[MatchCandidate]) -> String {
+  let body = json!({
+    "error": format!("{} : {} {}", match_result.match_key(), request.method, request.path),
+    "type": match_result.to_json().get("type").cloned().unwrap_or_default(),
+    "request": request.to_json(&PactSpecification::V3),
+    "candidates": candidates.iter().map(|candidate| candidate.to_json(request)).collect::<Vec<Value>>()
+  });
+  body.to_string()
 }
 
-fn match_result_to_hyper_response(
+fn match_result_to_response(
   request: &Request,
-  match_result: MatchResult,
-  mock_server: Arc<Mutex<MockServer>>
-) -> Result<Response<Body>, InteractionError> {
-  let cors_preflight = {
+  match_result: &MatchResult,
+  mock_server: &Arc<Mutex<MockServer>>
+) -> PactResponse {
+  let (cors_preflight, cors, unmatched_status) = {
     let ms = mock_server.lock().unwrap();
-    ms.config.cors_preflight
+    (ms.config.cors_preflight, ms.config.cors.clone(), ms.config.unmatched_status())
   };
 
   match match_result {
-    MatchResult::RequestMatch(_, ref response) => {
+    MatchResult::RequestMatch(_, response) => {
       let ms = mock_server.lock().unwrap();
       let context = hashmap!{
         "mockServer" => json!({
           "href": ms.url(),
           "port": ms.port
-        })
+        }),
+        "request" => request_context(request)
       };
       debug!("Test context = {:?}", context);
-      let response = pact_matching::generate_response(response, &GeneratorTestMode::Consumer, &context);
+      let response = pact_matching::generate_response(response, &GeneratorTestMode::Consumer, &context,
+        ms.config.generator_seed);
       info!("Request matched, sending response {}", response);
       if response.has_text_body() {
         debug!("     body: '{}'", response.body.str_value());
       }
 
-      let mut builder = Response::builder()
-        .status(response.status)
-        .header(hyper::header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-        .header(hyper::header::ACCESS_CONTROL_ALLOW_HEADERS, "*")
-        .header(hyper::header::ACCESS_CONTROL_ALLOW_METHODS, "GET, HEAD, POST, PUT, DELETE, CONNECT, OPTIONS, TRACE, PATCH")
-        .header(hyper::header::ACCESS_CONTROL_EXPOSE_HEADERS, "Location, Link");
-
-      set_hyper_headers(&mut builder, &response.headers)?;
-
-      builder.body(match response.body {
-        OptionalBody::Present(ref s, _) => Body::from(s.clone()),
-        _ => Body::empty()
-      })
-        .map_err(|_| InteractionError::ResponseBodyError)
+      PactResponse {
+        status: response.status,
+        headers: merge_cors_headers(cors.response_headers(request), &response.headers),
+        body: response.body.clone(),
+        .. PactResponse::default()
+      }
     },
     _ => {
       debug!("Request did not match: {}", match_result);
-      if cors_preflight && request.method.to_uppercase() == "OPTIONS" {
+      if cors_preflight && cors.enabled && request.method.to_uppercase() == "OPTIONS" {
         info!("Responding to CORS pre-flight request");
-        let origin = match request.headers.clone() {
-          Some(ref h) => h.iter()
-            .find(|kv| kv.0.to_lowercase() == "referer")
-            .map(|kv| kv.1.clone().join(", ")).unwrap_or("*".to_string()),
-          None => "*".to_string()
+        PactResponse {
+          status: 204,
+          headers: merge_cors_headers(cors.preflight_headers(request), &None),
+          body: OptionalBody::Empty,
+          .. PactResponse::default()
+        }
+      } else {
+        let headers = hashmap!{
+          "Content-Type".to_string() => vec!["application/json; charset=utf-8".to_string()],
+          "X-Pact".to_string() => vec![match_result.match_key()]
         };
-        let cors_headers = match request.headers.clone() {
-          Some(ref h) => h.iter()
-            .find(|kv| kv.0.to_lowercase() == "access-control-request-headers")
-            .map(|kv| kv.1.clone().join(", ") + ", *").unwrap_or("*".to_string()),
-          None => "*".to_string()
+        let candidates = {
+          let ms = mock_server.lock().unwrap();
+          let pact = ms.http_pact.clone();
+          closest_candidates(request, interactions_for_provider_state(pact.interactions(), &ms.provider_state),
+            MAX_CANDIDATES)
         };
-
-        Response::builder()
-          .status(204)
-          .header(hyper::header::ACCESS_CONTROL_ALLOW_ORIGIN, origin)
-          .header(hyper::header::ACCESS_CONTROL_ALLOW_METHODS, "GET, HEAD, POST, PUT, DELETE, CONNECT, OPTIONS, TRACE, PATCH")
-          .header(hyper::header::ACCESS_CONTROL_ALLOW_HEADERS, cors_headers)
-          .header(hyper::header::ACCESS_CONTROL_EXPOSE_HEADERS, "Location, Link")
-          .body(Body::empty())
-          .map_err(|_| InteractionError::ResponseBodyError)
-      } else {
-        Response::builder()
-          .status(500)
-          .header(hyper::header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-          .header(hyper::header::CONTENT_TYPE, "application/json; charset=utf-8")
-          .header("X-Pact", match_result.match_key())
-          .body(Body::from(error_body(&request, &match_result.match_key())))
-          .map_err(|_| InteractionError::ResponseBodyError)
+        PactResponse {
+          status: unmatched_status,
+          headers: merge_cors_headers(cors.response_headers(request), &Some(headers)),
+          body: OptionalBody::Present(error_body(request, match_result, &candidates).into(), None),
+          .. PactResponse::default()
+        }
       }
     }
   }
 }
 
+fn pact_response_to_hyper_response(
+  response: &PactResponse,
+  faults: &FaultConfig,
+  streamed_body: Option<&StreamedBody>
+) -> Result<Response<Body>, InteractionError> {
+  let mut builder = Response::builder()
+    .status(response.status);
+
+  let body = match streamed_body {
+    Some(streamed_body) => {
+      set_hyper_headers(&mut builder, &response.headers)?;
+      faults.streamed_body(streamed_body)
+    },
+    None => {
+      let encoded = encoded_response(response)?;
+      set_hyper_headers(&mut builder, &encoded.headers)?;
+      faults.body(&encoded.body)
+    }
+  };
+  builder.body(body)
+    .map_err(|_| InteractionError::ResponseBodyError)
+}
+
+/// Compresses the body of the response if the response has a Content-Encoding header. As the
+/// length of the body then changes, any Content-Length header is removed.
+fn encoded_response(response: &PactResponse) -> Result<PactResponse, InteractionError> {
+  let encodings = content_encodings(&response.headers).map_err(|err| {
+    error!("Could not encode the response body - {}", err);
+    InteractionError::ResponseBodyError
+  })?;
+  if encodings.is_empty() || !response.body.is_present() {
+    return Ok(response.clone());
+  }
+
+  let body = encode_body(&response.body, &response.headers).map_err(|err| {
+    error!("Could not encode the response body - {}", err);
+    InteractionError::ResponseBodyError
+  })?;
+  let headers = response.headers.as_ref().map(|headers| headers.iter()
+    .filter(|(name, _)| !name.eq_ignore_ascii_case("content-length"))
+    .map(|(name, values)| (name.clone(), values.clone()))
+    .collect());
+  Ok(PactResponse { headers, body, .. response.clone() })
+}
+
+/// Replaces the body of the response with the complete contents of the streamed body. As the
+/// streamed body is sent using chunked transfer encoding, any Content-Length header is removed.
+/// Streamed bodies can not be compressed, so responses with a Content-Encoding are rejected.
+fn with_streamed_body(response: &PactResponse, streamed_body: &StreamedBody) -> Result<PactResponse, InteractionError> {
+  let encodings = content_encodings(&response.headers).map_err(|err| {
+    error!("Could not encode the response body - {}", err);
+    InteractionError::ResponseBodyError
+  })?;
+  if !encodings.is_empty() {
+    error!("Content encodings are not supported for streamed response bodies, but the response has {:?}", encodings);
+    return Err(InteractionError::ResponseBodyError);
+  }
+
+  let mut headers = response.headers.clone().unwrap_or_default();
+  headers.retain(|name, _| !name.eq_ignore_ascii_case("content-length"));
+  if let Some(content_type) = streamed_body.content_type() {
+    if !headers.keys().any(|name| name.eq_ignore_ascii_case("content-type")) {
+      headers.insert("Content-Type".to_string(), vec![content_type.to_string()]);
+    }
+  }
+  let response = PactResponse {
+    headers: if headers.is_empty() { None } else { Some(headers) },
+    .. response.clone()
+  };
+  Ok(PactResponse {
+    body: OptionalBody::Present(streamed_body.content(), response.content_type()),
+    .. response
+  })
+}
+
 async fn handle_request(
   req: hyper::Request<Body>,
-  pact: Arc<RequestResponsePact>,
   matches: Arc<Mutex<Vec<MatchResult>>>,
-  mock_server: Arc<Mutex<MockServer>>
-) -> Result<Response<Body>, InteractionError> {
+  mock_server: Arc<Mutex<MockServer>>,
+  client_certificate_subject: Option<String>
+) -> Result<MockResponse, InteractionError> {
   debug!("Creating pact request from hyper request");
+  let received = Local::now();
+  let started = Instant::now();
 
   {
     let mut guard = mock_server.lock().unwrap();
@@ -246,23 +364,112 @@ async fn handle_request(
     mock_server.metrics.requests = mock_server.metrics.requests + 1;
   }
 
+  let protocol_version = format!("{:?}", req.version());
   let pact_request = hyper_request_to_pact_request(req).await?;
   info!("Received request {}", pact_request);
   if pact_request.has_text_body() {
     debug!("     body: '{}'", pact_request.body.str_value());
   }
 
-  let match_result = match_request(&pact_request, pact.interactions());
+  let (id, observers) = {
+    let ms = mock_server.lock().unwrap();
+    (ms.id.clone(), ms.config.observers.clone())
+  };
+  for observer in &observers {
+    observer.request_received(&id, &pact_request);
+  }
+
+  let pact = mock_server.lock().unwrap().http_pact.clone();
+  let (match_result, interaction) = {
+    let mut guard = mock_server.lock().unwrap();
+    let mock_server = guard.borrow_mut();
+    let interactions = interactions_for_provider_state(pact.interactions(), &mock_server.provider_state);
+    if mock_server.config.stub {
+      match_request_loosely(&pact_request, interactions)
+    } else if mock_server.config.ordered_interactions {
+      match_request_in_order(&pact_request, interactions, &mut mock_server.interaction_order)
+    } else {
+      match_request_with_interaction(&pact_request, interactions)
+    }
+  };
+  let interaction = interaction.map(|interaction| interaction.boxed());
+
+  let upstream_url = mock_server.lock().unwrap().config.upstream_url.clone();
+  let recorded = match (&match_result, upstream_url) {
+    (MatchResult::RequestNotFound(_), Some(upstream_url)) => {
+      let client = mock_server.lock().unwrap().upstream_client();
+      match proxy::forward_request(&client, &upstream_url, &pact_request).await {
+        Ok(upstream_response) => {
+          let interaction = proxy::recorded_interaction(&pact_request, &upstream_response);
+          Some(mock_server.lock().unwrap().record_interaction(interaction))
+        },
+        Err(err) => {
+          error!("{}", err);
+          None
+        }
+      }
+    },
+    _ => None
+  };
+  let match_result = match &recorded {
+    Some(interaction) => MatchResult::RequestMatch(interaction.request.clone(), interaction.response.clone()),
+    None => match_result
+  };
 
   matches.lock().unwrap().push(match_result.clone());
 
-  match_result_to_hyper_response(&pact_request, match_result, mock_server)
+  let response = match_result_to_response(&pact_request, &match_result, &mock_server);
+
+  let matched_interaction = recorded.as_ref()
+    .map(|interaction| interaction as &dyn Interaction)
+    .or_else(|| interaction.as_deref()
+      .filter(|_| match_result.matched())
+      .map(|interaction| interaction as &dyn Interaction));
+  let (response, faults, streamed_body, delay) = {
+    let mut ms = mock_server.lock().unwrap();
+    let faults = ms.config.faults_for(matched_interaction);
+    let streamed_body = ms.config.streamed_body_for(matched_interaction);
+    let response = match &streamed_body {
+      Some(streamed_body) => faults.apply(&with_streamed_body(&response, streamed_body)?),
+      None => faults.apply(&response)
+    };
+    let delay = faults.delay.as_ref().map(|delay| delay.duration());
+    let entry = RequestJournalEntry {
+      request: pact_request.clone(),
+      protocol_version: Some(protocol_version),
+      received,
+      duration: started.elapsed() + delay.unwrap_or_default(),
+      interaction_description: matched_interaction.map(|i| i.description()),
+      interaction_key: matched_interaction.and_then(|i| interaction_key(i)),
+      match_key: match_result.match_key(),
+      response: response.clone(),
+      client_certificate_subject
+    };
+    ms.metrics.record(&entry, match_result.cors_preflight());
+    ms.record_request(entry);
+    (response, faults, streamed_body, delay)
+  };
+  notify_request_handled(&observers, &id, &pact_request, &match_result, &response);
+
+  if let Some(duration) = delay {
+    debug!("Delaying the response by {:?}", duration);
+    tokio::time::sleep(duration).await;
+  }
+
+  if faults.reset_connection {
+    warn!("Resetting the connection instead of sending the response");
+    return Ok(MockResponse::ResetConnection);
+  }
+
+  pact_response_to_hyper_response(&response, &faults, streamed_body.as_ref()).map(MockResponse::Send)
 }
 
 // TODO: Should instead use some form of X-Pact headers
-fn handle_mock_request_error(result: Result<Response<Body>, InteractionError>) -> Result<Response<Body>, Error> {
+fn handle_mock_request_error(result: Result<MockResponse, InteractionError>) -> Result<Response<Body>, io::Error> {
     match result {
-        Ok(response) => Ok(response),
+        Ok(MockResponse::Send(response)) => Ok(response),
+        Ok(MockResponse::ResetConnection) => Err(io::Error::new(io::ErrorKind::ConnectionReset,
+          "Connection reset by mock server fault injection")),
         Err(error) => {
             let response = match error {
                 InteractionError::RequestHeaderEncodingError => Response::builder()
@@ -288,19 +495,19 @@ fn handle_mock_request_error(result: Result<Response<Body>, InteractionError>) -
 // The reason that the function itself is still async (even if it performs
 // no async operations) is that it needs a tokio context to be able to call try_bind.
 pub(crate) async fn create_and_bind(
-  pact: RequestResponsePact,
   addr: SocketAddr,
   shutdown: impl std::future::Future<Output = ()>,
   matches: Arc<Mutex<Vec<MatchResult>>>,
   mock_server: Arc<Mutex<MockServer>>,
   mock_server_id: &String
 ) -> Result<(impl std::future::Future<Output = ()>, SocketAddr), hyper::Error> {
-  let pact = Arc::new(pact);
   let ms_id = Arc::new(mock_server_id.clone());
+  let http2 = mock_server.lock().unwrap().config.http2;
 
   let server = Server::try_bind(&addr)?
+    .http1_only(http2 == Some(false))
+    .http1_title_case_headers(true)
     .serve(make_service_fn(move |_| {
-      let pact = pact.clone();
       let matches = matches.clone();
       let mock_server = mock_server.clone();
       let mock_server_id = ms_id.clone();
@@ -308,14 +515,13 @@ pub(crate) async fn create_and_bind(
       LOG_ID.scope(mock_server_id.to_string(), async {
         Ok::<_, hyper::Error>(
           service_fn(move |req| {
-            let pact = pact.clone();
             let matches = matches.clone();
             let mock_server = mock_server.clone();
             let mock_server_id = mock_server_id.clone();
 
             LOG_ID.scope(mock_server_id.to_string(), async {
               handle_mock_request_error(
-                handle_request(req, pact, matches, mock_server).await
+                handle_request(req, matches, mock_server, None).await
               )
             })
           })
@@ -336,6 +542,94 @@ pub(crate) async fn create_and_bind(
   ))
 }
 
+#[cfg(unix)]
+struct UnixAcceptor {
+  listener: tokio::net::UnixListener
+}
+
+#[cfg(unix)]
+impl hyper::server::accept::Accept for UnixAcceptor {
+  type Conn = tokio::net::UnixStream;
+  type Error = io::Error;
+
+  fn poll_accept(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+  ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+    self.listener.poll_accept(cx).map(|result| Some(result.map(|(stream, _)| stream)))
+  }
+}
+
+// Create and bind the server to a Unix domain socket, but do not start it.
+// Returns a future that drives the server, which will remove the socket file once the
+// server has shut down.
+#[cfg(unix)]
+pub(crate) async fn create_and_bind_unix_socket(
+  path: std::path::PathBuf,
+  shutdown: impl std::future::Future<Output = ()>,
+  matches: Arc<Mutex<Vec<MatchResult>>>,
+  mock_server: Arc<Mutex<MockServer>>,
+  mock_server_id: &String
+) -> Result<impl std::future::Future<Output = ()>, io::Error> {
+  let ms_id = Arc::new(mock_server_id.clone());
+  let http2 = mock_server.lock().unwrap().config.http2;
+
+  remove_stale_unix_socket(&path)?;
+  let listener = tokio::net::UnixListener::bind(&path)?;
+  let server = Server::builder(UnixAcceptor { listener })
+    .http1_only(http2 == Some(false))
+    .http1_title_case_headers(true)
+    .serve(make_service_fn(move |_| {
+      let matches = matches.clone();
+      let mock_server = mock_server.clone();
+      let mock_server_id = ms_id.clone();
+
+      LOG_ID.scope(mock_server_id.to_string(), async {
+        Ok::<_, hyper::Error>(
+          service_fn(move |req| {
+            let matches = matches.clone();
+            let mock_server = mock_server.clone();
+            let mock_server_id = mock_server_id.clone();
+
+            LOG_ID.scope(mock_server_id.to_string(), async {
+              handle_mock_request_error(
+                handle_request(req, matches, mock_server, None).await
+              )
+            })
+          })
+        )
+      })
+    }));
+
+  Ok(async move {
+    let _ = server
+      .with_graceful_shutdown(shutdown)
+      .await;
+    if let Err(err) = std::fs::remove_file(&path) {
+      warn!("Failed to remove the Unix domain socket '{}' - {}", path.display(), err);
+    }
+  })
+}
+
+// Removes a socket file left behind at the path (i.e. by a process that was killed), so that the
+// path can be bound again. Any other kind of file at the path is an error.
+#[cfg(unix)]
+fn remove_stale_unix_socket(path: &std::path::Path) -> Result<(), io::Error> {
+  use std::os::unix::fs::FileTypeExt;
+
+  match std::fs::symlink_metadata(path) {
+    Ok(metadata) => if metadata.file_type().is_socket() {
+      debug!("Removing existing Unix domain socket '{}'", path.display());
+      std::fs::remove_file(path)
+    } else {
+      Err(io::Error::new(io::ErrorKind::AlreadyExists,
+        format!("'{}' already exists and is not a Unix domain socket", path.display())))
+    },
+    Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+    Err(err) => Err(err)
+  }
+}
+
 // Taken from https://github.com/ctz/hyper-rustls/blob/master/examples/server.rs
 struct HyperAcceptor {
   stream: Pin<Box<dyn Stream<Item = Result<TlsStream<TcpStream>, io::Error>> + Send>>
@@ -353,15 +647,34 @@ impl hyper::server::accept::Accept for HyperAcceptor {
   }
 }
 
+/// Returns the subject of the certificate the client authenticated the TLS connection with
+fn client_certificate_subject(conn: &TlsStream<TcpStream>) -> Option<String> {
+  let (_, session) = conn.get_ref();
+  session.get_peer_certificates()
+    .and_then(|certificates| certificates.first().cloned())
+    .and_then(|certificate| match parse_x509_certificate(&certificate.0) {
+      Ok((_, x509)) => Some(x509.subject().to_string()),
+      Err(err) => {
+        warn!("Failed to parse the client certificate - {}", err);
+        None
+      }
+    })
+}
+
 pub(crate) async fn create_and_bind_tls(
-  pact: RequestResponsePact,
   addr: SocketAddr,
   shutdown: impl std::future::Future<Output = ()>,
   matches: Arc<Mutex<Vec<MatchResult>>>,
   tls_cfg: ServerConfig,
   mock_server: Arc<Mutex<MockServer>>
 ) -> Result<(impl std::future::Future<Output = ()>, SocketAddr), io::Error> {
-  let pact = Arc::new(pact);
+  let http2 = mock_server.lock().unwrap().config.http2;
+  let mut tls_cfg = tls_cfg;
+  match http2 {
+    Some(true) => tls_cfg.set_protocols(&["h2".into(), "http/1.1".into()]),
+    Some(false) => tls_cfg.set_protocols(&["http/1.1".into()]),
+    None => ()
+  }
 
   let tcp = TcpListener::bind(&addr).await?;
   let socket_addr = tcp.local_addr()?;
@@ -380,21 +693,23 @@ pub(crate) async fn create_and_bind_tls(
   let server = Server::builder(HyperAcceptor {
     stream: tls_stream.boxed()
   })
-    .serve(make_service_fn(move |_| {
-      let pact = pact.clone();
+    .http1_only(http2 == Some(false))
+    .http1_title_case_headers(true)
+    .serve(make_service_fn(move |conn: &TlsStream<TcpStream>| {
       let matches = matches.clone();
       let mock_server = mock_server.clone();
+      let client_certificate_subject = client_certificate_subject(conn);
 
       async {
         Ok::<_, hyper::Error>(
           service_fn(move |req| {
-            let pact = pact.clone();
             let matches = matches.clone();
             let mock_server = mock_server.clone();
+            let client_certificate_subject = client_certificate_subject.clone();
 
             async {
               handle_mock_request_error(
-                handle_request(req, pact, matches, mock_server).await
+                handle_request(req, matches, mock_server, client_certificate_subject).await
               )
             }
           })
@@ -417,7 +732,7 @@ pub(crate) async fn create_and_bind_tls(
 mod tests {
   use expectest::expect;
   use expectest::prelude::*;
-  use hyper::header::{ACCEPT, CONTENT_TYPE, USER_AGENT};
+  use hyper::header::{ACCEPT, ACCEPT_RANGES, CONTENT_TYPE, COOKIE, DATE, SET_COOKIE, USER_AGENT, VARY};
   use hyper::HeaderMap;
 
   use super::*;
@@ -428,7 +743,6 @@ mod tests {
     let matches = Arc::new(Mutex::new(vec![]));
 
     let (future, _) = create_and_bind(
-      RequestResponsePact::default(),
       ([0, 0, 0, 0], 0 as u16).into(),
       async {
           shutdown_rx.await.ok();
@@ -459,9 +773,51 @@ mod tests {
     headers.append(CONTENT_TYPE, "text/plain".parse().unwrap());
     let result = extract_headers(&headers);
     expect!(result).to(be_ok().value(Some(hashmap! {
-      "accept".to_string() => vec!["application/xml".to_string(), "application/json".to_string()],
+      "accept".to_string() => vec!["application/xml, application/json".to_string()],
       "user-agent".to_string() => vec!["test".to_string(), "test2".to_string()],
       "content-type".to_string() => vec!["text/plain".to_string()]
     })));
   }
+
+  #[test]
+  fn extract_headers_keeps_the_received_header_values() {
+    let mut headers = HeaderMap::new();
+    headers.append(DATE, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+    headers.append(COOKIE, "a=1, b=2".parse().unwrap());
+    let result = extract_headers(&headers);
+    expect!(result).to(be_ok().value(Some(hashmap! {
+      "date".to_string() => vec!["Wed, 21 Oct 2015 07:28:00 GMT".to_string()],
+      "cookie".to_string() => vec!["a=1, b=2".to_string()]
+    })));
+  }
+
+  #[test]
+  fn set_hyper_headers_sends_the_header_values_as_written() {
+    let mut builder = Response::builder();
+    let headers = Some(hashmap! {
+      "Set-Cookie".to_string() => vec![
+        "id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT".to_string(),
+        "theme=dark".to_string()
+      ],
+      "Vary".to_string() => vec!["Accept".to_string(), "Origin".to_string()],
+      "Date".to_string() => vec!["Wed, 21 Oct 2015 07:28:00 GMT".to_string()],
+      "Accept-Ranges".to_string() => vec!["bytes, none".to_string()]
+    });
+    expect!(set_hyper_headers(&mut builder, &headers)).to(be_ok());
+
+    let response = builder.body(Body::empty()).unwrap();
+    let cookies = response.headers().get_all(SET_COOKIE).iter()
+      .map(|value| value.to_str().unwrap().to_string())
+      .collect::<Vec<String>>();
+    expect!(cookies).to(be_equal_to(vec![
+      "id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT".to_string(),
+      "theme=dark".to_string()
+    ]));
+    let vary = response.headers().get_all(VARY).iter()
+      .map(|value| value.to_str().unwrap().to_string())
+      .collect::<Vec<String>>();
+    expect!(vary).to(be_equal_to(vec!["Accept".to_string(), "Origin".to_string()]));
+    expect!(response.headers().get(DATE).unwrap().to_str().unwrap()).to(be_equal_to("Wed, 21 Oct 2015 07:28:00 GMT"));
+    expect!(response.headers().get(ACCEPT_RANGES).unwrap().to_str().unwrap()).to(be_equal_to("bytes, none"));
+  }
 }
@@ -0,0 +1,67 @@
+//!
+//! The journal module defines the record of all the requests received by a mock server.
+//!
+
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde_json::json;
+
+use pact_models::PactSpecification;
+use pact_models::request::Request;
+use pact_models::response::Response;
+
+/// Entry in the request journal of a mock server
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestJournalEntry {
+  /// The request as it was received by the mock server
+  pub request: Request,
+  /// HTTP protocol version the request was received with (i.e. `HTTP/1.1` or `HTTP/2.0`)
+  pub protocol_version: Option<String>,
+  /// When the request was received
+  pub received: DateTime<Local>,
+  /// Time taken to respond to the request, including any delay injected as a fault
+  pub duration: Duration,
+  /// Description of the interaction that matched the request
+  pub interaction_description: Option<String>,
+  /// Key of the interaction that matched the request
+  pub interaction_key: Option<String>,
+  /// Key of the result of matching the request (see `MatchResult::match_key`)
+  pub match_key: String,
+  /// The response that was sent back
+  pub response: Response,
+  /// Subject of the client certificate, if the client authenticated with one over TLS
+  pub client_certificate_subject: Option<String>
+}
+
+impl RequestJournalEntry {
+  /// If the request matched an interaction
+  pub fn matched(&self) -> bool {
+    self.interaction_description.is_some()
+  }
+
+  /// Converts this journal entry to a `Value` struct
+  pub fn to_json(&self) -> serde_json::Value {
+    let mut json = json!({
+      "receivedAt": self.received.to_rfc3339(),
+      "request": self.request.to_json(&PactSpecification::V3),
+      "response": self.response.to_json(&PactSpecification::V3)
+    });
+
+    let map = json.as_object_mut().unwrap();
+    if let Some(ref protocol_version) = self.protocol_version {
+      map.insert("protocolVersion".to_string(), json!(protocol_version));
+    }
+    if let Some(ref description) = self.interaction_description {
+      map.insert("interaction".to_string(), json!({
+        "description": description,
+        "key": self.interaction_key
+      }));
+    }
+    if let Some(ref subject) = self.client_certificate_subject {
+      map.insert("clientCertificate".to_string(), json!({ "subject": subject }));
+    }
+
+    json
+  }
+}
@@ -4,10 +4,17 @@
 //!
 //! The exported functions using C bindings for controlling the mock server now live in the `pact_mock_server_ffi`
 //! crate.
+//!
+//! The functions in this crate manage the mock servers with a global server manager, and look
+//! them up by port number. Tests that run in their own tokio runtime can instead start a mock
+//! server with `handle::MockServerHandle`, which runs the mock server on the runtime of the caller
+//! and returns an owned handle to it.
 
 #![warn(missing_docs)]
 
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use lazy_static::*;
 use log::*;
@@ -15,15 +22,28 @@ use rustls::ServerConfig;
 use serde_json::json;
 use uuid::Uuid;
 
-use pact_matching::models::{load_pact_from_json, Pact};
+use pact_matching::models::{Interaction, load_pact_from_json, Pact, read_pact, RequestResponseInteraction};
+use pact_matching::models::v4;
+use pact_models::PactSpecification;
 
 use crate::mock_server::MockServerConfig;
 use crate::server_manager::ServerManager;
+use crate::tls::{CertificateAuthority, TlsConfigBuilder};
 
+pub mod aggregator;
+pub mod cors;
+pub mod faults;
+pub mod handle;
+pub mod har;
+pub mod journal;
 pub mod matching;
+pub mod metrics;
 pub mod mock_server;
+pub mod observer;
 pub mod server_manager;
+pub mod streaming;
 mod hyper_server;
+mod proxy;
 pub mod tls;
 
 /// Mock server errors
@@ -93,6 +113,34 @@ pub fn start_mock_server_with_config(
     .map(|addr| addr.port() as i32)
 }
 
+/// Starts a mock server with the given ID and pact, bound to a Unix domain socket at the given
+/// path instead of a TCP port. The ID needs to be unique. Any existing socket file at the path
+/// (i.e. left behind by a process that was killed) will be replaced, and the socket file is
+/// removed when the mock server is shut down. If the path exists and is not a socket, the mock
+/// server is not started.
+///
+/// * `id` - Unique ID for the mock server.
+/// * `pact` - Pact model to use for the mock server.
+/// * `path` - Path of the Unix domain socket that the server should listen on.
+/// * `config` - Configuration for the mock server
+///
+/// # Errors
+///
+/// An error with a message will be returned in the following conditions:
+///
+/// - If a mock server is not able to be started
+#[cfg(unix)]
+pub fn start_mock_server_with_unix_socket(
+  id: String,
+  pact: Box<dyn Pact>,
+  path: &Path,
+  config: MockServerConfig
+) -> Result<(), String> {
+  MANAGER.lock().unwrap()
+    .get_or_insert_with(ServerManager::new)
+    .start_mock_server_with_unix_socket(id, pact, path, config)
+}
+
 /// Starts a TLS mock server with the given ID, pact and port number. The ID needs to be unique. A port
 /// number of 0 will result in an auto-allocated port by the operating system. Returns the port
 /// that the mock server is running on wrapped in a `Result`.
@@ -144,6 +192,47 @@ pub fn start_tls_mock_server_with_config(
     .map(|addr| addr.port() as i32)
 }
 
+/// Starts a TLS mock server with the given ID, pact and port number, using a server certificate
+/// generated for the given DNS names and IP addresses. The ID needs to be unique. A port number of
+/// 0 will result in an auto-allocated port by the operating system. Returns the port that the mock
+/// server is running on and the CA certificate (in PEM format) that signed the server certificate
+/// wrapped in a `Result`. Clients need to trust the CA certificate to connect to the mock server.
+///
+/// * `id` - Unique ID for the mock server.
+/// * `pact` - Pact model to use for the mock server.
+/// * `addr` - Socket address that the server should listen on.
+/// * `subject_alt_names` - DNS names and IP addresses to generate the server certificate for.
+/// * `ca` - Certificate authority to sign the server certificate with. If `None`, the certificate
+///   authority generated for this process is used.
+/// * `config` - Configuration for the mock server
+///
+/// # Errors
+///
+/// An error with a message will be returned in the following conditions:
+///
+/// - If the server certificate is not able to be generated
+/// - If a mock server is not able to be started
+pub fn start_tls_mock_server_with_generated_cert(
+  id: String,
+  pact: Box<dyn Pact>,
+  addr: std::net::SocketAddr,
+  subject_alt_names: &[String],
+  ca: Option<Arc<CertificateAuthority>>,
+  config: MockServerConfig
+) -> Result<(i32, String), String> {
+  let builder = TlsConfigBuilder::new().generate_cert(subject_alt_names.iter().cloned());
+  let builder = match ca {
+    Some(ca) => builder.certificate_authority(ca),
+    None => builder
+  };
+  let ca_pem = builder.ca_certificate_pem()
+    .map_err(|err| format!("Failed to generate the certificate authority - {}", err))?;
+  let tls = builder.build()
+    .map_err(|err| format!("Failed to generate the server certificate - {}", err))?;
+  start_tls_mock_server_with_config(id, pact, addr, &tls, config)
+    .map(|port| (port, ca_pem))
+}
+
 /// Creates a mock server. Requires the pact JSON as a string as well as the port for the mock
 /// server to run on. A value of 0 for the port will result in a
 /// port being allocated by the operating system. The port of the mock server is returned.
@@ -226,6 +315,186 @@ pub fn mock_server_mismatches(mock_server_port: i32) -> Option<std::string::Stri
         })
 }
 
+/// Gets all the requests received by a mock server. The port number of the mock
+/// server is passed in, and the journal entries are returned in JSON format as a String.
+///
+/// If there is no mock server with the provided port number, `None` is returned.
+///
+pub fn mock_server_journal(mock_server_port: i32) -> Option<std::string::String> {
+  MANAGER.lock().unwrap()
+    .get_or_insert_with(ServerManager::new)
+    .find_mock_server_by_port_mut(mock_server_port as u16, &|mock_server| {
+      let journal = mock_server.journal().iter()
+        .map(|entry| entry.to_json())
+        .collect::<Vec<serde_json::Value>>();
+      json!(journal).to_string()
+    })
+}
+
+/// External interface to export all the requests received by a mock server, and the responses
+/// that were sent back, as an HTTP Archive (HAR 1.2) document. The port number of the mock server
+/// is passed in, and the HAR document is returned in JSON format as a String.
+///
+/// If there is no mock server with the provided port number, `None` is returned.
+///
+pub fn mock_server_har(mock_server_port: i32) -> Option<std::string::String> {
+  MANAGER.lock().unwrap()
+    .get_or_insert_with(ServerManager::new)
+    .find_mock_server_by_port_mut(mock_server_port as u16, &|mock_server| {
+      mock_server.to_har().to_string()
+    })
+}
+
+/// Activates a provider state on a running mock server. The port number of the mock server is
+/// passed in, and once the state is active only the interactions with that provider state will be
+/// matched against the received requests. Passing `None` for the state clears it.
+///
+/// Returns false if there is no mock server with the provided port number.
+///
+pub fn set_mock_server_provider_state(mock_server_port: i32, provider_state: Option<String>) -> bool {
+  MANAGER.lock().unwrap()
+    .get_or_insert_with(ServerManager::new)
+    .find_mock_server_by_port_mut(mock_server_port as u16, &|mock_server| {
+      mock_server.set_provider_state(provider_state.clone())
+    })
+    .is_some()
+}
+
+/// Parses an interaction from its JSON form. Interactions with a `type` attribute are loaded as
+/// V4 interactions, otherwise they are loaded as V3 request/response interactions.
+pub fn interaction_from_json(interaction_json: &str) -> Result<Box<dyn Interaction + Send>, String> {
+  let json: serde_json::Value = serde_json::from_str(interaction_json)
+    .map_err(|err| format!("Could not parse the interaction JSON - {}", err))?;
+  if json.get("type").is_some() {
+    v4::interaction_from_json("<interaction>", 0, &json)
+      .map(|interaction| interaction.boxed())
+      .map_err(|err| format!("Could not load the interaction - {}", err))
+  } else {
+    Ok(Box::new(RequestResponseInteraction::from_json(0, &json, &PactSpecification::V3)))
+  }
+}
+
+/// Loads the pact files into one pact, to be served by a mock server running in stub mode. Any
+/// directories are searched for pact files (files with a `.json` extension). The interactions of
+/// all the pacts are combined, and the consumer and provider of the first pact are used.
+pub fn load_stub_pact(paths: &[PathBuf]) -> Result<v4::V4Pact, String> {
+  let mut files = vec![];
+  for path in paths {
+    if path.is_dir() {
+      let entries = std::fs::read_dir(path)
+        .map_err(|err| format!("Could not read directory '{}' - {}", path.display(), err))?;
+      let mut pact_files = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().map(|ext| ext == "json").unwrap_or(false))
+        .collect::<Vec<PathBuf>>();
+      pact_files.sort();
+      files.extend(pact_files);
+    } else {
+      files.push(path.clone());
+    }
+  }
+
+  let mut stub_pact: Option<v4::V4Pact> = None;
+  for file in &files {
+    debug!("Loading pact file '{}' for the stub server", file.display());
+    let pact = read_pact(file)
+      .and_then(|pact| pact.as_v4_pact())
+      .map_err(|err| format!("Could not load pact file '{}' - {}", file.display(), err))?;
+    match stub_pact.as_mut() {
+      Some(stub_pact) => stub_pact.interactions.extend(pact.interactions),
+      None => stub_pact = Some(pact)
+    }
+  }
+  stub_pact.ok_or_else(|| "No pact files were found".to_string())
+}
+
+/// Adds an interaction to a running mock server. The port number of the mock server and the
+/// interaction in JSON format are passed in. If the mock server already has an interaction with
+/// the same description, it is replaced. The mock server keeps running on the same port.
+///
+/// Returns an error if the interaction is not valid, or there is no mock server with the
+/// provided port number.
+///
+pub fn upsert_mock_server_interaction(mock_server_port: i32, interaction_json: &str) -> Result<(), String> {
+  let interaction = interaction_from_json(interaction_json)?;
+  MANAGER.lock().unwrap()
+    .get_or_insert_with(ServerManager::new)
+    .find_mock_server_by_port_mut(mock_server_port as u16, &|mock_server| {
+      mock_server.upsert_interaction(interaction.as_ref())
+    })
+    .unwrap_or_else(|| Err(format!("No mock server running on port {}", mock_server_port)))
+}
+
+/// Removes the interaction with the given description from a running mock server. Returns
+/// `Ok(false)` if the mock server does not have an interaction with that description, and an
+/// error if there is no mock server with the provided port number.
+///
+pub fn remove_mock_server_interaction(mock_server_port: i32, description: &str) -> Result<bool, String> {
+  MANAGER.lock().unwrap()
+    .get_or_insert_with(ServerManager::new)
+    .find_mock_server_by_port_mut(mock_server_port as u16, &|mock_server| {
+      mock_server.remove_interaction(description)
+    })
+    .unwrap_or_else(|| Err(format!("No mock server running on port {}", mock_server_port)))
+}
+
+/// Replaces all the interactions of a running mock server with the interactions from the pact.
+/// The port number of the mock server and the pact in JSON format are passed in.
+///
+/// Returns an error if the pact is not valid, or there is no mock server with the provided port
+/// number.
+///
+pub fn replace_mock_server_interactions(mock_server_port: i32, pact_json: &str) -> Result<(), String> {
+  let json: serde_json::Value = serde_json::from_str(pact_json)
+    .map_err(|err| format!("Could not parse the pact JSON - {}", err))?;
+  let pact = load_pact_from_json("<replace_mock_server_interactions>", &json)
+    .map_err(|err| format!("Could not load the pact - {}", err))?;
+  MANAGER.lock().unwrap()
+    .get_or_insert_with(ServerManager::new)
+    .find_mock_server_by_port_mut(mock_server_port as u16, &|mock_server| {
+      mock_server.replace_interactions(pact.as_ref())
+    })
+    .unwrap_or_else(|| Err(format!("No mock server running on port {}", mock_server_port)))
+}
+
+/// Watches the pact file, and reloads the interactions of the running mock server on the given
+/// port whenever the file is modified. The file is checked every `interval` until the mock server
+/// is shut down.
+pub fn watch_mock_server_pact_file(mock_server_port: i32, path: &Path, interval: Duration) -> Result<(), String> {
+  let mut guard = MANAGER.lock().unwrap();
+  let manager = guard.get_or_insert_with(ServerManager::new);
+  let id = manager.find_mock_server_by_port_mut(mock_server_port as u16, &|mock_server| mock_server.id.clone())
+    .ok_or_else(|| format!("No mock server running on port {}", mock_server_port))?;
+  manager.watch_mock_server_pact_file(&id, path, interval)
+}
+
+/// Function to check if a mock server bound to a Unix domain socket has matched all its requests.
+/// Returns false if there is no mock server bound to the given path, or if any request has not
+/// been successfully matched.
+pub fn unix_socket_mock_server_matched(path: &Path) -> bool {
+  MANAGER.lock().unwrap()
+    .get_or_insert_with(ServerManager::new)
+    .find_mock_server_by_unix_socket_mut(path, &|mock_server| {
+      mock_server.mismatches().is_empty()
+    })
+    .unwrap_or(false)
+}
+
+/// Gets all the mismatches from a mock server bound to a Unix domain socket, in JSON format.
+///
+/// If there is no mock server bound to the provided path, `None` is returned.
+///
+pub fn unix_socket_mock_server_mismatches(path: &Path) -> Option<std::string::String> {
+  MANAGER.lock().unwrap()
+    .get_or_insert_with(ServerManager::new)
+    .find_mock_server_by_unix_socket_mut(path, &|mock_server| {
+      let mismatches = mock_server.mismatches().iter()
+        .map(|mismatch| mismatch.to_json() )
+        .collect::<Vec<serde_json::Value>>();
+      json!(mismatches).to_string()
+    })
+}
+
 /// Write Pact File Errors
 pub enum WritePactFileErr {
   /// IO Error occurred
@@ -267,6 +536,54 @@ pub fn write_pact_file(
     }
 }
 
+/// Trigger a mock server bound to a Unix domain socket to write out its pact file. Behaves the
+/// same as `write_pact_file`, except the mock server is looked up by the path of its socket.
+pub fn write_unix_socket_pact_file(
+  path: &Path,
+  directory: Option<String>,
+  overwrite: bool
+) -> Result<(), WritePactFileErr> {
+  let opt_result = MANAGER.lock().unwrap()
+    .get_or_insert_with(ServerManager::new)
+    .find_mock_server_by_unix_socket_mut(path, &|mock_server| {
+      mock_server.write_pact(&directory, overwrite)
+        .map(|_| ())
+        .map_err(|err| {
+          log::error!("Failed to write pact to file - {}", err);
+          WritePactFileErr::IOError
+        })
+    });
+
+  match opt_result {
+    Some(result) => result,
+    None => {
+      log::error!("No mock server bound to Unix domain socket {}", path.display());
+      Err(WritePactFileErr::NoMockServer)
+    }
+  }
+}
+
+/// Writes out one pact file for each consumer and provider, with the interactions exercised by
+/// all the mock servers started in this process (both running and shut down). Interactions that
+/// were not exercised are not included. The interactions previously written by the owner are
+/// replaced, and interactions written by other owners are kept. If `None` is passed in for the
+/// directory, the current working directory is used, and if `None` is passed in for the owner, the
+/// name of the current executable is used.
+///
+/// Returns the paths of the pact files written, or an `Err` if a file could not be written.
+pub fn write_aggregated_pact_files(
+  directory: Option<String>,
+  owner: Option<String>
+) -> Result<Vec<PathBuf>, WritePactFileErr> {
+  MANAGER.lock().unwrap()
+    .get_or_insert_with(ServerManager::new)
+    .write_aggregated_pacts(&directory, &owner)
+    .map_err(|err| {
+      log::error!("Failed to write aggregated pacts to file - {}", err);
+      WritePactFileErr::IOError
+    })
+}
+
 /// Shuts down the mock server with the provided port. Returns a boolean value to indicate if
 /// the mock server was successfully shut down.
 pub fn shutdown_mock_server(mock_server_port: i32) -> bool {
@@ -275,5 +592,13 @@ pub fn shutdown_mock_server(mock_server_port: i32) -> bool {
     .shutdown_mock_server_by_port(mock_server_port as u16)
 }
 
+/// Shuts down the mock server bound to the Unix domain socket at the provided path. Returns a
+/// boolean value to indicate if the mock server was successfully shut down.
+pub fn shutdown_unix_socket_mock_server(path: &Path) -> bool {
+  MANAGER.lock().unwrap()
+    .get_or_insert_with(ServerManager::new)
+    .shutdown_mock_server_by_unix_socket(path)
+}
+
 #[cfg(test)]
 mod tests;
@@ -3,13 +3,15 @@
 //! against a list of potential interactions.
 //!
 
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::{Debug, Display, Formatter};
 
+use difference::{Changeset, Difference};
 use itertools::Itertools;
 use serde_json::json;
 
-use pact_matching::Mismatch;
-use pact_matching::models::Interaction;
+use pact_matching::{Mismatch, RequestMatchResult};
+use pact_matching::models::{Interaction, RequestResponseInteraction};
 use pact_models::PactSpecification;
 use pact_models::request::Request;
 use pact_models::response::Response;
@@ -24,7 +26,15 @@ pub enum MatchResult {
   /// Match result where the request was not expected
   RequestNotFound(Request),
   /// Match result where an expected request was not received
-  MissingRequest(Request)
+  MissingRequest(Request),
+  /// Match result where an expected request was received a different number of times than
+  /// expected. Contains the expected request, the expected number of invocations and the actual
+  /// number of times it was received.
+  InvocationCountMismatch(Request, InvocationCount, usize),
+  /// Match result where the request matched an interaction, but not in the expected order. Contains
+  /// the expected request of the interaction that matched, the (zero-based) position of the
+  /// interaction that was expected next and the position of the interaction that matched.
+  RequestOutOfOrder(Request, usize, usize)
 }
 
 impl MatchResult {
@@ -34,7 +44,9 @@ impl MatchResult {
             &MatchResult::RequestMatch(_, _) => "Request-Matched",
             &MatchResult::RequestMismatch(_, _) => "Request-Mismatch",
             &MatchResult::RequestNotFound(_) => "Unexpected-Request",
-            &MatchResult::MissingRequest(_) => "Missing-Request"
+            &MatchResult::MissingRequest(_) => "Missing-Request",
+            &MatchResult::InvocationCountMismatch(_, _, _) => "Invocation-Count-Mismatch",
+            &MatchResult::RequestOutOfOrder(_, _, _) => "Request-Out-Of-Order"
         }.to_string()
     }
 
@@ -70,6 +82,22 @@ impl MatchResult {
                 "method": request.method,
                 "path": request.path,
                 "request": request.to_json(&PactSpecification::V3)
+            }),
+            &MatchResult::InvocationCountMismatch(ref request, ref expected, actual) => json!({
+                "type": "invocation-count-mismatch",
+                "method": request.method,
+                "path": request.path,
+                "request": request.to_json(&PactSpecification::V3),
+                "expected": expected.to_json(),
+                "actual": actual
+            }),
+            &MatchResult::RequestOutOfOrder(ref request, expected, actual) => json!({
+                "type": "request-out-of-order",
+                "method": request.method,
+                "path": request.path,
+                "request": request.to_json(&PactSpecification::V3),
+                "expectedPosition": expected,
+                "actualPosition": actual
             })
         }
     }
@@ -93,11 +121,70 @@ impl Display for MatchResult {
       },
       MatchResult::MissingRequest(request) => {
         write!(f, "Request was not received - {}", request)
+      },
+      MatchResult::InvocationCountMismatch(request, expected, actual) => {
+        write!(f, "Request was expected {} but was received {} times - {}", expected, actual, request)
+      },
+      MatchResult::RequestOutOfOrder(request, expected, actual) => {
+        write!(f, "Request was received out of order (expected the interaction at position {}, but matched the one at position {}) - {}",
+          expected, actual, request)
       }
     }
   }
 }
 
+/// Expected number of times an interaction is to be invoked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvocationCount {
+  /// Exactly the given number of times
+  Times(usize),
+  /// At least the given number of times
+  AtLeast(usize),
+  /// At most the given number of times
+  AtMost(usize)
+}
+
+impl InvocationCount {
+  /// If the actual number of invocations satisfies this expectation
+  pub fn matches(&self, actual: usize) -> bool {
+    match self {
+      InvocationCount::Times(count) => actual == *count,
+      InvocationCount::AtLeast(count) => actual >= *count,
+      InvocationCount::AtMost(count) => actual <= *count
+    }
+  }
+
+  /// Converts this invocation count to a `Value` struct
+  pub fn to_json(&self) -> serde_json::Value {
+    match self {
+      InvocationCount::Times(count) => json!({ "times": count }),
+      InvocationCount::AtLeast(count) => json!({ "atLeast": count }),
+      InvocationCount::AtMost(count) => json!({ "atMost": count })
+    }
+  }
+
+  /// Parses an invocation count from a JSON `Value` (i.e. `{ "atLeast": 2 }`). Returns `None` if
+  /// the JSON is not a valid invocation count.
+  pub fn from_json(json: &serde_json::Value) -> Option<InvocationCount> {
+    let count = |key: &str| json.get(key)
+      .and_then(|count| count.as_u64())
+      .map(|count| count as usize);
+    count("times").map(InvocationCount::Times)
+      .or_else(|| count("atLeast").map(InvocationCount::AtLeast))
+      .or_else(|| count("atMost").map(InvocationCount::AtMost))
+  }
+}
+
+impl Display for InvocationCount {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      InvocationCount::Times(count) => write!(f, "exactly {} times", count),
+      InvocationCount::AtLeast(count) => write!(f, "at least {} times", count),
+      InvocationCount::AtMost(count) => write!(f, "at most {} times", count)
+    }
+  }
+}
+
 fn mismatches_to_json(request: &Request, mismatches: &Vec<Mismatch>) -> serde_json::Value {
     json!({
         "type" : "request-mismatch",
@@ -111,27 +198,248 @@ fn mismatches_to_json(request: &Request, mismatches: &Vec<Mismatch>) -> serde_js
 /// Matches a request against a list of interactions
 ///
 pub fn match_request(req: &Request, interactions: Vec<&dyn Interaction>) -> MatchResult {
-  let mut match_results = interactions
-    .into_iter()
-    .filter(|i| i.is_request_response())
+  match_request_with_interaction(req, interactions).0
+}
+
+///
+/// Matches a request against a list of interactions, also returning the interaction that was
+/// the best match for the request (if there was one)
+///
+pub fn match_request_with_interaction<'a>(
+  req: &Request,
+  interactions: Vec<&'a dyn Interaction>
+) -> (MatchResult, Option<&'a dyn Interaction>) {
+  let interactions = http_interactions(interactions);
+  match best_match(req, &interactions) {
+    Some((index, interaction, result)) => {
+      let match_result = to_match_result(req, interaction, result);
+      match match_result {
+        MatchResult::RequestNotFound(_) => (match_result, None),
+        _ => (match_result, Some(interactions[index]))
+      }
+    },
+    None => (MatchResult::RequestNotFound(req.clone()), None)
+  }
+}
+
+///
+/// Matches a request against a list of interactions for a mock server running in stub mode. Any
+/// mismatches in the query parameters and headers are ignored, so the request matches the
+/// interaction that has the best match score out of the ones with the same method, path and body.
+///
+pub fn match_request_loosely<'a>(
+  req: &Request,
+  interactions: Vec<&'a dyn Interaction>
+) -> (MatchResult, Option<&'a dyn Interaction>) {
+  let best = http_interactions(interactions).into_iter()
     .map(|i| {
-      let interaction = i.as_request_response().unwrap();
-      (interaction.clone(), pact_matching::match_request(interaction.request.clone(), req.clone()))
+      let result = pact_matching::match_request(i.as_request_response().unwrap().request, req.clone());
+      (i, result)
     })
-    .sorted_by(|(_, i1), (_, i2)| {
-      Ord::cmp(&i2.score(), &i1.score())
-    });
-  match match_results.next() {
-    Some((interaction, result)) => {
-      let request_response_interaction = interaction.as_request_response().unwrap();
+    .filter(|(_, result)| result.mismatches().iter()
+      .all(|mismatch| matches!(mismatch, Mismatch::QueryMismatch { .. } | Mismatch::HeaderMismatch { .. })))
+    .sorted_by(|(_, r1), (_, r2)| Ord::cmp(&r2.score(), &r1.score()))
+    .next();
+  match best {
+    Some((i, _)) => {
+      let interaction = i.as_request_response().unwrap();
+      (MatchResult::RequestMatch(interaction.request, interaction.response), Some(i))
+    },
+    None => (MatchResult::RequestNotFound(req.clone()), None)
+  }
+}
+
+///
+/// Filters the interactions down to the ones that have the given provider state. If no provider
+/// state is given, all the interactions are returned.
+///
+pub fn interactions_for_provider_state<'a>(
+  interactions: Vec<&'a dyn Interaction>,
+  provider_state: &Option<String>
+) -> Vec<&'a dyn Interaction> {
+  match provider_state {
+    Some(state) => interactions.into_iter()
+      .filter(|i| i.provider_states().iter().any(|ps| &ps.name == state))
+      .collect(),
+    None => interactions
+  }
+}
+
+/// Progress through a list of interactions that are expected to be received in the order they
+/// are defined. Each interaction is expected to be received once.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InteractionOrder {
+  /// (Zero-based) position of the next interaction that is expected
+  pub next: usize,
+  /// Positions of the interactions that have been received
+  pub received: BTreeSet<usize>
+}
+
+///
+/// Matches a request against a list of interactions that are expected to be received in the
+/// order they are defined. The order will be updated as requests are matched. If the request
+/// matches an interaction at a different position, a `RequestOutOfOrder` result is returned, and
+/// the expected position is moved to after the interaction that matched. Interactions that have
+/// already been received are not matched again, so a request that only matches those is
+/// unexpected.
+///
+pub fn match_request_in_order<'a>(
+  req: &Request,
+  interactions: Vec<&'a dyn Interaction>,
+  order: &mut InteractionOrder
+) -> (MatchResult, Option<&'a dyn Interaction>) {
+  let interactions = http_interactions(interactions);
+  if let Some(interaction) = interactions.get(order.next) {
+    let expected = interaction.as_request_response().unwrap();
+    if pact_matching::match_request(expected.request.clone(), req.clone()).all_matched() {
+      order.received.insert(order.next);
+      order.next += 1;
+      return (MatchResult::RequestMatch(expected.request, expected.response), Some(*interaction));
+    }
+  }
+
+  let pending: Vec<usize> = (0..interactions.len())
+    .filter(|index| !order.received.contains(index))
+    .collect();
+  let pending_interactions: Vec<&dyn Interaction> = pending.iter().map(|index| interactions[*index]).collect();
+  match best_match(req, &pending_interactions) {
+    Some((index, interaction, result)) => {
+      let index = pending[index];
       if result.all_matched() {
-        MatchResult::RequestMatch(request_response_interaction.request, request_response_interaction.response)
-      } else if result.method_or_path_mismatch() {
-        MatchResult::RequestNotFound(req.clone())
+        let expected_position = order.next;
+        order.received.insert(index);
+        order.next = order.next.max(index + 1);
+        (MatchResult::RequestOutOfOrder(interaction.request, expected_position, index), Some(interactions[index]))
       } else {
-        MatchResult::RequestMismatch(request_response_interaction.request, result.mismatches())
+        let match_result = to_match_result(req, interaction, result);
+        match match_result {
+          MatchResult::RequestNotFound(_) => (match_result, None),
+          _ => (match_result, Some(interactions[index]))
+        }
       }
     },
-    None => MatchResult::RequestNotFound(req.clone())
+    None => (MatchResult::RequestNotFound(req.clone()), None)
+  }
+}
+
+fn http_interactions(interactions: Vec<&dyn Interaction>) -> Vec<&dyn Interaction> {
+  interactions.into_iter()
+    .filter(|i| i.is_request_response())
+    .collect()
+}
+
+fn best_match(
+  req: &Request,
+  interactions: &[&dyn Interaction]
+) -> Option<(usize, RequestResponseInteraction, RequestMatchResult)> {
+  interactions.iter()
+    .enumerate()
+    .map(|(index, i)| {
+      let interaction = i.as_request_response().unwrap();
+      let result = pact_matching::match_request(interaction.request.clone(), req.clone());
+      (index, interaction, result)
+    })
+    .sorted_by(|(_, _, r1), (_, _, r2)| {
+      Ord::cmp(&r2.score(), &r1.score())
+    })
+    .next()
+}
+
+fn to_match_result(req: &Request, interaction: RequestResponseInteraction, result: RequestMatchResult) -> MatchResult {
+  if result.all_matched() {
+    MatchResult::RequestMatch(interaction.request, interaction.response)
+  } else if result.method_or_path_mismatch() {
+    MatchResult::RequestNotFound(req.clone())
+  } else {
+    MatchResult::RequestMismatch(interaction.request, result.mismatches())
   }
 }
+
+/// Interaction that was one of the closest matches for a request that did not match any
+/// interaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchCandidate {
+  /// Description of the interaction
+  pub description: String,
+  /// Expected request of the interaction
+  pub request: Request,
+  /// Match score of the request against the interaction. Higher scores are closer matches.
+  pub score: i8,
+  /// Mismatches between the expected and received request
+  pub mismatches: Vec<Mismatch>
+}
+
+impl MatchCandidate {
+  /// Returns a line-based diff between the expected request and the received one. Only the
+  /// headers that the interaction expects are included for the received request.
+  pub fn diff(&self, actual: &Request) -> String {
+    let expected_headers = self.request.headers.as_ref()
+      .map(|headers| headers.keys().map(|key| key.to_lowercase()).collect::<Vec<String>>())
+      .unwrap_or_default();
+    let changeset = Changeset::new(
+      diff_view(&self.request, &expected_headers).as_str(),
+      diff_view(actual, &expected_headers).as_str(),
+      "\n"
+    );
+    let mut diff = vec![];
+    for change in &changeset.diffs {
+      let (prefix, lines) = match change {
+        Difference::Same(lines) => (" ", lines),
+        Difference::Rem(lines) => ("-", lines),
+        Difference::Add(lines) => ("+", lines)
+      };
+      diff.extend(lines.lines().map(|line| format!("{}{}", prefix, line)));
+    }
+    diff.join("\n")
+  }
+
+  /// Converts this candidate to a `Value` struct, including the diff against the received request
+  pub fn to_json(&self, actual: &Request) -> serde_json::Value {
+    json!({
+      "description": self.description,
+      "score": self.score,
+      "request": self.request.to_json(&PactSpecification::V3),
+      "mismatches": self.mismatches.iter().map(|m| m.to_json()).collect::<Vec<serde_json::Value>>(),
+      "diff": self.diff(actual)
+    })
+  }
+}
+
+/// Pretty printed JSON form of the request used for diffs, with only the given headers
+fn diff_view(request: &Request, header_names: &[String]) -> String {
+  let headers = request.headers.as_ref()
+    .map(|headers| headers.iter()
+      .filter(|(key, _)| header_names.contains(&key.to_lowercase()))
+      .map(|(key, values)| (key.to_lowercase(), values.clone()))
+      .collect::<HashMap<String, Vec<String>>>())
+    .filter(|headers| !headers.is_empty());
+  let request = Request { headers, .. request.clone() };
+  serde_json::to_string_pretty(&request.to_json(&PactSpecification::V3)).unwrap_or_default()
+}
+
+///
+/// Returns the interactions that are the closest matches for the request, ordered by their match
+/// score (best first). At most `limit` candidates are returned.
+///
+pub fn closest_candidates(req: &Request, interactions: Vec<&dyn Interaction>, limit: usize) -> Vec<MatchCandidate> {
+  http_interactions(interactions).iter()
+    .map(|i| {
+      let interaction = i.as_request_response().unwrap();
+      let result = pact_matching::match_request(interaction.request.clone(), req.clone());
+      MatchCandidate {
+        description: interaction.description.clone(),
+        request: interaction.request,
+        score: result.score(),
+        mismatches: result.mismatches()
+      }
+    })
+    .sorted_by(|c1, c2| Ord::cmp(&c2.score, &c1.score))
+    .take(limit)
+    .collect()
+}
+
+/// Returns the key for the interaction. V4 HTTP interactions that have not been assigned a key
+/// will have one calculated.
+pub fn interaction_key(interaction: &dyn Interaction) -> Option<String> {
+  interaction.as_v4_http().and_then(|i| i.key.clone().or_else(|| i.with_key().key))
+}
@@ -0,0 +1,289 @@
+//!
+//! The metrics module defines the metrics collected by a mock server, and how they are exported
+//! in the Prometheus text exposition format.
+//!
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::journal::RequestJournalEntry;
+
+/// Upper bounds (in seconds) of the buckets of the request duration histogram. These are the
+/// default buckets used by the Prometheus client libraries.
+pub const DURATION_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Metrics for the mock server
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct MockServerMetrics {
+  /// Total requests
+  pub requests: usize,
+  /// Number of requests that matched each interaction, keyed by the interaction description
+  #[serde(default)]
+  pub interaction_hits: BTreeMap<String, usize>,
+  /// Number of requests that did not match, keyed by the kind of match result
+  /// (i.e. `Request-Mismatch` or `Unexpected-Request`)
+  #[serde(default)]
+  pub mismatches: BTreeMap<String, usize>,
+  /// Histogram of the time taken to respond to the requests
+  #[serde(default)]
+  pub request_durations: DurationHistogram,
+  /// Total size in bytes of the request bodies, after any content encoding has been decoded. This
+  /// is the logical size of the bodies, not the number of bytes received on the connection.
+  #[serde(default)]
+  pub request_body_bytes: u64,
+  /// Total size in bytes of the response bodies as recorded in the request journal, before any
+  /// content encoding is applied. This is the logical size of the bodies, not the number of bytes
+  /// sent on the connection.
+  #[serde(default)]
+  pub response_body_bytes: u64
+}
+
+impl MockServerMetrics {
+  /// Updates the metrics with a request that has been handled by the mock server. Unexpected CORS
+  /// pre-flight requests are not counted as mismatches.
+  pub fn record(&mut self, entry: &RequestJournalEntry, cors_preflight: bool) {
+    if let Some(description) = &entry.interaction_description {
+      *self.interaction_hits.entry(description.clone()).or_default() += 1;
+    } else if !cors_preflight {
+      *self.mismatches.entry(entry.match_key.clone()).or_default() += 1;
+    }
+    self.request_durations.observe(entry.duration);
+    self.request_body_bytes += entry.request.body.value().map(|body| body.len()).unwrap_or_default() as u64;
+    self.response_body_bytes += entry.response.body.value().map(|body| body.len()).unwrap_or_default() as u64;
+  }
+}
+
+/// Histogram of durations, with the buckets from `DURATION_BUCKETS`
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct DurationHistogram {
+  /// Number of durations that fell in each bucket. These are not cumulative.
+  pub buckets: Vec<u64>,
+  /// Total number of durations observed
+  pub count: u64,
+  /// Sum of all the durations observed
+  pub sum: Duration
+}
+
+impl DurationHistogram {
+  /// Adds a duration to the histogram. If the histogram does not have a count for each bucket
+  /// (i.e. it was deserialised from metrics with fewer buckets), the missing counts are added.
+  pub fn observe(&mut self, duration: Duration) {
+    let seconds = duration.as_secs_f64();
+    if let Some(index) = DURATION_BUCKETS.iter().position(|bound| seconds <= *bound) {
+      if self.buckets.len() < DURATION_BUCKETS.len() {
+        self.buckets.resize(DURATION_BUCKETS.len(), 0);
+      }
+      self.buckets[index] += 1;
+    }
+    self.count += 1;
+    self.sum += duration;
+  }
+
+  /// Returns the cumulative counts for each bucket bound, as used by Prometheus. The `+Inf`
+  /// bucket is not included, as it is always equal to the count. Missing bucket counts are
+  /// treated as zero.
+  pub fn cumulative_buckets(&self) -> Vec<(f64, u64)> {
+    let mut total = 0;
+    DURATION_BUCKETS.iter().enumerate()
+      .map(|(index, bound)| {
+        total += self.buckets.get(index).cloned().unwrap_or_default();
+        (*bound, total)
+      })
+      .collect()
+  }
+}
+
+impl Default for DurationHistogram {
+  fn default() -> Self {
+    DurationHistogram {
+      buckets: vec![0; DURATION_BUCKETS.len()],
+      count: 0,
+      sum: Duration::default()
+    }
+  }
+}
+
+/// Metrics of a mock server, with the values of the labels that identify it
+#[derive(Debug, Clone)]
+pub struct LabelledMetrics {
+  /// Mock server unique ID
+  pub mock_server_id: String,
+  /// Name of the provider of the pact the mock server is based on
+  pub provider: String,
+  /// Metrics collected by the mock server
+  pub metrics: MockServerMetrics
+}
+
+/// Formats the metrics of a number of mock servers in the Prometheus text exposition format.
+/// Each sample is labelled with the ID of the mock server and the name of the provider.
+pub fn prometheus_text(mock_servers: &[LabelledMetrics]) -> String {
+  let mut buffer = String::new();
+
+  family(&mut buffer, "pact_mock_server_requests_total", "counter",
+    "Total number of requests received by the mock server");
+  for ms in mock_servers {
+    sample(&mut buffer, "pact_mock_server_requests_total", &labels(ms, &[]), ms.metrics.requests);
+  }
+
+  family(&mut buffer, "pact_mock_server_interaction_hits_total", "counter",
+    "Number of requests that matched each interaction");
+  for ms in mock_servers {
+    for (description, hits) in &ms.metrics.interaction_hits {
+      sample(&mut buffer, "pact_mock_server_interaction_hits_total",
+        &labels(ms, &[("interaction", description.as_str())]), hits);
+    }
+  }
+
+  family(&mut buffer, "pact_mock_server_mismatches_total", "counter",
+    "Number of requests that did not match an interaction, by kind of mismatch");
+  for ms in mock_servers {
+    for (kind, count) in &ms.metrics.mismatches {
+      sample(&mut buffer, "pact_mock_server_mismatches_total", &labels(ms, &[("kind", kind.as_str())]), count);
+    }
+  }
+
+  family(&mut buffer, "pact_mock_server_request_duration_seconds", "histogram",
+    "Time taken by the mock server to respond to requests");
+  for ms in mock_servers {
+    let histogram = &ms.metrics.request_durations;
+    for (bound, count) in histogram.cumulative_buckets() {
+      sample(&mut buffer, "pact_mock_server_request_duration_seconds_bucket",
+        &labels(ms, &[("le", bound.to_string().as_str())]), count);
+    }
+    sample(&mut buffer, "pact_mock_server_request_duration_seconds_bucket",
+      &labels(ms, &[("le", "+Inf")]), histogram.count);
+    sample(&mut buffer, "pact_mock_server_request_duration_seconds_sum", &labels(ms, &[]),
+      histogram.sum.as_secs_f64());
+    sample(&mut buffer, "pact_mock_server_request_duration_seconds_count", &labels(ms, &[]),
+      histogram.count);
+  }
+
+  family(&mut buffer, "pact_mock_server_request_body_bytes_total", "counter",
+    "Total size in bytes of the decoded request bodies");
+  for ms in mock_servers {
+    sample(&mut buffer, "pact_mock_server_request_body_bytes_total", &labels(ms, &[]), ms.metrics.request_body_bytes);
+  }
+
+  family(&mut buffer, "pact_mock_server_response_body_bytes_total", "counter",
+    "Total size in bytes of the response bodies, before any content encoding");
+  for ms in mock_servers {
+    sample(&mut buffer, "pact_mock_server_response_body_bytes_total", &labels(ms, &[]), ms.metrics.response_body_bytes);
+  }
+
+  buffer
+}
+
+fn family(buffer: &mut String, name: &str, metric_type: &str, help: &str) {
+  let _ = writeln!(buffer, "# HELP {} {}", name, help);
+  let _ = writeln!(buffer, "# TYPE {} {}", name, metric_type);
+}
+
+fn sample<V: ToString>(buffer: &mut String, name: &str, labels: &str, value: V) {
+  let _ = writeln!(buffer, "{}{{{}}} {}", name, labels, value.to_string());
+}
+
+fn labels(ms: &LabelledMetrics, extra: &[(&str, &str)]) -> String {
+  let mut labels = vec![
+    format!("mock_server=\"{}\"", escape_label(&ms.mock_server_id)),
+    format!("provider=\"{}\"", escape_label(&ms.provider))
+  ];
+  for (name, value) in extra {
+    labels.push(format!("{}=\"{}\"", name, escape_label(value)));
+  }
+  labels.join(",")
+}
+
+fn escape_label(value: &str) -> String {
+  value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use chrono::Local;
+  use expectest::prelude::*;
+
+  use pact_models::bodies::OptionalBody;
+  use pact_models::request::Request;
+  use pact_models::response::Response;
+
+  use crate::journal::RequestJournalEntry;
+
+  use super::*;
+
+  fn entry(description: Option<&str>, match_key: &str, duration: u64) -> RequestJournalEntry {
+    RequestJournalEntry {
+      request: Request { body: OptionalBody::from("1234"), .. Request::default() },
+      protocol_version: None,
+      received: Local::now(),
+      duration: Duration::from_millis(duration),
+      interaction_description: description.map(|d| d.to_string()),
+      interaction_key: None,
+      match_key: match_key.to_string(),
+      response: Response { body: OptionalBody::from("12"), .. Response::default() },
+      client_certificate_subject: None
+    }
+  }
+
+  #[test]
+  fn record_updates_the_metrics() {
+    let mut metrics = MockServerMetrics::default();
+    metrics.record(&entry(Some("a request"), "Request-Matched", 3), false);
+    metrics.record(&entry(Some("a request"), "Request-Matched", 20), false);
+    metrics.record(&entry(None, "Unexpected-Request", 2000), false);
+    metrics.record(&entry(None, "Unexpected-Request", 1), true);
+
+    expect!(metrics.interaction_hits.get("a request")).to(be_some().value(&2));
+    expect!(metrics.mismatches.get("Unexpected-Request")).to(be_some().value(&1));
+    expect!(metrics.request_durations.count).to(be_equal_to(4));
+    expect!(metrics.request_durations.cumulative_buckets()[0]).to(be_equal_to((0.005, 2)));
+    expect!(metrics.request_durations.cumulative_buckets()[2]).to(be_equal_to((0.025, 3)));
+    expect!(metrics.request_durations.cumulative_buckets()[8]).to(be_equal_to((2.5, 4)));
+    expect!(metrics.request_body_bytes).to(be_equal_to(16));
+    expect!(metrics.response_body_bytes).to(be_equal_to(8));
+  }
+
+  #[test]
+  fn observe_handles_a_deserialised_histogram_with_missing_buckets() {
+    let mut histogram: DurationHistogram = serde_json::from_str(
+      r#"{"buckets":[1,2],"count":3,"sum":{"secs":0,"nanos":30000000}}"#).unwrap();
+    histogram.observe(Duration::from_secs(2));
+
+    expect!(histogram.buckets.len()).to(be_equal_to(DURATION_BUCKETS.len()));
+    expect!(histogram.count).to(be_equal_to(4));
+    expect!(histogram.cumulative_buckets()[1]).to(be_equal_to((0.01, 3)));
+    expect!(histogram.cumulative_buckets()[8]).to(be_equal_to((2.5, 4)));
+
+    let histogram: DurationHistogram = serde_json::from_str(
+      r#"{"buckets":[1],"count":1,"sum":{"secs":0,"nanos":1000000}}"#).unwrap();
+    expect!(histogram.cumulative_buckets().len()).to(be_equal_to(DURATION_BUCKETS.len()));
+    expect!(histogram.cumulative_buckets()[10]).to(be_equal_to((10.0, 1)));
+  }
+
+  #[test]
+  fn prometheus_text_formats_the_metrics_of_all_the_mock_servers() {
+    let mut metrics = MockServerMetrics { requests: 2, .. MockServerMetrics::default() };
+    metrics.record(&entry(Some("a \"quoted\" request"), "Request-Matched", 3), false);
+    metrics.record(&entry(None, "Request-Mismatch", 3), false);
+    let text = prometheus_text(&[LabelledMetrics {
+      mock_server_id: "1234".to_string(),
+      provider: "Alice Service".to_string(),
+      metrics
+    }]);
+
+    let lines = text.lines().collect::<Vec<&str>>();
+    expect!(lines.contains(&"# TYPE pact_mock_server_requests_total counter")).to(be_true());
+    expect!(lines.contains(&"pact_mock_server_requests_total{mock_server=\"1234\",provider=\"Alice Service\"} 2")).to(be_true());
+    expect!(lines.contains(&"pact_mock_server_interaction_hits_total{mock_server=\"1234\",provider=\"Alice Service\",interaction=\"a \\\"quoted\\\" request\"} 1")).to(be_true());
+    expect!(lines.contains(&"pact_mock_server_mismatches_total{mock_server=\"1234\",provider=\"Alice Service\",kind=\"Request-Mismatch\"} 1")).to(be_true());
+    expect!(lines.contains(&"pact_mock_server_request_duration_seconds_bucket{mock_server=\"1234\",provider=\"Alice Service\",le=\"0.005\"} 2")).to(be_true());
+    expect!(lines.contains(&"pact_mock_server_request_duration_seconds_bucket{mock_server=\"1234\",provider=\"Alice Service\",le=\"+Inf\"} 2")).to(be_true());
+    expect!(lines.contains(&"pact_mock_server_request_duration_seconds_count{mock_server=\"1234\",provider=\"Alice Service\"} 2")).to(be_true());
+    expect!(lines.contains(&"pact_mock_server_request_body_bytes_total{mock_server=\"1234\",provider=\"Alice Service\"} 8")).to(be_true());
+    expect!(lines.contains(&"pact_mock_server_response_body_bytes_total{mock_server=\"1234\",provider=\"Alice Service\"} 4")).to(be_true());
+  }
+}
@@ -4,27 +4,107 @@
 //!
 
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::ffi::CString;
-use std::ops::DerefMut;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
+use futures::channel::oneshot;
 use log::*;
 use rustls::ServerConfig;
-use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-use pact_matching::models::{Pact, RequestResponsePact, write_pact};
+use pact_matching::models::{Interaction, Pact, RequestResponseInteraction, RequestResponsePact, write_pact};
+use pact_matching::models::v4::{V4Interaction, V4Pact};
+use pact_models::PactSpecification;
 use pact_models::request::Request;
+use pact_models::v4::V4InteractionType;
 
+use crate::cors::CorsPolicy;
+use crate::faults::FaultConfig;
+use crate::har::journal_to_har;
 use crate::hyper_server;
-use crate::matching::MatchResult;
+use crate::journal::RequestJournalEntry;
+use crate::matching::{interaction_key, InteractionOrder, InvocationCount, MatchResult};
+pub use crate::metrics::MockServerMetrics;
+use crate::observer::MockServerObserver;
+use crate::proxy::{self, UpstreamClient};
+use crate::streaming::StreamedBody;
 
 /// Mock server configuration
 #[derive(Debug, Default, Clone)]
 pub struct MockServerConfig {
   /// If CORS Pre-Flight requests should be responded to
-  pub cors_preflight: bool
+  pub cors_preflight: bool,
+  /// CORS policy that controls the CORS headers added to the responses. Headers defined in the
+  /// interaction responses are never overridden.
+  pub cors: CorsPolicy,
+  /// Expected number of invocations for interactions, keyed by the interaction description.
+  /// These take precedence over any invocation counts set in the interaction comments.
+  pub invocation_counts: HashMap<String, InvocationCount>,
+  /// If the interactions must be received in the order they are defined in the pact. This also
+  /// allows the same request to return different responses on successive calls.
+  pub ordered_interactions: bool,
+  /// Faults to inject into all the responses for matched requests
+  pub faults: Option<FaultConfig>,
+  /// Faults to inject into the responses for specific interactions, keyed by the interaction
+  /// description. These take precedence over the global faults.
+  pub interaction_faults: HashMap<String, FaultConfig>,
+  /// Base URL of an upstream provider to forward any unexpected requests to. The responses from
+  /// the upstream provider are returned, and recorded as new interactions in the pact.
+  pub upstream_url: Option<String>,
+  /// If HTTP/2 should be supported as well as HTTP/1.1. TLS mock servers will negotiate it with
+  /// ALPN (`h2`), otherwise cleartext HTTP/2 with prior knowledge (`h2c`) is accepted. If not set,
+  /// both are supported and TLS mock servers use the ALPN protocols of their TLS configuration.
+  /// Set it to false to only support HTTP/1.1.
+  pub http2: Option<bool>,
+  /// Response bodies to stream for specific interactions, keyed by the interaction description.
+  /// These take precedence over any streamed bodies set in the interaction comments. The body
+  /// truncation and drip-feed faults are applied to the chunks, and responses with a
+  /// Content-Encoding header can not be streamed.
+  pub streamed_bodies: HashMap<String, StreamedBody>,
+  /// Status code to return for requests that do not match any interaction. Defaults to 500, or
+  /// 404 in stub mode.
+  pub unmatched_status: Option<u16>,
+  /// Observers that are notified of the requests received by the mock server, the results of
+  /// matching them, and when the mock server is shut down
+  pub observers: Vec<Arc<dyn MockServerObserver>>,
+  /// If the mock server should run as a stub server. Requests are matched loosely against the
+  /// interactions (ignoring any mismatches in the query parameters and headers), and mismatches
+  /// are never recorded, so the mock server can be used without verifying it.
+  pub stub: bool,
+  /// Seed for the generators applied to the responses. When set, the generators produce the same
+  /// values on every run, instead of random ones. Date and time generators use a fixed time.
+  pub generator_seed: Option<u64>
+}
+
+impl MockServerConfig {
+  /// Returns the status code to return for requests that do not match any interaction
+  pub fn unmatched_status(&self) -> u16 {
+    self.unmatched_status.unwrap_or(if self.stub { 404 } else { 500 })
+  }
+
+  /// Returns the faults to inject into the response for the matched interaction. Requests that
+  /// did not match an interaction never have any faults injected.
+  pub fn faults_for(&self, interaction: Option<&dyn Interaction>) -> FaultConfig {
+    match interaction {
+      Some(interaction) => self.interaction_faults.get(&interaction.description())
+        .or_else(|| self.faults.as_ref())
+        .cloned()
+        .unwrap_or_default(),
+      None => FaultConfig::default()
+    }
+  }
+
+  /// Returns the body to stream for the matched interaction, if one has been set in the mock
+  /// server config or in the interaction comments (as `streamedBody`)
+  pub fn streamed_body_for(&self, interaction: Option<&dyn Interaction>) -> Option<StreamedBody> {
+    interaction.and_then(|interaction| {
+      self.streamed_bodies.get(&interaction.description()).cloned()
+        .or_else(|| interaction.as_v4_http()
+          .and_then(|i| i.comments.get("streamedBody").and_then(StreamedBody::from_json)))
+    })
+  }
 }
 
 /// Mock server scheme
@@ -51,13 +131,6 @@ impl ToString for MockServerScheme {
   }
 }
 
-/// Metrics for the mock server
-#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
-pub struct MockServerMetrics {
-  /// Total requests
-  pub requests: usize
-}
-
 /// Struct to represent the "foreground" part of mock server
 #[derive(Debug)]
 pub struct MockServer {
@@ -69,46 +142,91 @@ pub struct MockServer {
   pub port: Option<u16>,
   /// Address the mock server is bound to
   pub address: Option<String>,
+  /// Path of the Unix domain socket the mock server is bound to, if it is not bound to a port
+  pub unix_socket: Option<PathBuf>,
   /// List of resources that need to be cleaned up when the mock server completes
   pub resources: Vec<CString>,
   /// Pact that this mock server is based on
   pub pact: Arc<Mutex<dyn Pact + Send + Sync>>,
   /// Receiver of match results
   matches: Arc<Mutex<Vec<MatchResult>>>,
+  /// Journal of all the requests received
+  journal: Arc<Mutex<Vec<RequestJournalEntry>>>,
   /// Shutdown signal
   shutdown_tx: RefCell<Option<futures::channel::oneshot::Sender<()>>>,
   /// Mock server config
   pub config: MockServerConfig,
   /// Metrics collected by the mock server
-  pub metrics: MockServerMetrics
+  pub metrics: MockServerMetrics,
+  /// Progress through the interactions when they must be received in order
+  pub(crate) interaction_order: InteractionOrder,
+  /// Provider state that is currently active. Only interactions with this state will be matched.
+  pub(crate) provider_state: Option<String>,
+  /// HTTP interactions that are served by the mock server. Interactions recorded from the
+  /// upstream provider are added to these, and they are replaced when the interactions are
+  /// reloaded.
+  pub(crate) http_pact: Arc<V4Pact>,
+  /// Client used to forward requests to the upstream provider, created when it is first needed
+  upstream_client: Option<UpstreamClient>
 }
 
+/// State of a new mock server, with the match results that are shared with the server and the
+/// receiver for the shutdown signal
+type MockServerState = (Arc<Mutex<MockServer>>, Arc<Mutex<Vec<MatchResult>>>, oneshot::Receiver<()>);
+
 impl MockServer {
-  /// Create a new mock server, consisting of its state (self) and its executable server future.
-  pub async fn new(
-    id: String,
+  /// Creates the state of a new mock server that is not yet bound, returning it with the match
+  /// results that are shared with the server and the receiver for the shutdown signal.
+  fn create(
+    id: &str,
     pact: Box<dyn Pact>,
-    addr: std::net::SocketAddr,
+    scheme: MockServerScheme,
+    unix_socket: Option<PathBuf>,
     config: MockServerConfig
-  ) -> Result<(Arc<Mutex<MockServer>>, impl std::future::Future<Output = ()>), String> {
-    let (shutdown_tx, shutdown_rx) = futures::channel::oneshot::channel();
+  ) -> Result<MockServerState, String> {
+    let http_pact = Arc::new(http_interactions_pact(pact.as_ref())?);
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
     let matches = Arc::new(Mutex::new(vec![]));
 
     let mock_server = Arc::new(Mutex::new(MockServer {
-      id: id.clone(),
+      id: id.to_string(),
       port: None,
       address: None,
-      scheme: MockServerScheme::HTTP,
+      unix_socket,
+      scheme,
       resources: vec![],
       pact: pact.thread_safe(),
       matches: matches.clone(),
+      journal: Arc::new(Mutex::new(vec![])),
       shutdown_tx: RefCell::new(Some(shutdown_tx)),
-      config: config.clone(),
-      metrics: MockServerMetrics::default()
+      config,
+      metrics: MockServerMetrics::default(),
+      interaction_order: InteractionOrder::default(),
+      provider_state: None,
+      http_pact,
+      upstream_client: None
     }));
 
+    Ok((mock_server, matches, shutdown_rx))
+  }
+
+  /// Records the address the mock server has been bound to
+  fn bound_to(&mut self, socket_addr: std::net::SocketAddr) {
+    self.port = Some(socket_addr.port());
+    self.address = Some(socket_addr.ip().to_string());
+    debug!("Started mock server on {}:{}", socket_addr.ip(), socket_addr.port());
+  }
+
+  /// Create a new mock server, consisting of its state (self) and its executable server future.
+  pub async fn new(
+    id: String,
+    pact: Box<dyn Pact>,
+    addr: std::net::SocketAddr,
+    config: MockServerConfig
+  ) -> Result<(Arc<Mutex<MockServer>>, impl std::future::Future<Output = ()>), String> {
+    let (mock_server, matches, shutdown_rx) = MockServer::create(&id, pact, MockServerScheme::HTTP, None, config)?;
+
     let (future, socket_addr) = hyper_server::create_and_bind(
-      pact.as_request_response_pact().unwrap(),
       addr,
       async {
         shutdown_rx.await.ok();
@@ -120,13 +238,7 @@ impl MockServer {
       .await
       .map_err(|err| format!("Could not start server: {}", err))?;
 
-    {
-      let mut ms = mock_server.lock().unwrap();
-      ms.deref_mut().port = Some(socket_addr.port());
-      ms.deref_mut().address = Some(socket_addr.ip().to_string());
-
-      debug!("Started mock server on {}:{}", socket_addr.ip(), socket_addr.port());
-    }
+    mock_server.lock().unwrap().bound_to(socket_addr);
 
     Ok((mock_server.clone(), future))
   }
@@ -139,23 +251,9 @@ impl MockServer {
     tls: &ServerConfig,
     config: MockServerConfig
   ) -> Result<(Arc<Mutex<MockServer>>, impl std::future::Future<Output = ()>), String> {
-    let (shutdown_tx, shutdown_rx) = futures::channel::oneshot::channel();
-    let matches = Arc::new(Mutex::new(vec![]));
-    let mock_server = Arc::new(Mutex::new(MockServer {
-      id: id.clone(),
-      port: None,
-      address: None,
-      scheme: MockServerScheme::HTTPS,
-      resources: vec![],
-      pact: pact.thread_safe(),
-      matches: matches.clone(),
-      shutdown_tx: RefCell::new(Some(shutdown_tx)),
-      config: config.clone(),
-      metrics: MockServerMetrics::default()
-    }));
+    let (mock_server, matches, shutdown_rx) = MockServer::create(&id, pact, MockServerScheme::HTTPS, None, config)?;
 
     let (future, socket_addr) = hyper_server::create_and_bind_tls(
-      pact.as_request_response_pact().unwrap(),
       addr,
       async {
         shutdown_rx.await.ok();
@@ -165,18 +263,42 @@ impl MockServer {
       mock_server.clone()
     ).await.map_err(|err| format!("Could not start server: {}", err))?;
 
-    {
-      let mut ms = mock_server.lock().unwrap();
-      ms.deref_mut().port = Some(socket_addr.port());
-      ms.deref_mut().address = Some(socket_addr.ip().to_string());
+    mock_server.lock().unwrap().bound_to(socket_addr);
 
-      debug!("Started mock server on {}:{}", socket_addr.ip(), socket_addr.port());
-    }
+    Ok((mock_server.clone(), future))
+  }
+
+  /// Create a new mock server bound to a Unix domain socket, consisting of its state (self) and
+  /// its executable server future. The socket file will be removed when the server shuts down.
+  #[cfg(unix)]
+  pub async fn new_unix_socket(
+    id: String,
+    pact: Box<dyn Pact>,
+    path: PathBuf,
+    config: MockServerConfig
+  ) -> Result<(Arc<Mutex<MockServer>>, impl std::future::Future<Output = ()>), String> {
+    let (mock_server, matches, shutdown_rx) = MockServer::create(&id, pact, MockServerScheme::HTTP,
+      Some(path.clone()), config)?;
+
+    let future = hyper_server::create_and_bind_unix_socket(
+      path.clone(),
+      async {
+        shutdown_rx.await.ok();
+      },
+      matches,
+      mock_server.clone(),
+      &id
+    )
+      .await
+      .map_err(|err| format!("Could not start server on '{}': {}", path.display(), err))?;
+
+    debug!("Started mock server on Unix domain socket {}", path.display());
 
     Ok((mock_server.clone(), future))
   }
 
-  /// Send the shutdown signal to the server
+  /// Send the shutdown signal to the server. This does not notify the observers of the mock
+  /// server, use `MockServer::shutdown_and_notify` for that.
   pub fn shutdown(&mut self) -> Result<(), String> {
     let shutdown_future = &mut *self.shutdown_tx.borrow_mut();
     match shutdown_future.take() {
@@ -193,6 +315,27 @@ impl MockServer {
     }
   }
 
+  /// Send the shutdown signal to the server, and then notify the observers with the mismatches
+  /// that have occurred. The observers are notified once the lock on the mock server has been
+  /// released, so they are free to access it.
+  pub fn shutdown_and_notify(mock_server: &Arc<Mutex<MockServer>>) -> Result<(), String> {
+    let (id, observers, mismatches) = {
+      let mut guard = mock_server.lock()
+        .map_err(|_| "Mock server lock is poisoned".to_string())?;
+      guard.shutdown()?;
+      let mismatches = if guard.config.observers.is_empty() {
+        vec![]
+      } else {
+        guard.mismatches()
+      };
+      (guard.id.clone(), guard.config.observers.clone(), mismatches)
+    };
+    for observer in &observers {
+      observer.shutdown(&id, &mismatches);
+    }
+    Ok(())
+  }
+
     /// Converts this mock server to a `Value` struct
     pub fn to_json(&self) -> serde_json::Value {
       let pact = self.pact.lock().unwrap();
@@ -200,42 +343,221 @@ impl MockServer {
         "id" : self.id.clone(),
         "port" : self.port.unwrap_or_default() as u64,
         "address" : self.address.clone().unwrap_or_default(),
+        "unixSocket" : self.unix_socket.as_ref().map(|path| path.to_string_lossy().to_string()),
         "scheme" : self.scheme.to_string(),
         "provider" : pact.provider().name.clone(),
         "status" : if self.mismatches().is_empty() { "ok" } else { "error" },
-        "metrics" : self.metrics
+        "metrics" : self.metrics,
+        "providerState" : self.provider_state
       })
     }
 
+    /// Returns the provider state that is currently active, if any
+    pub fn provider_state(&self) -> Option<String> {
+      self.provider_state.clone()
+    }
+
+    /// Activates the provider state, so that only the interactions with that state will be
+    /// matched against received requests. Passing `None` will clear it, and all interactions will
+    /// be matched again. If the interactions must be received in order, the order is restarted.
+    pub fn set_provider_state(&mut self, provider_state: Option<String>) {
+      debug!("Mock server {} - setting provider state to {:?}", self.id, provider_state);
+      self.provider_state = provider_state;
+      self.interaction_order = InteractionOrder::default();
+    }
+
     /// Returns all collected matches
     pub fn matches(&self) -> Vec<MatchResult> {
         self.matches.lock().unwrap().clone()
     }
 
+    /// Returns all the requests received by this mock server, in the order they were received
+    pub fn journal(&self) -> Vec<RequestJournalEntry> {
+      self.journal.lock().unwrap().clone()
+    }
+
+    /// Exports all the requests received by this mock server, and the responses that were sent
+    /// back, as an HTTP Archive (HAR 1.2) document
+    pub fn to_har(&self) -> serde_json::Value {
+      journal_to_har(&self.journal(), &self.url())
+    }
+
+    /// Records a request received by this mock server in the journal
+    pub(crate) fn record_request(&self, entry: RequestJournalEntry) {
+      self.journal.lock().unwrap().push(entry);
+    }
+
+    /// Returns the client to forward requests to the upstream provider with. It is created the
+    /// first time it is needed, and then reused for all the forwarded requests.
+    pub(crate) fn upstream_client(&mut self) -> UpstreamClient {
+      self.upstream_client.get_or_insert_with(proxy::upstream_client).clone()
+    }
+
+    /// Records an interaction created from a request forwarded to the upstream provider in the
+    /// pact for this mock server, and adds it to the interactions that are served, so that the
+    /// same request is not forwarded again. If the pact already has an interaction for the same
+    /// request, nothing is recorded. Returns the recorded interaction.
+    pub(crate) fn record_interaction(&mut self, interaction: RequestResponseInteraction) -> RequestResponseInteraction {
+      let mut pact = self.pact.lock().unwrap();
+      let interactions = pact.interactions().iter()
+        .filter_map(|i| i.as_request_response())
+        .collect::<Vec<RequestResponseInteraction>>();
+      if interactions.iter().any(|i| i.request == interaction.request) {
+        return interaction;
+      }
+
+      let mut description = interaction.description.clone();
+      let mut count = 1;
+      while interactions.iter().any(|i| i.description == description) {
+        count += 1;
+        description = format!("{} ({})", interaction.description, count);
+      }
+      let interaction = RequestResponseInteraction { description, .. interaction };
+      info!("Recording interaction '{}' from the upstream provider", interaction.description);
+      if let Err(err) = pact.add_interaction(&interaction) {
+        warn!("Failed to record interaction '{}' - {}", interaction.description, err);
+        return interaction;
+      }
+
+      if let Some(v4_interaction) = interaction.as_v4() {
+        let mut http_pact = self.http_pact.as_ref().clone();
+        http_pact.interactions.push(v4_interaction);
+        self.http_pact = Arc::new(http_pact);
+      }
+      interaction
+    }
+
+    /// Adds the interaction to the running mock server. If the mock server already has an
+    /// interaction with the same description, it is replaced. The interactions are also updated
+    /// in the pact that will be written out.
+    pub fn upsert_interaction(&mut self, interaction: &dyn Interaction) -> Result<(), String> {
+      let v4_interaction = interaction.as_v4()
+        .ok_or_else(|| format!("Interaction '{}' could not be converted into V4 format", interaction.description()))?;
+      self.update_interactions(|interactions| {
+        match interactions.iter().position(|i| i.description() == v4_interaction.description()) {
+          Some(index) => {
+            info!("Replacing interaction '{}'", v4_interaction.description());
+            interactions[index] = v4_interaction;
+          },
+          None => {
+            info!("Adding interaction '{}'", v4_interaction.description());
+            interactions.push(v4_interaction);
+          }
+        }
+        true
+      }).map(|_| ())
+    }
+
+    /// Removes the interaction with the given description from the running mock server. Returns
+    /// false if the mock server does not have an interaction with that description.
+    pub fn remove_interaction(&mut self, description: &str) -> Result<bool, String> {
+      self.update_interactions(|interactions| {
+        let count = interactions.len();
+        interactions.retain(|i| i.description() != description);
+        info!("Removed {} interaction(s) with description '{}'", count - interactions.len(), description);
+        interactions.len() < count
+      })
+    }
+
+    /// Replaces all the interactions of the running mock server with the ones from the pact
+    pub fn replace_interactions(&mut self, pact: &dyn Pact) -> Result<(), String> {
+      let v4_pact = pact.as_v4_pact()
+        .map_err(|err| format!("Could not convert the pact into V4 format - {}", err))?;
+      info!("Replacing all interactions with {} interaction(s)", v4_pact.interactions.len());
+      self.update_interactions(|interactions| {
+        *interactions = v4_pact.interactions.clone();
+        true
+      }).map(|_| ())
+    }
+
+    /// Applies the update to the interactions of the pact, and then updates the interactions
+    /// served by the mock server. The pact keeps its specification version.
+    fn update_interactions<F>(&mut self, update: F) -> Result<bool, String>
+      where F: FnOnce(&mut Vec<Box<dyn V4Interaction>>) -> bool {
+      let (mut v4_pact, spec_version) = {
+        let pact = self.pact.lock().unwrap();
+        let v4_pact = pact.as_v4_pact()
+          .map_err(|err| format!("Could not convert the pact into V4 format - {}", err))?;
+        (v4_pact, pact.specification_version())
+      };
+      if !update(&mut v4_pact.interactions) {
+        return Ok(false);
+      }
+
+      let http_pact = http_interactions_pact(&v4_pact)?;
+      self.pact = if spec_version >= PactSpecification::V4 {
+        v4_pact.thread_safe()
+      } else {
+        let pact = v4_pact.as_request_response_pact()
+          .map_err(|err| format!("Could not convert the pact into V3 format - {}", err))?;
+        RequestResponsePact { specification_version: spec_version, .. pact }.thread_safe()
+      };
+      self.http_pact = Arc::new(http_pact);
+      self.interaction_order = InteractionOrder::default();
+      Ok(true)
+    }
+
     /// Returns all the mismatches that have occurred with this mock server
     pub fn mismatches(&self) -> Vec<MatchResult> {
+      if self.config.stub {
+        return vec![];
+      }
+
       let matches = self.matches();
       let mismatches = matches.iter()
         .filter(|m| !m.matched() && !m.cors_preflight())
         .map(|m| m.clone());
-      let requests: Vec<Request> = matches.iter().map(|m| {
+      let mut requests: Vec<Request> = matches.iter().map(|m| {
         match m {
           MatchResult::RequestMatch(request, _) => Some(request),
           MatchResult::RequestMismatch(request, _) => Some(request),
           MatchResult::RequestNotFound(_) => None,
-          MatchResult::MissingRequest(_) => None
+          MatchResult::MissingRequest(_) => None,
+          MatchResult::InvocationCountMismatch(_, _, _) => None,
+          MatchResult::RequestOutOfOrder(request, _, _) => Some(request)
         }
       }).filter(|o| o.is_some()).map(|o| o.unwrap().clone()).collect();
 
+      let journal = self.journal();
       let pact = self.pact.lock().unwrap();
       let interactions = pact.interactions();
       let missing = interactions.iter()
-        .map(|i| i.as_request_response().unwrap().request)
-        .filter(|req| !requests.contains(req))
-        .map(|req| MatchResult::MissingRequest(req.clone()));
+        .filter(|i| i.is_request_response())
+        .filter_map(|i| {
+          let request = i.as_request_response().unwrap().request;
+          match self.expected_invocations(*i) {
+            Some(expected) => {
+              let key = interaction_key(*i);
+              let actual = journal.iter()
+                .filter(|entry| entry.matched() && entry.interaction_key == key)
+                .count();
+              if expected.matches(actual) {
+                None
+              } else {
+                Some(MatchResult::InvocationCountMismatch(request, expected, actual))
+              }
+            },
+            // Each received request only accounts for one of the interactions with the same request
+            None => match requests.iter().position(|r| *r == request) {
+              Some(position) => {
+                requests.remove(position);
+                None
+              },
+              None => Some(MatchResult::MissingRequest(request))
+            }
+          }
+        });
       mismatches.chain(missing).collect()
     }
 
+  /// Returns the number of times the interaction is expected to be invoked, if it has been set
+  /// in the mock server config or in the interaction comments (as `invocationCount`)
+  fn expected_invocations(&self, interaction: &dyn Interaction) -> Option<InvocationCount> {
+    self.config.invocation_counts.get(&interaction.description()).cloned()
+      .or_else(|| interaction.as_v4_http()
+        .and_then(|i| i.comments.get("invocationCount").and_then(InvocationCount::from_json)))
+  }
+
   /// Mock server writes its pact out to the provided directory
   pub fn write_pact(&self, output_path: &Option<String>, overwrite: bool) -> anyhow::Result<()> {
     let pact = self.pact.lock().unwrap().boxed();
@@ -263,14 +585,35 @@ impl MockServer {
     /// Returns the URL of the mock server
     pub fn url(&self) -> String {
       let addr = self.address.clone().unwrap_or_else(|| "127.0.0.1".to_string());
-      match self.port {
-        Some(port) => format!("{}://{}:{}", self.scheme.to_string(),
+      match (self.port, &self.unix_socket) {
+        (Some(port), _) => format!("{}://{}:{}", self.scheme.to_string(),
           if addr == "0.0.0.0" { "127.0.0.1" } else { addr.as_str() }, port),
-        None => "error(port is not set)".to_string()
+        (None, Some(path)) => format!("unix://{}", path.display()),
+        (None, None) => "error(port is not set)".to_string()
       }
     }
 }
 
+/// Converts the pact into a V4 pact that only contains the synchronous HTTP interactions, as those
+/// are the only ones the mock server can serve. Any other interactions are ignored with a warning.
+fn http_interactions_pact(pact: &dyn Pact) -> Result<V4Pact, String> {
+  let v4_pact = pact.as_v4_pact()
+    .map_err(|err| format!("Could not convert the pact into V4 format - {}", err))?;
+  let interactions = v4_pact.interactions.iter()
+    .filter(|interaction| {
+      if interaction.v4_type() == V4InteractionType::Synchronous_HTTP {
+        true
+      } else {
+        warn!("Ignoring interaction '{}' as it is a {} interaction, and the mock server only supports HTTP",
+          interaction.description(), interaction.v4_type());
+        false
+      }
+    })
+    .cloned()
+    .collect();
+  Ok(V4Pact { interactions, .. v4_pact })
+}
+
 impl Clone for MockServer {
   /// Make a clone all of the MockServer fields.
   /// Note that the clone of the original server cannot be shut down directly.
@@ -279,13 +622,19 @@ impl Clone for MockServer {
       id: self.id.clone(),
       port: self.port,
       address: self.address.clone(),
+      unix_socket: self.unix_socket.clone(),
       scheme: self.scheme.clone(),
       resources: vec![],
       pact: self.pact.clone(),
       matches: self.matches.clone(),
+      journal: self.journal.clone(),
       shutdown_tx: RefCell::new(None),
       config: self.config.clone(),
-      metrics: self.metrics.clone()
+      metrics: self.metrics.clone(),
+      interaction_order: self.interaction_order.clone(),
+      provider_state: self.provider_state.clone(),
+      http_pact: self.http_pact.clone(),
+      upstream_client: self.upstream_client.clone()
     }
   }
 }
@@ -297,12 +646,18 @@ impl Default for MockServer {
       scheme: Default::default(),
       port: None,
       address: None,
+      unix_socket: None,
       resources: vec![],
       pact: Arc::new(Mutex::new(RequestResponsePact::default())),
       matches: Arc::new(Mutex::new(vec![])),
+      journal: Arc::new(Mutex::new(vec![])),
       shutdown_tx: RefCell::new(None),
       config: Default::default(),
-      metrics: Default::default()
+      metrics: Default::default(),
+      interaction_order: InteractionOrder::default(),
+      provider_state: None,
+      http_pact: Arc::new(V4Pact::default()),
+      upstream_client: None
     }
   }
 }
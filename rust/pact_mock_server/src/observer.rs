@@ -0,0 +1,55 @@
+//!
+//! The observer module defines callbacks that are notified of the events in the lifecycle of a
+//! mock server, as they happen.
+//!
+
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+
+use pact_models::request::Request;
+use pact_models::response::Response;
+
+use crate::matching::MatchResult;
+
+/// Observer of the events of a mock server. Observers are registered with the `observers`
+/// attribute of the `MockServerConfig`. All the callbacks default to doing nothing.
+///
+/// The callbacks are invoked on the threads of the mock server while it is handling a request, so
+/// they should return quickly. Panicking in a callback drops the connection to the client.
+pub trait MockServerObserver: Send + Sync {
+  /// Called when the mock server has received a request, before it is matched
+  fn request_received(&self, _mock_server_id: &str, _request: &Request) {}
+
+  /// Called when a request matched an interaction, with the response that is sent back
+  fn request_matched(&self, _mock_server_id: &str, _request: &Request, _result: &MatchResult, _response: &Response) {}
+
+  /// Called when a request did not match any interaction, with the response that is sent back.
+  /// Unexpected CORS pre-flight requests are not treated as mismatches.
+  fn request_mismatched(&self, _mock_server_id: &str, _request: &Request, _result: &MatchResult, _response: &Response) {}
+
+  /// Called when the mock server is shut down, with all the mismatches that have occurred
+  fn shutdown(&self, _mock_server_id: &str, _mismatches: &[MatchResult]) {}
+}
+
+impl Debug for dyn MockServerObserver {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "MockServerObserver")
+  }
+}
+
+/// Notifies the observers that a request has been handled, as either a match or a mismatch
+pub(crate) fn notify_request_handled(
+  observers: &[Arc<dyn MockServerObserver>],
+  mock_server_id: &str,
+  request: &Request,
+  result: &MatchResult,
+  response: &Response
+) {
+  for observer in observers {
+    if result.matched() {
+      observer.request_matched(mock_server_id, request, result, response);
+    } else if !result.cors_preflight() {
+      observer.request_mismatched(mock_server_id, request, result, response);
+    }
+  }
+}
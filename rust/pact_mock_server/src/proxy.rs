@@ -0,0 +1,127 @@
+//!
+//! The proxy module forwards requests that did not match any interaction to an upstream
+//! provider, so that the responses can be recorded as new interactions.
+//!
+
+use std::collections::HashMap;
+
+use hyper::{Body, Client};
+use hyper::client::HttpConnector;
+use hyper::http::header::{HeaderName, HeaderValue};
+use hyper_rustls::HttpsConnector;
+use log::*;
+
+use pact_matching::content_encoding::{decode_body, encode_body};
+use pact_matching::models::RequestResponseInteraction;
+use pact_models::bodies::OptionalBody;
+use pact_models::http_parts::HttpPart;
+use pact_models::query_strings::build_query_string;
+use pact_models::request::Request;
+use pact_models::response::Response;
+
+/// Headers that only apply to a single connection, and so are not forwarded or recorded
+const HOP_BY_HOP_HEADERS: [&str; 6] = [
+  "connection", "content-length", "host", "keep-alive", "transfer-encoding", "upgrade"
+];
+
+/// HTTP client used to forward requests to the upstream provider
+pub(crate) type UpstreamClient = Client<HttpsConnector<HttpConnector>, Body>;
+
+/// Creates the client to forward requests to the upstream provider with. The client keeps a pool
+/// of connections, so it should be reused for all the forwarded requests.
+pub(crate) fn upstream_client() -> UpstreamClient {
+  Client::builder().build::<_, Body>(HttpsConnector::with_native_roots())
+}
+
+/// Forwards the request to the upstream base URL using the client, and returns the response from
+/// the upstream provider
+pub(crate) async fn forward_request(
+  client: &UpstreamClient,
+  upstream_url: &str,
+  request: &Request
+) -> Result<Response, String> {
+  let mut url = format!("{}{}", upstream_url.trim_end_matches('/'), request.path);
+  if let Some(query) = &request.query {
+    url.push('?');
+    url.push_str(build_query_string(query.clone()).as_str());
+  }
+  debug!("Forwarding request {} to {}", request, url);
+
+  let mut builder = hyper::Request::builder()
+    .method(request.method.as_str())
+    .uri(url.as_str());
+  if let Some(headers) = &request.headers {
+    let hyper_headers = builder.headers_mut().unwrap();
+    for (name, values) in headers {
+      if HOP_BY_HOP_HEADERS.contains(&name.to_lowercase().as_str()) {
+        continue;
+      }
+      let header_name = HeaderName::from_bytes(name.as_bytes())
+        .map_err(|err| format!("Invalid header name '{}' - {}", name, err))?;
+      for value in values {
+        let header_value = HeaderValue::from_str(value.as_str())
+          .map_err(|err| format!("Invalid header value for '{}' - {}", name, err))?;
+        hyper_headers.append(header_name.clone(), header_value);
+      }
+    }
+  }
+  // The mock server decodes the request body, so it is encoded again before being forwarded
+  let body = match encode_body(&request.body, &request.headers)
+    .map_err(|err| format!("Failed to encode the request body - {}", err))? {
+    OptionalBody::Present(bytes, _) => Body::from(bytes),
+    _ => Body::empty()
+  };
+  let upstream_request = builder.body(body)
+    .map_err(|err| format!("Failed to create the upstream request - {}", err))?;
+
+  let upstream_response = client.request(upstream_request).await
+    .map_err(|err| format!("Failed to forward the request to '{}' - {}", url, err))?;
+
+  let status = upstream_response.status().as_u16();
+  let mut headers: HashMap<String, Vec<String>> = HashMap::new();
+  for (name, value) in upstream_response.headers() {
+    if HOP_BY_HOP_HEADERS.contains(&name.as_str()) {
+      continue;
+    }
+    match value.to_str() {
+      Ok(value) => headers.entry(name.to_string()).or_default().push(value.to_string()),
+      Err(err) => warn!("Ignoring upstream response header '{}' as it is not valid - {}", name, err)
+    }
+  }
+  let bytes = hyper::body::to_bytes(upstream_response.into_body()).await
+    .map_err(|err| format!("Failed to read the upstream response body - {}", err))?;
+
+  let response = Response {
+    status,
+    headers: if headers.is_empty() { None } else { Some(headers) },
+    .. Response::default()
+  };
+  let body = if bytes.is_empty() {
+    OptionalBody::Empty
+  } else {
+    let body = decode_body(&OptionalBody::Present(bytes, None), &response.headers)
+      .map_err(|err| format!("Failed to decode the upstream response body - {}", err))?;
+    match body {
+      OptionalBody::Present(bytes, _) => OptionalBody::Present(bytes, response.content_type()),
+      body => body
+    }
+  };
+  Ok(Response { body, .. response })
+}
+
+/// Creates a new interaction from the forwarded request and the response from the upstream
+/// provider
+pub(crate) fn recorded_interaction(request: &Request, response: &Response) -> RequestResponseInteraction {
+  let headers = request.headers.as_ref()
+    .map(|headers| headers.iter()
+      .filter(|(name, _)| !HOP_BY_HOP_HEADERS.contains(&name.to_lowercase().as_str()))
+      .map(|(name, values)| (name.clone(), values.clone()))
+      .collect::<HashMap<String, Vec<String>>>())
+    .filter(|headers| !headers.is_empty());
+  RequestResponseInteraction {
+    description: format!("{} {}", request.method, request.path),
+    request: Request { headers, .. request.clone() },
+    response: response.clone(),
+    .. RequestResponseInteraction::default()
+  }
+}
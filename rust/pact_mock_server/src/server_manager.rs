@@ -3,25 +3,26 @@
 //!
 
 use std::collections::BTreeMap;
+use std::fs;
 use std::net::SocketAddr;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 use log::*;
 use rustls::ServerConfig;
 
-use pact_matching::models::Pact;
+use pact_matching::models::{Interaction, Pact, read_pact};
 
+use crate::aggregator::PactAggregator;
+use crate::handle::MockServerHandle;
 use crate::mock_server::{MockServer, MockServerConfig};
 
-struct ServerEntry {
-  mock_server: Arc<Mutex<MockServer>>,
-  join_handle: tokio::task::JoinHandle<()>,
-}
-
 /// Struct to represent many mock servers running in a background thread
 pub struct ServerManager {
     runtime: tokio::runtime::Runtime,
-    mock_servers: BTreeMap<String, ServerEntry>,
+    mock_servers: BTreeMap<String, MockServerHandle>,
+    aggregator: PactAggregator
 }
 
 impl ServerManager {
@@ -34,7 +35,8 @@ impl ServerManager {
         .enable_all()
         .build()
         .unwrap(),
-      mock_servers: BTreeMap::new()
+      mock_servers: BTreeMap::new(),
+      aggregator: PactAggregator::new()
     }
   }
 
@@ -46,17 +48,10 @@ impl ServerManager {
       addr: SocketAddr,
       config: MockServerConfig
     ) -> Result<SocketAddr, String> {
-      let (mock_server, future) =
-        self.runtime.block_on(MockServer::new(id.clone(), pact, addr, config))?;
-
-      let port = { mock_server.lock().unwrap().port.clone() };
-      self.mock_servers.insert(
-        id,
-        ServerEntry {
-          mock_server,
-          join_handle: self.runtime.spawn(future),
-        },
-      );
+      let handle = self.runtime.block_on(MockServerHandle::start_with_id(id.clone(), pact, addr, config))?;
+
+      let port = handle.port();
+      self.mock_servers.insert(id, handle);
 
       match port {
         Some(port) => Ok(SocketAddr::new(addr.ip(), port)),
@@ -73,17 +68,11 @@ impl ServerManager {
       tls_config: &ServerConfig,
       config: MockServerConfig
     ) -> Result<SocketAddr, String> {
-      let (mock_server, future) =
-        self.runtime.block_on(MockServer::new_tls(id.clone(), pact, addr, tls_config, config))?;
-
-      let port = { mock_server.lock().unwrap().port.clone() };
-      self.mock_servers.insert(
-        id,
-        ServerEntry {
-          mock_server,
-          join_handle: self.runtime.spawn(future),
-        }
-      );
+      let handle = self.runtime.block_on(
+        MockServerHandle::start_tls_with_id(id.clone(), pact, addr, tls_config, config))?;
+
+      let port = handle.port();
+      self.mock_servers.insert(id, handle);
 
       match port {
         Some(port) => Ok(SocketAddr::new(addr.ip(), port)),
@@ -103,6 +92,22 @@ impl ServerManager {
             .map(|addr| addr.port())
     }
 
+  /// Start a new server bound to a Unix domain socket on the runtime
+  #[cfg(unix)]
+  pub fn start_mock_server_with_unix_socket(
+    &mut self,
+    id: String,
+    pact: Box<dyn Pact>,
+    path: &Path,
+    config: MockServerConfig
+  ) -> Result<(), String> {
+    let handle = self.runtime.block_on(
+      MockServerHandle::start_with_unix_socket(id.clone(), pact, path, config))?;
+    self.mock_servers.insert(id, handle);
+
+    Ok(())
+  }
+
   /// Start a new server on the runtime, returning the future
   pub async fn start_mock_server_nonblocking(
     &mut self,
@@ -117,13 +122,8 @@ impl ServerManager {
     let port = {
       mock_server.lock().unwrap().port.clone()
     };
-    self.mock_servers.insert(
-      id,
-      ServerEntry {
-        mock_server,
-        join_handle: self.runtime.spawn(future),
-      },
-    );
+    let join_handle = self.runtime.spawn(future);
+    self.mock_servers.insert(id, MockServerHandle::from_parts(mock_server, join_handle));
 
     port.ok_or_else(|| "Started mock server has no port".to_string())
   }
@@ -144,15 +144,18 @@ impl ServerManager {
     /// Shut down a server by its id
     pub fn shutdown_mock_server_by_id(&mut self, id: String) -> bool {
       match self.mock_servers.remove(&id) {
-        Some(entry) => {
-          let mut ms = entry.mock_server.lock().unwrap();
-          debug!("Shutting down mock server with ID {} - {:?}", id, ms.metrics);
-          match ms.shutdown() {
-            Ok(()) => {
-              self.runtime.block_on(entry.join_handle).unwrap();
-              true
+        Some(handle) => {
+          debug!("Shutting down mock server with ID {} - {:?}", id, handle.with_mock_server(|ms| ms.metrics.clone()));
+          let aggregator = &mut self.aggregator;
+          if let Err(err) = handle.with_mock_server(|ms| aggregator.add_mock_server(ms)) {
+            warn!("Failed to collect the interactions of mock server with ID {} - {}", id, err);
+          }
+          match self.runtime.block_on(handle.shutdown()) {
+            Ok(()) => true,
+            Err(err) => {
+              warn!("Failed to shut down mock server with ID {} - {}", id, err);
+              false
             }
-            Err(_) => false,
           }
         },
         None => false,
@@ -165,24 +168,26 @@ impl ServerManager {
       let result = self
         .mock_servers
         .iter()
-        .find(|(_id, entry)| entry.mock_server.lock().unwrap().port.unwrap_or_default() == port)
-        .map(|(_id, entry)| entry.mock_server.lock().unwrap().id.clone());
-
-      if let Some(id) = result {
-        if let Some(entry) = self.mock_servers.remove(&id) {
-          let mut ms = entry.mock_server.lock().unwrap();
-          debug!("Shutting down mock server with port {} - {:?}", port, ms.metrics);
-          return match ms.shutdown() {
-            Ok(()) => {
-              self.runtime.block_on(entry.join_handle).unwrap();
-              true
-            }
-            Err(_) => false,
-          };
-        }
+        .find(|(_id, entry)| entry.mock_server().lock().unwrap().port == Some(port))
+        .map(|(id, _entry)| id.clone());
+
+      match result {
+        Some(id) => self.shutdown_mock_server_by_id(id),
+        None => false
       }
+    }
 
-      false
+    /// Shut down a server by the path of the Unix domain socket it is bound to
+    pub fn shutdown_mock_server_by_unix_socket(&mut self, path: &Path) -> bool {
+      debug!("Shutting down mock server with Unix domain socket {}", path.display());
+      let result = self.mock_servers.iter()
+        .find(|(_id, entry)| entry.mock_server().lock().unwrap().unix_socket.as_deref() == Some(path))
+        .map(|(id, _entry)| id.clone());
+
+      match result {
+        Some(id) => self.shutdown_mock_server_by_id(id),
+        None => false
+      }
     }
 
     /// Find mock server by id, and map it using supplied function if found
@@ -192,7 +197,7 @@ impl ServerManager {
       f: &dyn Fn(&MockServer) -> R,
     ) -> Option<R> {
       match self.mock_servers.get(id) {
-        Some(entry) => Some(f(&entry.mock_server.lock().unwrap())),
+        Some(entry) => Some(f(&entry.mock_server().lock().unwrap())),
         None => None,
       }
     }
@@ -206,18 +211,147 @@ impl ServerManager {
       match self
         .mock_servers
         .iter_mut()
-        .find(|(_id, entry)| entry.mock_server.lock().unwrap().port.unwrap_or_default() == port)
+        .find(|(_id, entry)| entry.mock_server().lock().unwrap().port == Some(port))
       {
-        Some((_id, entry)) => Some(f(&mut entry.mock_server.lock().unwrap())),
+        Some((_id, entry)) => Some(f(&mut entry.mock_server().lock().unwrap())),
         None => None,
       }
     }
 
+    /// Activates the provider state on the mock server with the given id, so that only the
+    /// interactions with that state will be matched. Passing `None` clears the provider state.
+    /// Returns false if there is no mock server with the id.
+    pub fn set_mock_server_provider_state(&mut self, id: &String, provider_state: Option<String>) -> bool {
+      match self.mock_servers.get(id) {
+        Some(entry) => {
+          entry.mock_server().lock().unwrap().set_provider_state(provider_state);
+          true
+        },
+        None => false
+      }
+    }
+
+    /// Adds the interaction to the running mock server with the given id, replacing any
+    /// interaction with the same description. The mock server keeps running on the same port.
+    pub fn upsert_mock_server_interaction(&mut self, id: &String, interaction: &dyn Interaction) -> Result<(), String> {
+      match self.mock_servers.get(id) {
+        Some(entry) => entry.mock_server().lock().unwrap().upsert_interaction(interaction),
+        None => Err(format!("No mock server with id '{}'", id))
+      }
+    }
+
+    /// Removes the interaction with the given description from the running mock server with the
+    /// given id. Returns false if the mock server does not have an interaction with that
+    /// description.
+    pub fn remove_mock_server_interaction(&mut self, id: &String, description: &str) -> Result<bool, String> {
+      match self.mock_servers.get(id) {
+        Some(entry) => entry.mock_server().lock().unwrap().remove_interaction(description),
+        None => Err(format!("No mock server with id '{}'", id))
+      }
+    }
+
+    /// Replaces all the interactions of the running mock server with the given id with the ones
+    /// from the pact
+    pub fn replace_mock_server_interactions(&mut self, id: &String, pact: &dyn Pact) -> Result<(), String> {
+      match self.mock_servers.get(id) {
+        Some(entry) => entry.mock_server().lock().unwrap().replace_interactions(pact),
+        None => Err(format!("No mock server with id '{}'", id))
+      }
+    }
+
+    /// Watches the pact file, and reloads the interactions of the running mock server with the
+    /// given id whenever the file is modified. The file is checked at the given interval until
+    /// the mock server is shut down.
+    pub fn watch_mock_server_pact_file(&mut self, id: &String, path: &Path, interval: Duration) -> Result<(), String> {
+      let mock_server = match self.mock_servers.get(id) {
+        Some(entry) => Arc::downgrade(entry.mock_server()),
+        None => return Err(format!("No mock server with id '{}'", id))
+      };
+      let path = path.to_path_buf();
+      let mut last_modified = fs::metadata(&path).and_then(|md| md.modified())
+        .map_err(|err| format!("Could not watch pact file '{}' - {}", path.display(), err))?;
+
+      debug!("Watching pact file {} for mock server {}", path.display(), id);
+      self.runtime.spawn(async move {
+        let mut timer = tokio::time::interval(interval);
+        loop {
+          timer.tick().await;
+          let mock_server = match mock_server.upgrade() {
+            Some(mock_server) => mock_server,
+            None => break
+          };
+          let modified = match fs::metadata(&path).and_then(|md| md.modified()) {
+            Ok(modified) => modified,
+            Err(err) => {
+              warn!("Could not check pact file '{}' - {}", path.display(), err);
+              continue;
+            }
+          };
+          if modified != last_modified {
+            last_modified = modified;
+            info!("Pact file {} has changed, reloading the interactions", path.display());
+            let result = read_pact(&path)
+              .map_err(|err| err.to_string())
+              .and_then(|pact| mock_server.lock().unwrap().replace_interactions(pact.as_ref()));
+            if let Err(err) = result {
+              error!("Failed to reload the interactions from '{}' - {}", path.display(), err);
+            }
+          }
+        }
+        debug!("Stopped watching pact file {}", path.display());
+      });
+      Ok(())
+    }
+
+    /// Find a mock server by the path of the Unix domain socket it is bound to and apply a
+    /// mutating operation on it if successful
+    pub fn find_mock_server_by_unix_socket_mut<R>(
+      &mut self,
+      path: &Path,
+      f: &dyn Fn(&mut MockServer) -> R,
+    ) -> Option<R> {
+      match self
+        .mock_servers
+        .iter_mut()
+        .find(|(_id, entry)| entry.mock_server().lock().unwrap().unix_socket.as_deref() == Some(path))
+      {
+        Some((_id, entry)) => Some(f(&mut entry.mock_server().lock().unwrap())),
+        None => None,
+      }
+    }
+
+    /// Returns the pacts aggregated from all the mock servers of this session, one for each
+    /// consumer and provider. Only the interactions that were exercised are included.
+    pub fn aggregated_pacts(&mut self) -> Result<Vec<Box<dyn Pact + Send>>, String> {
+      self.collect_running_mock_servers()?;
+      self.aggregator.pacts()
+    }
+
+    /// Writes out one pact file for each consumer and provider with the interactions exercised
+    /// by all the mock servers of this session, both running and shut down. The interactions
+    /// previously written by the owner are replaced (see `PactAggregator::write_pacts`). Returns
+    /// the paths of the files written.
+    pub fn write_aggregated_pacts(
+      &mut self,
+      output_path: &Option<String>,
+      owner: &Option<String>
+    ) -> anyhow::Result<Vec<PathBuf>> {
+      self.collect_running_mock_servers().map_err(|err| anyhow::anyhow!(err))?;
+      self.aggregator.write_pacts(output_path, owner)
+    }
+
+    fn collect_running_mock_servers(&mut self) -> Result<(), String> {
+      for handle in self.mock_servers.values() {
+        self.aggregator.add_mock_server(&handle.mock_server().lock().unwrap())?;
+      }
+      Ok(())
+    }
+
     /// Map all the running mock servers
     pub fn map_mock_servers<R>(&self, f: &dyn Fn(&MockServer) -> R) -> Vec<R> {
       let mut results = vec![];
       for (_id_, entry) in self.mock_servers.iter() {
-        results.push(f(&entry.mock_server.lock().unwrap()));
+        results.push(f(&entry.mock_server().lock().unwrap()));
       }
       return results;
     }
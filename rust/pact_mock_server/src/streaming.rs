@@ -0,0 +1,323 @@
+//!
+//! The streaming module defines response bodies that are sent by the mock server as a sequence
+//! of chunks (using chunked transfer encoding), including Server-Sent Event streams.
+//!
+
+use std::time::Duration;
+
+use bytes::{Bytes, BytesMut};
+use futures::stream::{self, StreamExt};
+use hyper::Body;
+use serde_json::{json, Value};
+
+/// Content type of Server-Sent Event streams
+pub const EVENT_STREAM_CONTENT_TYPE: &str = "text/event-stream";
+
+/// Chunk of a streamed response body
+#[derive(Debug, Clone, PartialEq)]
+pub struct BodyChunk {
+  /// Contents of the chunk
+  pub content: Bytes,
+  /// Time to wait before sending the chunk
+  pub delay: Option<Duration>
+}
+
+impl BodyChunk {
+  /// Creates a chunk that is sent without any delay
+  pub fn new<C: Into<Bytes>>(content: C) -> Self {
+    BodyChunk { content: content.into(), delay: None }
+  }
+
+  /// Creates a chunk that is sent after the delay
+  pub fn delayed<C: Into<Bytes>>(content: C, delay: Duration) -> Self {
+    BodyChunk { content: content.into(), delay: Some(delay) }
+  }
+}
+
+/// Event in a Server-Sent Event stream
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerSentEvent {
+  /// Event ID
+  pub id: Option<String>,
+  /// Event type
+  pub event: Option<String>,
+  /// Event data. Multiple lines are sent as multiple `data` fields.
+  pub data: String,
+  /// Reconnection time in milliseconds
+  pub retry: Option<u64>,
+  /// Time to wait before sending the event
+  pub delay: Option<Duration>
+}
+
+impl ServerSentEvent {
+  /// Formats the event as it is sent in the event stream
+  pub fn to_bytes(&self) -> Bytes {
+    let mut event = String::new();
+    if let Some(id) = &self.id {
+      event.push_str(format!("id: {}\n", id).as_str());
+    }
+    if let Some(event_type) = &self.event {
+      event.push_str(format!("event: {}\n", event_type).as_str());
+    }
+    if let Some(retry) = self.retry {
+      event.push_str(format!("retry: {}\n", retry).as_str());
+    }
+    for line in self.data.lines() {
+      event.push_str(format!("data: {}\n", line).as_str());
+    }
+    event.push('\n');
+    Bytes::from(event)
+  }
+
+  fn to_json(&self) -> Value {
+    let mut json = json!({ "data": self.data });
+    if let Some(id) = &self.id {
+      json["id"] = json!(id);
+    }
+    if let Some(event) = &self.event {
+      json["event"] = json!(event);
+    }
+    if let Some(retry) = self.retry {
+      json["retry"] = json!(retry);
+    }
+    if let Some(delay) = self.delay {
+      json["delay"] = json!(delay.as_millis() as u64);
+    }
+    json
+  }
+
+  fn from_json(json: &Value) -> Option<ServerSentEvent> {
+    let string = |key: &str| json.get(key).and_then(|v| v.as_str()).map(|v| v.to_string());
+    Some(ServerSentEvent {
+      id: string("id"),
+      event: string("event"),
+      data: string("data")?,
+      retry: json.get("retry").and_then(|v| v.as_u64()),
+      delay: delay_from_json(json)
+    })
+  }
+}
+
+/// Response body that is streamed to the consumer
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamedBody {
+  /// Sequence of chunks
+  Chunks(Vec<BodyChunk>),
+  /// Server-Sent Event stream (`text/event-stream`)
+  EventStream(Vec<ServerSentEvent>)
+}
+
+impl StreamedBody {
+  /// Content type of the streamed body, if it defines one
+  pub fn content_type(&self) -> Option<&'static str> {
+    match self {
+      StreamedBody::Chunks(_) => None,
+      StreamedBody::EventStream(_) => Some(EVENT_STREAM_CONTENT_TYPE)
+    }
+  }
+
+  /// The complete contents of the body, once all the chunks have been received
+  pub fn content(&self) -> Bytes {
+    let mut content = BytesMut::new();
+    for (chunk, _) in self.chunks() {
+      content.extend_from_slice(&chunk);
+    }
+    content.freeze()
+  }
+
+  /// Creates the body to send back, with each chunk sent after its delay
+  pub fn body(&self) -> Body {
+    let chunks = stream::iter(self.chunks()).then(|(chunk, delay)| async move {
+      if let Some(delay) = delay {
+        tokio::time::sleep(delay).await;
+      }
+      Ok::<_, std::io::Error>(chunk)
+    });
+    Body::wrap_stream(chunks)
+  }
+
+  /// Returns the streamed body cut off after the given number of bytes. The chunk where the body
+  /// is cut off is shortened, and any chunks after it are dropped.
+  pub fn truncated(&self, length: usize) -> StreamedBody {
+    let mut remaining = length;
+    let mut chunks = vec![];
+    for (content, delay) in self.chunks() {
+      if remaining == 0 {
+        break;
+      }
+      let content = content.slice(0..content.len().min(remaining));
+      remaining -= content.len();
+      chunks.push(BodyChunk { content, delay });
+    }
+    StreamedBody::Chunks(chunks)
+  }
+
+  /// Returns the streamed body with each chunk split into chunks of at most `chunk_size` bytes,
+  /// each sent after the interval. The first part of a chunk is also sent after the delay of the
+  /// chunk.
+  pub fn rechunked(&self, chunk_size: usize, interval: Duration) -> StreamedBody {
+    let chunk_size = chunk_size.max(1);
+    let mut chunks = vec![];
+    for (content, delay) in self.chunks() {
+      for (index, start) in (0..content.len()).step_by(chunk_size).enumerate() {
+        let delay = if index == 0 { delay.unwrap_or_default() + interval } else { interval };
+        chunks.push(BodyChunk::delayed(content.slice(start..(start + chunk_size).min(content.len())), delay));
+      }
+    }
+    StreamedBody::Chunks(chunks)
+  }
+
+  fn chunks(&self) -> Vec<(Bytes, Option<Duration>)> {
+    match self {
+      StreamedBody::Chunks(chunks) => chunks.iter()
+        .map(|chunk| (chunk.content.clone(), chunk.delay))
+        .collect(),
+      StreamedBody::EventStream(events) => events.iter()
+        .map(|event| (event.to_bytes(), event.delay))
+        .collect()
+    }
+  }
+
+  /// Converts this streamed body to a `Value` struct. Chunks that are not valid UTF-8 are base64
+  /// encoded, and have an `encoding` attribute set to `base64`.
+  pub fn to_json(&self) -> Value {
+    match self {
+      StreamedBody::Chunks(chunks) => json!({
+        "chunks": chunks.iter().map(|chunk| {
+          let mut json = match std::str::from_utf8(&chunk.content) {
+            Ok(content) => json!({ "content": content }),
+            Err(_) => json!({ "content": base64::encode(&chunk.content), "encoding": "base64" })
+          };
+          if let Some(delay) = chunk.delay {
+            json["delay"] = json!(delay.as_millis() as u64);
+          }
+          json
+        }).collect::<Vec<Value>>()
+      }),
+      StreamedBody::EventStream(events) => json!({
+        "events": events.iter().map(|event| event.to_json()).collect::<Vec<Value>>()
+      })
+    }
+  }
+
+  /// Parses a streamed body from a JSON `Value`, either a list of chunks
+  /// (`{ "chunks": [ { "content": "...", "delay": 100 } ] }`) or a list of events
+  /// (`{ "events": [ { "event": "update", "data": "...", "delay": 100 } ] }`). Delays are in
+  /// milliseconds, and chunks with an `encoding` of `base64` are decoded. Returns `None` if the
+  /// JSON is not a valid streamed body.
+  pub fn from_json(json: &Value) -> Option<StreamedBody> {
+    if let Some(chunks) = json.get("chunks").and_then(|chunks| chunks.as_array()) {
+      chunks.iter()
+        .map(|chunk| chunk_content_from_json(chunk)
+          .map(|content| BodyChunk {
+            content,
+            delay: delay_from_json(chunk)
+          }))
+        .collect::<Option<Vec<BodyChunk>>>()
+        .map(StreamedBody::Chunks)
+    } else if let Some(events) = json.get("events").and_then(|events| events.as_array()) {
+      events.iter()
+        .map(ServerSentEvent::from_json)
+        .collect::<Option<Vec<ServerSentEvent>>>()
+        .map(StreamedBody::EventStream)
+    } else {
+      None
+    }
+  }
+}
+
+fn chunk_content_from_json(json: &Value) -> Option<Bytes> {
+  let content = json.get("content").and_then(|content| content.as_str())?;
+  match json.get("encoding").and_then(|encoding| encoding.as_str()) {
+    Some("base64") => base64::decode(content).ok().map(Bytes::from),
+    Some(_) => None,
+    None => Some(Bytes::from(content.to_string()))
+  }
+}
+
+fn delay_from_json(json: &Value) -> Option<Duration> {
+  json.get("delay").and_then(|delay| delay.as_u64()).map(Duration::from_millis)
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use super::*;
+
+  #[test]
+  fn server_sent_event_format() {
+    let event = ServerSentEvent {
+      id: Some("1".to_string()),
+      event: Some("update".to_string()),
+      data: "line 1\nline 2".to_string(),
+      .. ServerSentEvent::default()
+    };
+    expect!(event.to_bytes()).to(be_equal_to(Bytes::from("id: 1\nevent: update\ndata: line 1\ndata: line 2\n\n")));
+  }
+
+  #[test]
+  fn streamed_body_content_is_all_the_chunks() {
+    let body = StreamedBody::Chunks(vec![
+      BodyChunk::new("Hello "),
+      BodyChunk::delayed("World", Duration::from_millis(10))
+    ]);
+    expect!(body.content()).to(be_equal_to(Bytes::from("Hello World")));
+    expect!(body.content_type()).to(be_none());
+  }
+
+  #[test]
+  fn streamed_body_json_round_trip() {
+    let chunks = StreamedBody::Chunks(vec![BodyChunk::delayed("a", Duration::from_millis(100))]);
+    let events = StreamedBody::EventStream(vec![ServerSentEvent {
+      event: Some("ping".to_string()),
+      data: "{}".to_string(),
+      retry: Some(1000),
+      .. ServerSentEvent::default()
+    }]);
+    expect!(StreamedBody::from_json(&chunks.to_json())).to(be_some().value(chunks));
+    expect!(StreamedBody::from_json(&events.to_json())).to(be_some().value(events));
+    expect!(StreamedBody::from_json(&json!({ "chunks": [ { "delay": 1 } ] }))).to(be_none());
+  }
+
+  #[test]
+  fn streamed_body_json_base64_encodes_binary_chunks() {
+    let body = StreamedBody::Chunks(vec![
+      BodyChunk::new(Bytes::from_static(&[0x89, 0x50, 0x4e, 0x47, 0x00, 0xff])),
+      BodyChunk::new("text")
+    ]);
+    let json = body.to_json();
+    expect!(&json["chunks"][0]["encoding"]).to(be_equal_to(&json!("base64")));
+    expect!(&json["chunks"][0]["content"]).to(be_equal_to(&json!("iVBORwD/")));
+    expect!(json["chunks"][1].get("encoding")).to(be_none());
+    expect!(StreamedBody::from_json(&json)).to(be_some().value(body));
+  }
+
+  #[test]
+  fn streamed_body_truncated() {
+    let body = StreamedBody::Chunks(vec![
+      BodyChunk::new("Hello "),
+      BodyChunk::delayed("World", Duration::from_millis(10)),
+      BodyChunk::new("!")
+    ]);
+    expect!(body.truncated(8)).to(be_equal_to(StreamedBody::Chunks(vec![
+      BodyChunk::new("Hello "),
+      BodyChunk::delayed("Wo", Duration::from_millis(10))
+    ])));
+    expect!(body.truncated(100)).to(be_equal_to(body.clone()));
+  }
+
+  #[test]
+  fn streamed_body_rechunked() {
+    let body = StreamedBody::Chunks(vec![
+      BodyChunk::new("Hello"),
+      BodyChunk::delayed("!", Duration::from_millis(10))
+    ]);
+    expect!(body.rechunked(2, Duration::from_millis(5))).to(be_equal_to(StreamedBody::Chunks(vec![
+      BodyChunk::delayed("He", Duration::from_millis(5)),
+      BodyChunk::delayed("ll", Duration::from_millis(5)),
+      BodyChunk::delayed("o", Duration::from_millis(5)),
+      BodyChunk::delayed("!", Duration::from_millis(15))
+    ])));
+  }
+}
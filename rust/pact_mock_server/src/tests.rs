@@ -1,17 +1,29 @@
 use expectest::expect;
 use expectest::prelude::*;
 use maplit::*;
+use rcgen::{BasicConstraints, Certificate, CertificateParams, DistinguishedName, DnType, ExtendedKeyUsagePurpose, IsCa};
 use reqwest::header::ACCEPT;
 
 use pact_matching::Mismatch;
+use pact_matching::content_encoding::ContentEncoding;
 use pact_matching::models::{Interaction, RequestResponseInteraction, RequestResponsePact};
+use pact_matching::models::v4::{AsynchronousMessage, SynchronousHttp, V4Interaction, V4Pact};
+use pact_models::PactSpecification;
 use pact_models::bodies::OptionalBody;
+use pact_models::generators::{Generator, GeneratorCategory, Generators};
 use pact_models::matchingrules;
 use pact_models::matchingrules::MatchingRule;
+use pact_models::provider_states::ProviderState;
 use pact_models::request::Request;
 use pact_models::response::Response;
+use pact_models::v4::http_parts::{HttpRequest, HttpResponse};
 
-use crate::matching::{match_request, MatchResult};
+use crate::cors::CorsPolicy;
+use crate::faults::{FaultConfig, ResponseDelay};
+use crate::handle::MockServerHandle;
+use crate::matching::{closest_candidates, InteractionOrder, InvocationCount, match_request, match_request_in_order, MatchResult};
+use crate::streaming::{BodyChunk, StreamedBody};
+use crate::tls::{CertificateAuthority, TlsConfigBuilder};
 
 use super::*;
 
@@ -239,3 +251,1600 @@ fn match_request_with_more_specific_request() {
     &interaction1 as &dyn Interaction, &interaction2 as &dyn Interaction]);
   expect!(result2).to(be_equal_to(MatchResult::RequestMatch(expected.request, expected.response)));
 }
+
+#[test]
+fn mock_server_serves_http_interactions_from_a_v4_pact_with_mixed_interactions() {
+  let pact = V4Pact {
+    interactions: vec![
+      SynchronousHttp {
+        description: "a V4 HTTP request".to_string(),
+        request: HttpRequest { path: "/v4".to_string(), .. HttpRequest::default() },
+        response: HttpResponse { status: 204, .. HttpResponse::default() },
+        comments: hashmap!{ "text".to_string() => json!(["a comment"]) },
+        .. SynchronousHttp::default()
+      }.boxed_v4(),
+      AsynchronousMessage::default().boxed_v4()
+    ],
+    .. V4Pact::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_serves_http_interactions_from_a_v4_pact_with_mixed_interactions".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, MockServerConfig::default()).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  let response = client.get(format!("http://127.0.0.1:{}/v4", port).as_str()).send();
+
+  let mismatches = manager.find_mock_server_by_id(&id, &|ms| ms.mismatches());
+  let spec_version = manager.find_mock_server_by_id(&id, &|ms| ms.pact.lock().unwrap().specification_version());
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(response.unwrap().status()).to(be_equal_to(204));
+  expect!(mismatches).to(be_some().value(vec![]));
+  expect!(spec_version).to(be_some().value(PactSpecification::V4));
+}
+
+#[test]
+fn mock_server_records_all_received_requests_in_the_journal() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "a request for the journal".to_string(),
+        request: Request { path: "/journal".to_string(), .. Request::default() },
+        response: Response { status: 201, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_records_all_received_requests_in_the_journal".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, MockServerConfig::default()).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  client.get(format!("http://127.0.0.1:{}/journal?a=b", port).as_str()).send().unwrap();
+  client.post(format!("http://127.0.0.1:{}/other", port).as_str()).body("some body").send().unwrap();
+
+  let journal = manager.find_mock_server_by_id(&id, &|ms| ms.journal()).unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(journal.len()).to(be_equal_to(2));
+  let first = &journal[0];
+  expect!(first.request.method.as_str()).to(be_equal_to("GET"));
+  expect!(first.request.path.as_str()).to(be_equal_to("/journal"));
+  expect!(first.request.query.clone()).to(be_some().value(hashmap!{ "a".to_string() => vec!["b".to_string()] }));
+  expect!(first.interaction_description.clone()).to(be_some().value("a request for the journal".to_string()));
+  expect!(first.response.status).to(be_equal_to(201));
+  let second = &journal[1];
+  expect!(second.request.method.as_str()).to(be_equal_to("POST"));
+  expect!(second.request.body.str_value()).to(be_equal_to("some body"));
+  expect!(second.matched()).to(be_false());
+  expect!(second.response.status).to(be_equal_to(500));
+}
+
+#[test]
+fn mock_server_exports_the_journal_as_a_har_document() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "a request for the HAR export".to_string(),
+        request: Request { path: "/har".to_string(), .. Request::default() },
+        response: Response { status: 200, body: OptionalBody::from("ok"), .. Response::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_exports_the_journal_as_a_har_document".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, MockServerConfig::default()).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  client.get(format!("http://127.0.0.1:{}/har", port).as_str()).send().unwrap();
+  client.post(format!("http://127.0.0.1:{}/har", port).as_str()).body("some body").send().unwrap();
+
+  let har = manager.find_mock_server_by_id(&id, &|ms| ms.to_har()).unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(&har["log"]["version"]).to(be_equal_to(&json!("1.2")));
+  let entries = har["log"]["entries"].as_array().unwrap();
+  expect!(entries.len()).to(be_equal_to(2));
+  expect!(&entries[0]["request"]["url"]).to(be_equal_to(&json!(format!("http://127.0.0.1:{}/har", port))));
+  expect!(&entries[0]["response"]["status"]).to(be_equal_to(&json!(200)));
+  expect!(&entries[0]["response"]["content"]["text"]).to(be_equal_to(&json!("ok")));
+  expect!(entries[0]["time"].as_f64()).to(be_some());
+  expect!(entries[0].get("_matchResult")).to(be_none());
+  expect!(&entries[1]["request"]["method"]).to(be_equal_to(&json!("POST")));
+  expect!(&entries[1]["request"]["postData"]["text"]).to(be_equal_to(&json!("some body")));
+  expect!(&entries[1]["response"]["status"]).to(be_equal_to(&json!(500)));
+  expect!(&entries[1]["_matchResult"]).to(be_equal_to(&json!("Unexpected-Request")));
+}
+
+#[test]
+fn mock_server_reports_interactions_invoked_more_times_than_expected() {
+  let interaction = RequestResponseInteraction {
+    description: "a request that should only be made once".to_string(),
+    request: Request { path: "/once".to_string(), .. Request::default() },
+    .. RequestResponseInteraction::default()
+  };
+  let pact = RequestResponsePact {
+    interactions: vec![ interaction.clone() ],
+    .. RequestResponsePact::default()
+  };
+  let config = MockServerConfig {
+    invocation_counts: hashmap!{ interaction.description.clone() => InvocationCount::Times(1) },
+    .. MockServerConfig::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_reports_interactions_invoked_more_times_than_expected".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, config).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  client.get(format!("http://127.0.0.1:{}/once", port).as_str()).send().unwrap();
+  client.get(format!("http://127.0.0.1:{}/once", port).as_str()).send().unwrap();
+
+  let mismatches = manager.find_mock_server_by_id(&id, &|ms| ms.mismatches());
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(mismatches).to(be_some().value(vec![
+    MatchResult::InvocationCountMismatch(interaction.request, InvocationCount::Times(1), 2)
+  ]));
+}
+
+#[test]
+fn mock_server_uses_invocation_counts_from_the_interaction_comments() {
+  let pact = V4Pact {
+    interactions: vec![
+      SynchronousHttp {
+        description: "a request that can be made many times".to_string(),
+        request: HttpRequest { path: "/many".to_string(), .. HttpRequest::default() },
+        comments: hashmap!{ "invocationCount".to_string() => json!({ "atLeast": 2 }) },
+        .. SynchronousHttp::default()
+      }.boxed_v4(),
+      SynchronousHttp {
+        description: "a request that is optional".to_string(),
+        request: HttpRequest { path: "/optional".to_string(), .. HttpRequest::default() },
+        comments: hashmap!{ "invocationCount".to_string() => json!({ "atMost": 1 }) },
+        .. SynchronousHttp::default()
+      }.boxed_v4()
+    ],
+    .. V4Pact::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_uses_invocation_counts_from_the_interaction_comments".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, MockServerConfig::default()).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  for _ in 0..3 {
+    client.get(format!("http://127.0.0.1:{}/many", port).as_str()).send().unwrap();
+  }
+
+  let mismatches = manager.find_mock_server_by_id(&id, &|ms| ms.mismatches());
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(mismatches).to(be_some().value(vec![]));
+}
+
+#[test]
+fn invocation_count_json_round_trip() {
+  for count in vec![InvocationCount::Times(2), InvocationCount::AtLeast(1), InvocationCount::AtMost(3)] {
+    expect!(InvocationCount::from_json(&count.to_json())).to(be_some().value(count));
+  }
+  expect!(InvocationCount::from_json(&json!({ "sometimes": 1 }))).to(be_none());
+}
+
+#[test]
+fn match_request_in_order_returns_successive_responses_for_identical_requests() {
+  let request = Request { path: "/status".to_string(), .. Request::default() };
+  let pending = RequestResponseInteraction {
+    description: "the job is pending".to_string(),
+    request: request.clone(),
+    response: Response { status: 202, .. Response::default() },
+    .. RequestResponseInteraction::default()
+  };
+  let done = RequestResponseInteraction {
+    description: "the job is done".to_string(),
+    request: request.clone(),
+    response: Response { status: 200, .. Response::default() },
+    .. RequestResponseInteraction::default()
+  };
+  let mut order = InteractionOrder::default();
+
+  let (first, _) = match_request_in_order(&request, vec![&pending as &dyn Interaction, &done], &mut order);
+  expect!(first).to(be_equal_to(MatchResult::RequestMatch(pending.request.clone(), pending.response.clone())));
+  let (second, _) = match_request_in_order(&request, vec![&pending as &dyn Interaction, &done], &mut order);
+  expect!(second).to(be_equal_to(MatchResult::RequestMatch(done.request.clone(), done.response.clone())));
+  expect!(order.next).to(be_equal_to(2));
+}
+
+#[test]
+fn match_request_in_order_returns_not_found_once_all_the_interactions_have_been_received() {
+  let request = Request { path: "/status".to_string(), .. Request::default() };
+  let pending = RequestResponseInteraction {
+    description: "the job is pending".to_string(),
+    request: request.clone(),
+    response: Response { status: 202, .. Response::default() },
+    .. RequestResponseInteraction::default()
+  };
+  let done = RequestResponseInteraction {
+    description: "the job is done".to_string(),
+    request: request.clone(),
+    response: Response { status: 200, .. Response::default() },
+    .. RequestResponseInteraction::default()
+  };
+  let mut order = InteractionOrder::default();
+
+  match_request_in_order(&request, vec![&pending as &dyn Interaction, &done], &mut order);
+  match_request_in_order(&request, vec![&pending as &dyn Interaction, &done], &mut order);
+  let (third, interaction) = match_request_in_order(&request, vec![&pending as &dyn Interaction, &done], &mut order);
+  expect!(third).to(be_equal_to(MatchResult::RequestNotFound(request.clone())));
+  expect!(interaction.is_none()).to(be_true());
+  expect!(order.next).to(be_equal_to(2));
+}
+
+#[test]
+fn match_request_in_order_returns_out_of_order_for_a_later_interaction() {
+  let first = RequestResponseInteraction {
+    description: "the first request".to_string(),
+    request: Request { path: "/one".to_string(), .. Request::default() },
+    .. RequestResponseInteraction::default()
+  };
+  let second = RequestResponseInteraction {
+    description: "the second request".to_string(),
+    request: Request { path: "/two".to_string(), .. Request::default() },
+    .. RequestResponseInteraction::default()
+  };
+  let mut order = InteractionOrder::default();
+
+  let (result, interaction) = match_request_in_order(&second.request,
+    vec![&first as &dyn Interaction, &second], &mut order);
+  expect!(result).to(be_equal_to(MatchResult::RequestOutOfOrder(second.request.clone(), 0, 1)));
+  expect!(interaction.map(|i| i.description())).to(be_some().value("the second request".to_string()));
+  expect!(order.next).to(be_equal_to(2));
+
+  let (result, _) = match_request_in_order(&first.request,
+    vec![&first as &dyn Interaction, &second], &mut order);
+  expect!(result).to(be_equal_to(MatchResult::RequestOutOfOrder(first.request.clone(), 2, 0)));
+  expect!(order.next).to(be_equal_to(2));
+}
+
+#[test]
+fn mock_server_in_ordered_mode_serves_responses_in_sequence() {
+  let request = Request { path: "/status".to_string(), .. Request::default() };
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "the job is pending".to_string(),
+        request: request.clone(),
+        response: Response { status: 202, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      },
+      RequestResponseInteraction {
+        description: "the job is done".to_string(),
+        request: request.clone(),
+        response: Response { status: 200, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let config = MockServerConfig {
+    ordered_interactions: true,
+    .. MockServerConfig::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_in_ordered_mode_serves_responses_in_sequence".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, config).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  let first = client.get(format!("http://127.0.0.1:{}/status", port).as_str()).send().unwrap();
+  let second = client.get(format!("http://127.0.0.1:{}/status", port).as_str()).send().unwrap();
+
+  let mismatches = manager.find_mock_server_by_id(&id, &|ms| ms.mismatches());
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(first.status().as_u16()).to(be_equal_to(202));
+  expect!(second.status().as_u16()).to(be_equal_to(200));
+  expect!(mismatches).to(be_some().value(vec![]));
+}
+
+#[test]
+fn mock_server_reports_each_identical_request_that_was_not_received() {
+  let request = Request { path: "/status".to_string(), .. Request::default() };
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "the job is pending".to_string(),
+        request: request.clone(),
+        response: Response { status: 202, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      },
+      RequestResponseInteraction {
+        description: "the job is done".to_string(),
+        request: request.clone(),
+        response: Response { status: 200, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let config = MockServerConfig {
+    ordered_interactions: true,
+    .. MockServerConfig::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_reports_each_identical_request_that_was_not_received".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, config).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  let response = client.get(format!("http://127.0.0.1:{}/status", port).as_str()).send().unwrap();
+
+  let mismatches = manager.find_mock_server_by_id(&id, &|ms| ms.mismatches());
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(response.status().as_u16()).to(be_equal_to(202));
+  expect!(mismatches).to(be_some().value(vec![MatchResult::MissingRequest(request)]));
+}
+
+#[test]
+fn mock_server_only_matches_interactions_for_the_active_provider_state() {
+  let request = Request { path: "/users/1".to_string(), .. Request::default() };
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "a request for a user".to_string(),
+        provider_states: vec![ ProviderState::default(&"user exists".to_string()) ],
+        request: request.clone(),
+        response: Response { status: 200, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      },
+      RequestResponseInteraction {
+        description: "a request for a missing user".to_string(),
+        provider_states: vec![ ProviderState::default(&"user does not exist".to_string()) ],
+        request: request.clone(),
+        response: Response { status: 404, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_only_matches_interactions_for_the_active_provider_state".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, MockServerConfig::default()).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  manager.set_mock_server_provider_state(&id, Some("user does not exist".to_string()));
+  let missing = client.get(format!("http://127.0.0.1:{}/users/1", port).as_str()).send().unwrap();
+  manager.set_mock_server_provider_state(&id, Some("user exists".to_string()));
+  let found = client.get(format!("http://127.0.0.1:{}/users/1", port).as_str()).send().unwrap();
+  manager.set_mock_server_provider_state(&id, Some("there are no users".to_string()));
+  let not_found = client.get(format!("http://127.0.0.1:{}/users/1", port).as_str()).send().unwrap();
+
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(missing.status().as_u16()).to(be_equal_to(404));
+  expect!(found.status().as_u16()).to(be_equal_to(200));
+  expect!(not_found.status().as_u16()).to(be_equal_to(500));
+}
+
+#[test]
+fn mock_server_injects_faults_into_the_responses() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "a slow request".to_string(),
+        request: Request { path: "/slow".to_string(), .. Request::default() },
+        response: Response {
+          body: OptionalBody::Present("0123456789".into(), None),
+          .. Response::default()
+        },
+        .. RequestResponseInteraction::default()
+      },
+      RequestResponseInteraction {
+        description: "a broken request".to_string(),
+        request: Request { path: "/broken".to_string(), .. Request::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let config = MockServerConfig {
+    faults: Some(FaultConfig {
+      delay: Some(ResponseDelay::Fixed(std::time::Duration::from_millis(200))),
+      status: Some(503),
+      truncate_body: Some(4),
+      .. FaultConfig::default()
+    }),
+    interaction_faults: hashmap!{
+      "a broken request".to_string() => FaultConfig { reset_connection: true, .. FaultConfig::default() }
+    },
+    .. MockServerConfig::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_injects_faults_into_the_responses".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, config).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  let start = std::time::Instant::now();
+  let slow = client.get(format!("http://127.0.0.1:{}/slow", port).as_str()).send().unwrap();
+  let elapsed = start.elapsed();
+  let status = slow.status().as_u16();
+  let body = slow.text().unwrap();
+  let broken = client.get(format!("http://127.0.0.1:{}/broken", port).as_str()).send();
+
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(elapsed >= std::time::Duration::from_millis(200)).to(be_true());
+  expect!(status).to(be_equal_to(503));
+  expect!(body).to(be_equal_to("0123".to_string()));
+  expect!(broken).to(be_err());
+}
+
+#[test]
+fn mock_server_forwards_unexpected_requests_to_the_upstream_and_records_them() {
+  let upstream_pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "a request to the legacy provider".to_string(),
+        request: Request { path: "/legacy".to_string(), .. Request::default() },
+        response: Response {
+          headers: Some(hashmap!{ "Content-Type".to_string() => vec!["text/plain".to_string()] }),
+          body: OptionalBody::Present("legacy response".into(), None),
+          .. Response::default()
+        },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let mut manager = ServerManager::new();
+  let upstream_id = "mock_server_forwards_unexpected_requests_to_the_upstream".to_string();
+  let upstream_port = manager.start_mock_server(upstream_id.clone(), upstream_pact.boxed(), 0,
+    MockServerConfig::default()).unwrap();
+  let config = MockServerConfig {
+    upstream_url: Some(format!("http://127.0.0.1:{}", upstream_port)),
+    .. MockServerConfig::default()
+  };
+  let id = "mock_server_forwards_unexpected_requests_to_the_upstream_and_records_them".to_string();
+  let port = manager.start_mock_server(id.clone(), RequestResponsePact::default().boxed(), 0, config).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  let response = client.get(format!("http://127.0.0.1:{}/legacy", port).as_str()).send().unwrap();
+  let status = response.status().as_u16();
+  let body = response.text().unwrap();
+  client.get(format!("http://127.0.0.1:{}/legacy", port).as_str()).send().unwrap();
+
+  let recorded = manager.find_mock_server_by_id(&id, &|ms| {
+    ms.pact.lock().unwrap().interactions().iter()
+      .filter_map(|i| i.as_request_response())
+      .collect::<Vec<RequestResponseInteraction>>()
+  }).unwrap();
+  let mismatches = manager.find_mock_server_by_id(&id, &|ms| ms.mismatches());
+  manager.shutdown_mock_server_by_port(port);
+  manager.shutdown_mock_server_by_port(upstream_port);
+
+  expect!(status).to(be_equal_to(200));
+  expect!(body).to(be_equal_to("legacy response".to_string()));
+  expect!(recorded.len()).to(be_equal_to(1));
+  expect!(recorded[0].description.clone()).to(be_equal_to("GET /legacy".to_string()));
+  expect!(recorded[0].request.path.clone()).to(be_equal_to("/legacy".to_string()));
+  expect!(recorded[0].response.status).to(be_equal_to(200));
+  expect!(recorded[0].response.body.str_value()).to(be_equal_to("legacy response"));
+  expect!(mismatches).to(be_some().value(vec![]));
+}
+
+#[test]
+fn mock_server_serves_repeated_requests_from_the_recorded_interaction() {
+  let upstream_pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "a request to the legacy provider".to_string(),
+        request: Request { path: "/legacy".to_string(), .. Request::default() },
+        response: Response { body: OptionalBody::Present("legacy response".into(), None), .. Response::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let mut manager = ServerManager::new();
+  let upstream_id = "mock_server_serves_repeated_requests_from_the_upstream".to_string();
+  let upstream_port = manager.start_mock_server(upstream_id.clone(), upstream_pact.boxed(), 0,
+    MockServerConfig::default()).unwrap();
+  let config = MockServerConfig {
+    upstream_url: Some(format!("http://127.0.0.1:{}", upstream_port)),
+    .. MockServerConfig::default()
+  };
+  let id = "mock_server_serves_repeated_requests_from_the_recorded_interaction".to_string();
+  let port = manager.start_mock_server(id.clone(), RequestResponsePact::default().boxed(), 0, config).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  client.get(format!("http://127.0.0.1:{}/legacy", port).as_str()).send().unwrap();
+  let response = client.get(format!("http://127.0.0.1:{}/legacy", port).as_str()).send().unwrap();
+  let status = response.status().as_u16();
+  let body = response.text().unwrap();
+
+  let upstream_journal = manager.find_mock_server_by_id(&upstream_id, &|ms| ms.journal()).unwrap();
+  let journal = manager.find_mock_server_by_id(&id, &|ms| ms.journal()).unwrap();
+  manager.shutdown_mock_server_by_port(port);
+  manager.shutdown_mock_server_by_port(upstream_port);
+
+  expect!(status).to(be_equal_to(200));
+  expect!(body).to(be_equal_to("legacy response".to_string()));
+  expect!(upstream_journal.len()).to(be_equal_to(1));
+  expect!(journal.len()).to(be_equal_to(2));
+  expect!(journal[1].interaction_description.clone()).to(be_some().value("GET /legacy".to_string()));
+}
+
+/// Certificates for testing TLS client authentication: a CA, and a server certificate for
+/// localhost and a client certificate that are signed by it
+struct ClientAuthCertificates {
+  ca: String,
+  server_cert: String,
+  server_key: String,
+  client_identity: String
+}
+
+fn client_auth_certificates() -> ClientAuthCertificates {
+  let mut ca_params = CertificateParams::default();
+  ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+  ca_params.distinguished_name = DistinguishedName::new();
+  ca_params.distinguished_name.push(DnType::CommonName, "Pact Test CA");
+  let ca = Certificate::from_params(ca_params).unwrap();
+
+  let mut server_params = CertificateParams::new(vec!["localhost".to_string()]);
+  server_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+  let server = Certificate::from_params(server_params).unwrap();
+
+  let mut client_params = CertificateParams::new(vec!["pact-client".to_string()]);
+  client_params.distinguished_name = DistinguishedName::new();
+  client_params.distinguished_name.push(DnType::CommonName, "pact-client");
+  client_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+  let client = Certificate::from_params(client_params).unwrap();
+
+  ClientAuthCertificates {
+    ca: ca.serialize_pem().unwrap(),
+    server_cert: server.serialize_pem_with_signer(&ca).unwrap(),
+    server_key: server.serialize_private_key_pem(),
+    client_identity: format!("{}{}", client.serialize_private_key_pem(), client.serialize_pem_with_signer(&ca).unwrap())
+  }
+}
+
+fn client_auth_clients(certificates: &ClientAuthCertificates) -> (reqwest::blocking::Client, reqwest::blocking::Client) {
+  let ca = reqwest::Certificate::from_pem(certificates.ca.as_bytes()).unwrap();
+  let client = reqwest::blocking::Client::builder()
+    .add_root_certificate(ca.clone())
+    .identity(reqwest::Identity::from_pem(certificates.client_identity.as_bytes()).unwrap())
+    .build()
+    .unwrap();
+  let anonymous_client = reqwest::blocking::Client::builder()
+    .add_root_certificate(ca)
+    .build()
+    .unwrap();
+  (client, anonymous_client)
+}
+
+#[test]
+fn tls_mock_server_requiring_client_certificates() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        request: Request { path: "/secure".to_string(), .. Request::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let certificates = client_auth_certificates();
+  let tls = TlsConfigBuilder::new()
+    .cert(certificates.server_cert.as_bytes())
+    .key(certificates.server_key.as_bytes())
+    .client_auth_required(certificates.ca.as_bytes())
+    .build()
+    .unwrap();
+  let mut manager = ServerManager::new();
+  let id = "tls_mock_server_requiring_client_certificates".to_string();
+  let port = manager.start_tls_mock_server(id.clone(), pact.boxed(), 0, &tls, MockServerConfig::default()).unwrap();
+
+  let (client, anonymous_client) = client_auth_clients(&certificates);
+  let response = client.get(format!("https://localhost:{}/secure", port).as_str()).send();
+  let anonymous_response = anonymous_client.get(format!("https://localhost:{}/secure", port).as_str()).send();
+
+  let journal = manager.find_mock_server_by_id(&id, &|ms| ms.journal()).unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(response.unwrap().status().as_u16()).to(be_equal_to(200));
+  expect!(anonymous_response).to(be_err());
+  expect!(journal.len()).to(be_equal_to(1));
+  expect!(journal[0].client_certificate_subject.clone()).to(be_some().value("CN=pact-client".to_string()));
+  expect!(&journal[0].to_json()["clientCertificate"]["subject"]).to(be_equal_to(&serde_json::json!("CN=pact-client")));
+}
+
+#[test]
+fn tls_mock_server_accepting_optional_client_certificates() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        request: Request { path: "/secure".to_string(), .. Request::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let certificates = client_auth_certificates();
+  let tls = TlsConfigBuilder::new()
+    .cert(certificates.server_cert.as_bytes())
+    .key(certificates.server_key.as_bytes())
+    .client_auth_optional(certificates.ca.as_bytes())
+    .build()
+    .unwrap();
+  let mut manager = ServerManager::new();
+  let id = "tls_mock_server_accepting_optional_client_certificates".to_string();
+  let port = manager.start_tls_mock_server(id.clone(), pact.boxed(), 0, &tls, MockServerConfig::default()).unwrap();
+
+  let (client, anonymous_client) = client_auth_clients(&certificates);
+  let response = client.get(format!("https://localhost:{}/secure", port).as_str()).send();
+  let anonymous_response = anonymous_client.get(format!("https://localhost:{}/secure", port).as_str()).send();
+
+  let journal = manager.find_mock_server_by_id(&id, &|ms| ms.journal()).unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(response.unwrap().status().as_u16()).to(be_equal_to(200));
+  expect!(anonymous_response.unwrap().status().as_u16()).to(be_equal_to(200));
+  expect!(journal.len()).to(be_equal_to(2));
+  expect!(journal[0].client_certificate_subject.clone()).to(be_some().value("CN=pact-client".to_string()));
+  expect!(journal[1].client_certificate_subject.clone()).to(be_none());
+  expect!(journal[1].to_json().get("clientCertificate")).to(be_none());
+}
+
+#[test]
+fn tls_mock_server_with_a_generated_certificate() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        request: Request { path: "/secure".to_string(), .. Request::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let builder = TlsConfigBuilder::new().generate_cert(vec!["localhost", "127.0.0.1"]);
+  let ca_pem = builder.ca_certificate_pem().unwrap();
+  let tls = builder.build().unwrap();
+  let mut manager = ServerManager::new();
+  let id = "tls_mock_server_with_a_generated_certificate".to_string();
+  let port = manager.start_tls_mock_server(id.clone(), pact.boxed(), 0, &tls, MockServerConfig::default()).unwrap();
+
+  let client = reqwest::blocking::Client::builder()
+    .add_root_certificate(reqwest::Certificate::from_pem(ca_pem.as_bytes()).unwrap())
+    .build()
+    .unwrap();
+  let response = client.get(format!("https://localhost:{}/secure", port).as_str()).send();
+
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(response.unwrap().status().as_u16()).to(be_equal_to(200));
+}
+
+#[test]
+fn start_tls_mock_server_with_generated_cert_returns_the_certificate_of_the_ca() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        request: Request { path: "/secure".to_string(), .. Request::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let ca = Arc::new(CertificateAuthority::new().unwrap());
+  let (port, ca_pem) = start_tls_mock_server_with_generated_cert(
+    "start_tls_mock_server_with_generated_cert".to_string(), pact.boxed(), ([127, 0, 0, 1], 0).into(),
+    &["localhost".to_string(), "127.0.0.1".to_string()], Some(ca.clone()), MockServerConfig::default()
+  ).unwrap();
+
+  let client = reqwest::blocking::Client::builder()
+    .add_root_certificate(reqwest::Certificate::from_pem(ca_pem.as_bytes()).unwrap())
+    .build()
+    .unwrap();
+  let by_name = client.get(format!("https://localhost:{}/secure", port).as_str()).send();
+  let by_ip = client.get(format!("https://127.0.0.1:{}/secure", port).as_str()).send();
+  let untrusted = reqwest::blocking::get(format!("https://127.0.0.1:{}/secure", port).as_str());
+
+  shutdown_mock_server(port);
+
+  expect!(ca_pem).to(be_equal_to(ca.certificate_pem()));
+  expect!(by_name.unwrap().status().as_u16()).to(be_equal_to(200));
+  expect!(by_ip.unwrap().status().as_u16()).to(be_equal_to(200));
+  expect!(untrusted.is_err()).to(be_true());
+}
+
+#[test]
+fn mock_server_accepts_cleartext_http2_requests_with_prior_knowledge() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        request: Request { path: "/h2c".to_string(), .. Request::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let config = MockServerConfig {
+    http2: Some(true),
+    .. MockServerConfig::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_accepts_cleartext_http2_requests_with_prior_knowledge".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, config).unwrap();
+
+  let client = reqwest::blocking::Client::builder()
+    .http2_prior_knowledge()
+    .build()
+    .unwrap();
+  let http2_response = client.get(format!("http://127.0.0.1:{}/h2c", port).as_str()).send().unwrap();
+  let http1_response = reqwest::blocking::get(format!("http://127.0.0.1:{}/h2c", port).as_str()).unwrap();
+
+  let journal = manager.find_mock_server_by_id(&id, &|ms| ms.journal()).unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(http2_response.status().as_u16()).to(be_equal_to(200));
+  expect!(http1_response.status().as_u16()).to(be_equal_to(200));
+  expect!(journal[0].protocol_version.clone()).to(be_some().value("HTTP/2.0".to_string()));
+  expect!(journal[1].protocol_version.clone()).to(be_some().value("HTTP/1.1".to_string()));
+}
+
+#[test]
+fn tls_mock_server_negotiates_http2_with_alpn() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        request: Request { path: "/h2".to_string(), .. Request::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let builder = TlsConfigBuilder::new().generate_cert(vec!["localhost"]);
+  let ca_pem = builder.ca_certificate_pem().unwrap();
+  let tls = builder.build().unwrap();
+  let config = MockServerConfig {
+    http2: Some(true),
+    .. MockServerConfig::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "tls_mock_server_negotiates_http2_with_alpn".to_string();
+  let port = manager.start_tls_mock_server(id.clone(), pact.boxed(), 0, &tls, config).unwrap();
+
+  let client = reqwest::blocking::Client::builder()
+    .add_root_certificate(reqwest::Certificate::from_pem(ca_pem.as_bytes()).unwrap())
+    .build()
+    .unwrap();
+  let response = client.get(format!("https://localhost:{}/h2", port).as_str()).send().unwrap();
+
+  let journal = manager.find_mock_server_by_id(&id, &|ms| ms.journal()).unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(response.version()).to(be_equal_to(reqwest::Version::HTTP_2));
+  expect!(journal[0].protocol_version.clone()).to(be_some().value("HTTP/2.0".to_string()));
+}
+
+#[test]
+#[cfg(unix)]
+fn mock_server_can_be_bound_to_a_unix_domain_socket() {
+  use std::io::{Read, Write};
+  use std::os::unix::net::UnixStream;
+
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        request: Request { path: "/uds".to_string(), .. Request::default() },
+        response: Response { status: 204, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let socket_path = std::env::temp_dir()
+    .join(format!("pact-mock-server-{}.sock", std::process::id()));
+  let mut manager = ServerManager::new();
+  let id = "mock_server_can_be_bound_to_a_unix_domain_socket".to_string();
+  manager.start_mock_server_with_unix_socket(id.clone(), pact.boxed(), &socket_path,
+    MockServerConfig::default()).unwrap();
+
+  let mut stream = UnixStream::connect(&socket_path).unwrap();
+  stream.write_all(b"GET /uds HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").unwrap();
+  let mut response = String::new();
+  stream.read_to_string(&mut response).unwrap();
+
+  let matched = manager.find_mock_server_by_unix_socket_mut(&socket_path, &|ms| ms.mismatches().is_empty());
+  let url = manager.find_mock_server_by_id(&id, &|ms| ms.url());
+  expect!(manager.shutdown_mock_server_by_unix_socket(&socket_path)).to(be_true());
+
+  expect!(response.starts_with("HTTP/1.1 204")).to(be_true());
+  expect!(matched).to(be_some().value(true));
+  expect!(url).to(be_some().value(format!("unix://{}", socket_path.display())));
+}
+
+#[test]
+#[cfg(unix)]
+fn mock_server_replaces_a_stale_unix_domain_socket() {
+  use std::os::unix::net::{UnixListener, UnixStream};
+
+  let socket_path = std::env::temp_dir()
+    .join(format!("pact-mock-server-stale-{}.sock", std::process::id()));
+  let _ = std::fs::remove_file(&socket_path);
+  // Dropping the listener leaves the socket file behind, as when a process is killed
+  drop(UnixListener::bind(&socket_path).unwrap());
+
+  let mut manager = ServerManager::new();
+  let id = "mock_server_replaces_a_stale_unix_domain_socket".to_string();
+  let result = manager.start_mock_server_with_unix_socket(id.clone(), RequestResponsePact::default().boxed(),
+    &socket_path, MockServerConfig::default());
+  let connected = UnixStream::connect(&socket_path).is_ok();
+  manager.shutdown_mock_server_by_unix_socket(&socket_path);
+
+  expect!(result).to(be_ok());
+  expect!(connected).to(be_true());
+}
+
+#[test]
+#[cfg(unix)]
+fn mock_server_does_not_replace_a_file_that_is_not_a_unix_domain_socket() {
+  let path = std::env::temp_dir()
+    .join(format!("pact-mock-server-not-a-socket-{}.sock", std::process::id()));
+  std::fs::write(&path, "not a socket").unwrap();
+
+  let mut manager = ServerManager::new();
+  let id = "mock_server_does_not_replace_a_file_that_is_not_a_unix_domain_socket".to_string();
+  let result = manager.start_mock_server_with_unix_socket(id.clone(), RequestResponsePact::default().boxed(),
+    &path, MockServerConfig::default());
+  let contents = std::fs::read_to_string(&path).unwrap();
+  std::fs::remove_file(&path).unwrap();
+
+  expect!(result).to(be_err());
+  expect!(contents).to(be_equal_to("not a socket".to_string()));
+}
+
+#[test]
+fn mock_server_applies_the_cors_policy_without_overriding_the_response_headers() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        request: Request { path: "/cors".to_string(), .. Request::default() },
+        response: Response {
+          headers: Some(hashmap!{ "Access-Control-Expose-Headers".to_string() => vec!["X-Total".to_string()] }),
+          .. Response::default()
+        },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let config = MockServerConfig {
+    cors_preflight: true,
+    cors: CorsPolicy {
+      allow_credentials: true,
+      max_age: Some(600),
+      .. CorsPolicy::default()
+    },
+    .. MockServerConfig::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_applies_the_cors_policy_without_overriding_the_response_headers".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, config).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  let url = format!("http://127.0.0.1:{}/cors", port);
+  let preflight = client.request(reqwest::Method::OPTIONS, url.as_str())
+    .header("Origin", "http://localhost:3000")
+    .header("Access-Control-Request-Headers", "authorization")
+    .send().unwrap();
+  let response = client.get(url.as_str())
+    .header("Origin", "http://localhost:3000")
+    .send().unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(preflight.status().as_u16()).to(be_equal_to(204));
+  expect!(preflight.headers().get("access-control-allow-origin").unwrap().to_str().unwrap()).to(be_equal_to("http://localhost:3000"));
+  expect!(preflight.headers().get("access-control-allow-headers").unwrap().to_str().unwrap()).to(be_equal_to("authorization"));
+  expect!(preflight.headers().get("access-control-max-age").unwrap().to_str().unwrap()).to(be_equal_to("600"));
+  expect!(response.status().as_u16()).to(be_equal_to(200));
+  expect!(response.headers().get("access-control-allow-origin").unwrap().to_str().unwrap()).to(be_equal_to("http://localhost:3000"));
+  expect!(response.headers().get("access-control-allow-credentials").unwrap().to_str().unwrap()).to(be_equal_to("true"));
+  expect!(response.headers().get_all("access-control-expose-headers").iter().count()).to(be_equal_to(1));
+  expect!(response.headers().get("access-control-expose-headers").unwrap().to_str().unwrap()).to(be_equal_to("X-Total"));
+}
+
+#[test]
+fn mock_server_does_not_add_cors_headers_when_the_policy_is_disabled() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        request: Request { path: "/no-cors".to_string(), .. Request::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let config = MockServerConfig {
+    cors: CorsPolicy::disabled(),
+    .. MockServerConfig::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_does_not_add_cors_headers_when_the_policy_is_disabled".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, config).unwrap();
+
+  let response = reqwest::blocking::get(format!("http://127.0.0.1:{}/no-cors", port).as_str()).unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(response.status().as_u16()).to(be_equal_to(200));
+  expect!(response.headers().keys().any(|name| name.as_str().starts_with("access-control-"))).to(be_false());
+}
+
+#[test]
+fn mock_server_streams_the_response_bodies() {
+  let pact = V4Pact {
+    interactions: vec![
+      SynchronousHttp {
+        description: "a stream of events".to_string(),
+        request: HttpRequest { path: "/events".to_string(), .. HttpRequest::default() },
+        comments: hashmap!{
+          "streamedBody".to_string() => json!({
+            "events": [
+              { "id": "1", "event": "update", "data": "{\"count\":1}" },
+              { "id": "2", "event": "update", "data": "{\"count\":2}", "delay": 50 }
+            ]
+          })
+        },
+        .. SynchronousHttp::default()
+      }.boxed_v4(),
+      SynchronousHttp {
+        description: "a chunked download".to_string(),
+        request: HttpRequest { path: "/download".to_string(), .. HttpRequest::default() },
+        .. SynchronousHttp::default()
+      }.boxed_v4()
+    ],
+    .. V4Pact::default()
+  };
+  let config = MockServerConfig {
+    streamed_bodies: hashmap!{
+      "a chunked download".to_string() => StreamedBody::Chunks(vec![
+        BodyChunk::new("Hello "),
+        BodyChunk::delayed("World", std::time::Duration::from_millis(50))
+      ])
+    },
+    .. MockServerConfig::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_streams_the_response_bodies".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, config).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  let events = client.get(format!("http://127.0.0.1:{}/events", port).as_str()).send().unwrap();
+  let events_content_type = events.headers().get("content-type").unwrap().to_str().unwrap().to_string();
+  let events_transfer_encoding = events.headers().get("transfer-encoding").unwrap().to_str().unwrap().to_string();
+  let events_body = events.text().unwrap();
+  let download = client.get(format!("http://127.0.0.1:{}/download", port).as_str()).send().unwrap();
+  let download_body = download.text().unwrap();
+
+  let mismatches = manager.find_mock_server_by_id(&id, &|ms| ms.mismatches()).unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(events_content_type).to(be_equal_to("text/event-stream"));
+  expect!(events_transfer_encoding).to(be_equal_to("chunked"));
+  expect!(events_body).to(be_equal_to(
+    "id: 1\nevent: update\ndata: {\"count\":1}\n\nid: 2\nevent: update\ndata: {\"count\":2}\n\n"));
+  expect!(download_body).to(be_equal_to("Hello World"));
+  expect!(mismatches.iter()).to(be_empty());
+}
+
+#[test]
+fn mock_server_applies_faults_to_streamed_bodies_and_rejects_content_encodings() {
+  let pact = V4Pact {
+    interactions: vec![
+      SynchronousHttp {
+        description: "a chunked download".to_string(),
+        request: HttpRequest { path: "/download".to_string(), .. HttpRequest::default() },
+        .. SynchronousHttp::default()
+      }.boxed_v4(),
+      SynchronousHttp {
+        description: "a compressed download".to_string(),
+        request: HttpRequest { path: "/compressed".to_string(), .. HttpRequest::default() },
+        response: HttpResponse {
+          headers: Some(hashmap!{ "Content-Encoding".to_string() => vec!["gzip".to_string()] }),
+          .. HttpResponse::default()
+        },
+        .. SynchronousHttp::default()
+      }.boxed_v4()
+    ],
+    .. V4Pact::default()
+  };
+  let chunks = StreamedBody::Chunks(vec![
+    BodyChunk::new("Hello "),
+    BodyChunk::delayed("World", std::time::Duration::from_millis(50))
+  ]);
+  let config = MockServerConfig {
+    streamed_bodies: hashmap!{
+      "a chunked download".to_string() => chunks.clone(),
+      "a compressed download".to_string() => chunks
+    },
+    faults: Some(FaultConfig { truncate_body: Some(8), .. FaultConfig::default() }),
+    .. MockServerConfig::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_applies_faults_to_streamed_bodies_and_rejects_content_encodings".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, config).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  let download = client.get(format!("http://127.0.0.1:{}/download", port).as_str()).send().unwrap();
+  let download_body = download.text().unwrap();
+  let compressed = client.get(format!("http://127.0.0.1:{}/compressed", port).as_str()).send().unwrap();
+
+  let journal = manager.find_mock_server_by_id(&id, &|ms| ms.journal()).unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(download_body).to(be_equal_to("Hello Wo"));
+  expect!(journal[0].response.body.str_value()).to(be_equal_to("Hello Wo"));
+  expect!(compressed.status().as_u16()).to(be_equal_to(500));
+}
+
+#[test]
+fn interactions_can_be_added_and_removed_from_a_running_mock_server() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "a request for a user".to_string(),
+        request: Request { path: "/users/1".to_string(), .. Request::default() },
+        response: Response { status: 200, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "interactions_can_be_added_and_removed_from_a_running_mock_server".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, MockServerConfig::default()).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  let before = client.get(format!("http://127.0.0.1:{}/users/2", port).as_str()).send().unwrap();
+  let added = manager.upsert_mock_server_interaction(&id, &RequestResponseInteraction {
+    description: "a request for another user".to_string(),
+    request: Request { path: "/users/2".to_string(), .. Request::default() },
+    response: Response { status: 201, .. Response::default() },
+    .. RequestResponseInteraction::default()
+  });
+  let after_add = client.get(format!("http://127.0.0.1:{}/users/2", port).as_str()).send().unwrap();
+  let replaced = manager.upsert_mock_server_interaction(&id, &RequestResponseInteraction {
+    description: "a request for a user".to_string(),
+    request: Request { path: "/users/1".to_string(), .. Request::default() },
+    response: Response { status: 204, .. Response::default() },
+    .. RequestResponseInteraction::default()
+  });
+  let after_replace = client.get(format!("http://127.0.0.1:{}/users/1", port).as_str()).send().unwrap();
+  let removed = manager.remove_mock_server_interaction(&id, "a request for another user");
+  let removed_again = manager.remove_mock_server_interaction(&id, "a request for another user");
+  let after_remove = client.get(format!("http://127.0.0.1:{}/users/2", port).as_str()).send().unwrap();
+
+  let interactions = manager.find_mock_server_by_id(&id, &|ms| ms.pact.lock().unwrap().interactions().len()).unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(before.status().as_u16()).to(be_equal_to(500));
+  expect!(added).to(be_ok());
+  expect!(after_add.status().as_u16()).to(be_equal_to(201));
+  expect!(replaced).to(be_ok());
+  expect!(after_replace.status().as_u16()).to(be_equal_to(204));
+  expect!(removed).to(be_ok().value(true));
+  expect!(removed_again).to(be_ok().value(false));
+  expect!(after_remove.status().as_u16()).to(be_equal_to(500));
+  expect!(interactions).to(be_equal_to(1));
+  expect!(manager.upsert_mock_server_interaction(&"unknown".to_string(), &RequestResponseInteraction::default())).to(be_err());
+}
+
+#[test]
+fn interactions_of_a_running_mock_server_can_be_replaced_from_a_pact() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "a request for a user".to_string(),
+        request: Request { path: "/users/1".to_string(), .. Request::default() },
+        response: Response { status: 200, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    specification_version: PactSpecification::V3,
+    .. RequestResponsePact::default()
+  };
+  let new_pact = V4Pact {
+    interactions: vec![
+      SynchronousHttp {
+        description: "a request for the users".to_string(),
+        request: HttpRequest { path: "/users".to_string(), .. HttpRequest::default() },
+        response: HttpResponse { status: 200, .. HttpResponse::default() },
+        .. SynchronousHttp::default()
+      }.boxed_v4()
+    ],
+    .. V4Pact::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "interactions_of_a_running_mock_server_can_be_replaced_from_a_pact".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, MockServerConfig::default()).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  let result = manager.replace_mock_server_interactions(&id, &new_pact);
+  let old_response = client.get(format!("http://127.0.0.1:{}/users/1", port).as_str()).send().unwrap();
+  let new_response = client.get(format!("http://127.0.0.1:{}/users", port).as_str()).send().unwrap();
+
+  let spec_version = manager.find_mock_server_by_id(&id, &|ms| ms.pact.lock().unwrap().specification_version()).unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(result).to(be_ok());
+  expect!(old_response.status().as_u16()).to(be_equal_to(500));
+  expect!(new_response.status().as_u16()).to(be_equal_to(200));
+  expect!(spec_version).to(be_equal_to(PactSpecification::V3));
+}
+
+#[tokio::test]
+async fn mock_server_handle_runs_the_mock_server_on_the_callers_runtime() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "a request for a user".to_string(),
+        request: Request { path: "/users/1".to_string(), .. Request::default() },
+        response: Response { status: 200, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let handle = MockServerHandle::start(pact.boxed(), ([127, 0, 0, 1], 0).into(), MockServerConfig::default())
+    .await.unwrap();
+
+  let matched_before = handle.matched();
+  let response = reqwest::get(format!("{}/users/1", handle.url()).as_str()).await.unwrap();
+  let port = handle.port().unwrap();
+  let matched_after = handle.matched();
+  let journal = handle.journal();
+  let result = handle.shutdown().await;
+
+  expect!(matched_before).to(be_false());
+  expect!(response.status().as_u16()).to(be_equal_to(200));
+  expect!(matched_after).to(be_true());
+  expect!(journal.len()).to(be_equal_to(1));
+  expect!(result).to(be_ok());
+  expect!(std::net::TcpStream::connect(("127.0.0.1", port))).to(be_err());
+}
+
+#[test]
+fn mock_server_decodes_request_bodies_and_encodes_response_bodies() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "a compressed request".to_string(),
+        request: Request {
+          method: "POST".to_string(),
+          path: "/users".to_string(),
+          headers: Some(hashmap!{ "Content-Type".to_string() => vec!["application/json".to_string()] }),
+          body: OptionalBody::Present("{\"name\":\"Fred\"}".into(), None),
+          .. Request::default()
+        },
+        response: Response {
+          status: 201,
+          headers: Some(hashmap!{
+            "Content-Type".to_string() => vec!["application/json".to_string()],
+            "Content-Encoding".to_string() => vec!["br".to_string()]
+          }),
+          body: OptionalBody::Present("{\"id\":1}".into(), None),
+          .. Response::default()
+        },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_decodes_request_bodies_and_encodes_response_bodies".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, MockServerConfig::default()).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  let response = client.post(format!("http://127.0.0.1:{}/users", port).as_str())
+    .header("Content-Type", "application/json")
+    .header("Content-Encoding", "gzip")
+    .body(ContentEncoding::Gzip.encode("{\"name\":\"Fred\"}".as_bytes()).unwrap())
+    .send()
+    .unwrap();
+  let status = response.status().as_u16();
+  let body = response.bytes().unwrap();
+
+  let mismatches = manager.find_mock_server_by_id(&id, &|ms| ms.mismatches()).unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(status).to(be_equal_to(201));
+  expect!(ContentEncoding::Brotli.decode(&body).unwrap()).to(be_equal_to("{\"id\":1}".as_bytes().to_vec()));
+  expect!(mismatches.iter()).to(be_empty());
+}
+
+#[test]
+fn closest_candidates_are_ordered_by_their_match_score() {
+  let interaction1 = RequestResponseInteraction {
+    description: "a request for user 1".to_string(),
+    request: Request { method: "POST".to_string(), path: "/users/1".to_string(), .. Request::default() },
+    .. RequestResponseInteraction::default()
+  };
+  let interaction2 = RequestResponseInteraction {
+    description: "a request for user 2".to_string(),
+    request: Request { path: "/users/2".to_string(), .. Request::default() },
+    .. RequestResponseInteraction::default()
+  };
+  let interaction3 = RequestResponseInteraction {
+    description: "a request for the users".to_string(),
+    request: Request { method: "POST".to_string(), path: "/users".to_string(), .. Request::default() },
+    .. RequestResponseInteraction::default()
+  };
+  let request = Request { path: "/users/2".to_string(), query: Some(hashmap!{ "a".to_string() => vec!["b".to_string()] }), .. Request::default() };
+
+  let candidates = closest_candidates(&request, vec![
+    &interaction1 as &dyn Interaction,
+    &interaction2 as &dyn Interaction,
+    &interaction3 as &dyn Interaction
+  ], 2);
+
+  expect!(candidates.len()).to(be_equal_to(2));
+  expect!(candidates[0].description.as_str()).to(be_equal_to("a request for user 2"));
+  expect!(candidates[0].mismatches.is_empty()).to(be_false());
+  expect!(candidates[0].score > candidates[1].score).to(be_true());
+  expect!(candidates[0].diff(&request).lines().filter(|line| line.starts_with('+')).count() > 0).to(be_true());
+}
+
+#[test]
+fn mock_server_returns_the_closest_candidates_for_unmatched_requests() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "a request to create a user".to_string(),
+        request: Request {
+          method: "POST".to_string(),
+          path: "/users".to_string(),
+          headers: Some(hashmap!{ "Content-Type".to_string() => vec!["application/json".to_string()] }),
+          body: OptionalBody::Present("{\"name\":\"Fred\"}".into(), None),
+          .. Request::default()
+        },
+        response: Response { status: 201, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let config = MockServerConfig {
+    unmatched_status: Some(418),
+    .. MockServerConfig::default()
+  };
+  let mut manager = ServerManager::new();
+  let id = "mock_server_returns_the_closest_candidates_for_unmatched_requests".to_string();
+  let port = manager.start_mock_server(id.clone(), pact.boxed(), 0, config).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  let response = client.post(format!("http://127.0.0.1:{}/users", port).as_str())
+    .header("Content-Type", "application/json")
+    .body("{\"name\":\"Bob\"}")
+    .send()
+    .unwrap();
+  let status = response.status().as_u16();
+  let body: serde_json::Value = response.json().unwrap();
+
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(status).to(be_equal_to(418));
+  expect!(body["type"].as_str()).to(be_some().value("request-mismatch"));
+  expect!(body["error"].as_str()).to(be_some().value("Request-Mismatch : POST /users"));
+  expect!(body["candidates"][0]["description"].as_str()).to(be_some().value("a request to create a user"));
+  expect!(body["candidates"][0]["mismatches"][0]["type"].as_str()).to(be_some().value("BodyMismatch"));
+  let diff = body["candidates"][0]["diff"].as_str().unwrap_or_default();
+  expect!(diff.contains("-")).to(be_true());
+  expect!(diff.contains("Bob")).to(be_true());
+}
+
+#[test]
+fn server_manager_aggregates_the_exercised_interactions_for_each_consumer_and_provider() {
+  let interaction = |description: &str, path: &str| RequestResponseInteraction {
+    description: description.to_string(),
+    request: Request { path: path.to_string(), .. Request::default() },
+    response: Response { status: 200, .. Response::default() },
+    .. RequestResponseInteraction::default()
+  };
+  let pact1 = RequestResponsePact {
+    interactions: vec![interaction("c request", "/c"), interaction("an unused request", "/unused")],
+    specification_version: PactSpecification::V3,
+    .. RequestResponsePact::default()
+  };
+  let pact2 = RequestResponsePact {
+    interactions: vec![interaction("a request", "/a")],
+    specification_version: PactSpecification::V3,
+    .. RequestResponsePact::default()
+  };
+  let mut manager = ServerManager::new();
+  let port1 = manager.start_mock_server("aggregator-1".to_string(), pact1.boxed(), 0, MockServerConfig::default()).unwrap();
+  let port2 = manager.start_mock_server("aggregator-2".to_string(), pact2.boxed(), 0, MockServerConfig::default()).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  client.get(format!("http://127.0.0.1:{}/c", port1).as_str()).send().unwrap();
+  client.get(format!("http://127.0.0.1:{}/a", port2).as_str()).send().unwrap();
+  manager.shutdown_mock_server_by_port(port1);
+
+  let pacts = manager.aggregated_pacts().unwrap();
+  manager.shutdown_mock_server_by_port(port2);
+
+  expect!(pacts.len()).to(be_equal_to(1));
+  let descriptions = pacts[0].interactions().iter().map(|i| i.description()).collect::<Vec<String>>();
+  expect!(descriptions).to(be_equal_to(vec!["a request".to_string(), "c request".to_string()]));
+  expect!(pacts[0].specification_version()).to(be_equal_to(PactSpecification::V3));
+}
+
+#[test]
+fn aggregated_pact_files_only_replace_the_interactions_previously_written_by_the_owner() {
+  let dir = std::env::temp_dir().join(format!("pact-aggregator-{}", uuid::Uuid::new_v4()));
+  let output_dir = Some(dir.to_string_lossy().to_string());
+  let run_tests = |owner: &str, paths: Vec<&str>| {
+    let pact = RequestResponsePact {
+      interactions: paths.iter().map(|path| RequestResponseInteraction {
+        description: format!("{} request", path),
+        request: Request { path: format!("/{}", path), .. Request::default() },
+        response: Response { status: 200, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      }).collect(),
+      specification_version: PactSpecification::V3,
+      .. RequestResponsePact::default()
+    };
+    let mut manager = ServerManager::new();
+    let port = manager.start_mock_server(format!("aggregator-{}", owner), pact.boxed(), 0, MockServerConfig::default()).unwrap();
+    let client = reqwest::blocking::Client::new();
+    for path in &paths {
+      client.get(format!("http://127.0.0.1:{}/{}", port, path).as_str()).send().unwrap();
+    }
+    let files = manager.write_aggregated_pacts(&output_dir, &Some(owner.to_string())).unwrap();
+    manager.shutdown_mock_server_by_port(port);
+    let pact = pact_matching::models::read_pact(&files[0]).unwrap();
+    pact.interactions().iter().map(|i| i.description()).collect::<Vec<String>>()
+  };
+
+  let first = run_tests("binary-1", vec!["a"]);
+  let second = run_tests("binary-2", vec!["b"]);
+  let third = run_tests("binary-1", vec!["c"]);
+  let _ = std::fs::remove_dir_all(&dir);
+
+  expect!(first).to(be_equal_to(vec!["a request".to_string()]));
+  expect!(second).to(be_equal_to(vec!["a request".to_string(), "b request".to_string()]));
+  expect!(third).to(be_equal_to(vec!["b request".to_string(), "c request".to_string()]));
+}
+
+#[derive(Debug, Default)]
+struct RecordingObserver {
+  events: std::sync::Mutex<Vec<String>>
+}
+
+impl crate::observer::MockServerObserver for RecordingObserver {
+  fn request_received(&self, _mock_server_id: &str, request: &Request) {
+    self.events.lock().unwrap().push(format!("received {}", request.path));
+  }
+
+  fn request_matched(&self, _mock_server_id: &str, request: &Request, _result: &MatchResult, response: &Response) {
+    self.events.lock().unwrap().push(format!("matched {} {}", request.path, response.status));
+  }
+
+  fn request_mismatched(&self, _mock_server_id: &str, request: &Request, _result: &MatchResult, response: &Response) {
+    self.events.lock().unwrap().push(format!("mismatched {} {}", request.path, response.status));
+  }
+
+  fn shutdown(&self, mock_server_id: &str, mismatches: &[MatchResult]) {
+    self.events.lock().unwrap().push(format!("shutdown {} {}", mock_server_id, mismatches.len()));
+  }
+}
+
+#[test]
+fn mock_server_notifies_the_observers_of_its_events() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "a request for a user".to_string(),
+        request: Request { path: "/users/1".to_string(), .. Request::default() },
+        response: Response { status: 200, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let observer = std::sync::Arc::new(RecordingObserver::default());
+  let config = MockServerConfig {
+    observers: vec![observer.clone()],
+    .. MockServerConfig::default()
+  };
+  let mut manager = ServerManager::new();
+  let port = manager.start_mock_server("observed".to_string(), pact.boxed(), 0, config).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  client.get(format!("http://127.0.0.1:{}/users/1", port).as_str()).send().unwrap();
+  client.get(format!("http://127.0.0.1:{}/users/2", port).as_str()).send().unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(observer.events.lock().unwrap().clone()).to(be_equal_to(vec![
+    "received /users/1".to_string(),
+    "matched /users/1 200".to_string(),
+    "received /users/2".to_string(),
+    "mismatched /users/2 500".to_string(),
+    "shutdown observed 1".to_string()
+  ]));
+}
+
+fn write_stub_pact_file(dir: &std::path::Path, name: &str, consumer: &str, paths: &[&str]) -> std::path::PathBuf {
+  let interactions: Vec<serde_json::Value> = paths.iter().map(|path| json!({
+    "description": format!("{} request", path),
+    "request": { "method": "GET", "path": format!("/{}", path) },
+    "response": { "status": 200 }
+  })).collect();
+  let pact = json!({
+    "consumer": { "name": consumer },
+    "provider": { "name": "stub_provider" },
+    "interactions": interactions,
+    "metadata": { "pactSpecification": { "version": "3.0.0" } }
+  });
+  let file = dir.join(name);
+  std::fs::write(&file, pact.to_string()).unwrap();
+  file
+}
+
+fn stub_pact_descriptions(pact: &V4Pact) -> Vec<String> {
+  pact.interactions.iter().map(|i| i.description()).collect()
+}
+
+#[test]
+fn load_stub_pact_loads_the_pact_files_in_a_directory() {
+  let dir = std::env::temp_dir().join(format!("pact-stub-{}", uuid::Uuid::new_v4()));
+  std::fs::create_dir_all(&dir).unwrap();
+  write_stub_pact_file(&dir, "b.json", "consumer_b", &["b"]);
+  write_stub_pact_file(&dir, "a.json", "consumer_a", &["a1", "a2"]);
+  std::fs::write(dir.join("notes.txt"), "not a pact file").unwrap();
+
+  let result = load_stub_pact(&[dir.clone()]);
+  let _ = std::fs::remove_dir_all(&dir);
+
+  let pact = result.unwrap();
+  expect!(pact.consumer.name).to(be_equal_to("consumer_a".to_string()));
+  expect!(stub_pact_descriptions(&pact)).to(be_equal_to(vec!["a1 request".to_string(),
+    "a2 request".to_string(), "b request".to_string()]));
+}
+
+#[test]
+fn load_stub_pact_combines_the_interactions_of_several_pact_files() {
+  let dir = std::env::temp_dir().join(format!("pact-stub-{}", uuid::Uuid::new_v4()));
+  let sub_dir = dir.join("more");
+  std::fs::create_dir_all(&sub_dir).unwrap();
+  let first = write_stub_pact_file(&dir, "first.json", "consumer_1", &["one"]);
+  let second = write_stub_pact_file(&dir, "second.json", "consumer_2", &["two"]);
+  write_stub_pact_file(&sub_dir, "third.json", "consumer_3", &["three"]);
+
+  let result = load_stub_pact(&[second, first, sub_dir]);
+  let _ = std::fs::remove_dir_all(&dir);
+
+  let pact = result.unwrap();
+  expect!(pact.consumer.name).to(be_equal_to("consumer_2".to_string()));
+  expect!(stub_pact_descriptions(&pact)).to(be_equal_to(vec!["two request".to_string(),
+    "one request".to_string(), "three request".to_string()]));
+}
+
+#[test]
+fn load_stub_pact_returns_an_error_if_there_are_no_pact_files() {
+  let dir = std::env::temp_dir().join(format!("pact-stub-{}", uuid::Uuid::new_v4()));
+  std::fs::create_dir_all(&dir).unwrap();
+  std::fs::write(dir.join("notes.txt"), "not a pact file").unwrap();
+
+  let result = load_stub_pact(&[dir.clone()]);
+  let _ = std::fs::remove_dir_all(&dir);
+
+  expect!(result).to(be_err().value("No pact files were found".to_string()));
+}
+
+#[test]
+fn load_stub_pact_returns_an_error_if_a_pact_file_can_not_be_loaded() {
+  let dir = std::env::temp_dir().join(format!("pact-stub-{}", uuid::Uuid::new_v4()));
+  std::fs::create_dir_all(&dir).unwrap();
+  let file = dir.join("invalid.json");
+  std::fs::write(&file, "not json").unwrap();
+
+  let result = load_stub_pact(&[dir.clone()]);
+  let _ = std::fs::remove_dir_all(&dir);
+
+  let error = result.unwrap_err();
+  expect!(error.starts_with(&format!("Could not load pact file '{}'", file.display()))).to(be_true());
+}
+
+#[test]
+fn mock_server_in_stub_mode_responds_with_the_best_matching_interaction() {
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "a request for users".to_string(),
+        request: Request {
+          path: "/users".to_string(),
+          query: Some(hashmap!{ "page".to_string() => vec!["1".to_string()] }),
+          .. Request::default()
+        },
+        response: Response { status: 200, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      },
+      RequestResponseInteraction {
+        description: "a request to create a user".to_string(),
+        request: Request { method: "POST".to_string(), path: "/users".to_string(), .. Request::default() },
+        response: Response { status: 201, .. Response::default() },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let config = MockServerConfig { stub: true, .. MockServerConfig::default() };
+  let mut manager = ServerManager::new();
+  let port = manager.start_mock_server("stub".to_string(), pact.boxed(), 0, config).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  let loose = client.get(format!("http://127.0.0.1:{}/users?page=2", port).as_str())
+    .header("X-Trace-Id", "1234")
+    .send().unwrap();
+  let unknown = client.get(format!("http://127.0.0.1:{}/orders", port).as_str()).send().unwrap();
+  let mismatches = manager.find_mock_server_by_port_mut(port, &|ms| ms.mismatches()).unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(loose.status().as_u16()).to(be_equal_to(200));
+  expect!(unknown.status().as_u16()).to(be_equal_to(404));
+  expect!(mismatches.iter()).to(be_empty());
+}
+
+#[test]
+fn mock_server_generates_response_values_from_the_request() {
+  let mut generators = Generators::default();
+  generators.add_generator_with_subcategory(&GeneratorCategory::BODY, "$.orderId",
+    Generator::RequestValue("$.body.orderId".to_string(), None));
+  generators.add_generator_with_subcategory(&GeneratorCategory::HEADER, "X-Page",
+    Generator::RequestValue("$.query.page".to_string(), None));
+  let pact = RequestResponsePact {
+    interactions: vec![
+      RequestResponseInteraction {
+        description: "a request to create an order".to_string(),
+        request: Request {
+          method: "POST".to_string(),
+          path: "/orders".to_string(),
+          query: Some(hashmap!{ "page".to_string() => vec!["1".to_string()] }),
+          headers: Some(hashmap!{ "Content-Type".to_string() => vec!["application/json".to_string()] }),
+          body: OptionalBody::Present("{\"orderId\": 1}".into(), None),
+          matching_rules: matchingrules!{
+            "body" => { "$.orderId" => [ MatchingRule::Integer ] },
+            "query" => { "page" => [ MatchingRule::Regex("\\d+".to_string()) ] }
+          },
+          .. Request::default()
+        },
+        response: Response {
+          status: 201,
+          headers: Some(hashmap!{
+            "Content-Type".to_string() => vec!["application/json".to_string()],
+            "X-Page".to_string() => vec!["1".to_string()]
+          }),
+          body: OptionalBody::Present("{\"orderId\": 1}".into(), None),
+          generators,
+          .. Response::default()
+        },
+        .. RequestResponseInteraction::default()
+      }
+    ],
+    .. RequestResponsePact::default()
+  };
+  let mut manager = ServerManager::new();
+  let port = manager.start_mock_server("request-values".to_string(), pact.boxed(), 0, MockServerConfig::default()).unwrap();
+
+  let client = reqwest::blocking::Client::new();
+  let response = client.post(format!("http://127.0.0.1:{}/orders?page=7", port).as_str())
+    .header("Content-Type", "application/json")
+    .body("{\"orderId\": 1234}")
+    .send().unwrap();
+  manager.shutdown_mock_server_by_port(port);
+
+  expect!(response.status().as_u16()).to(be_equal_to(201));
+  expect!(response.headers().get("X-Page").unwrap().to_str().unwrap()).to(be_equal_to("7"));
+  let body: serde_json::Value = response.json().unwrap();
+  expect!(body).to(be_equal_to(serde_json::json!({ "orderId": 1234 })));
+}
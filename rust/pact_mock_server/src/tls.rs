@@ -22,9 +22,31 @@
 
 use std::fs::File;
 use std::io::{self, BufReader, Cursor, Read};
+use std::net::IpAddr;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
-use tokio_rustls::rustls::{NoClientAuth, ServerConfig, TLSError};
+use lazy_static::lazy_static;
+use rcgen::{
+  BasicConstraints,
+  Certificate,
+  CertificateParams,
+  DistinguishedName,
+  DnType,
+  IsCa,
+  KeyPair,
+  RcgenError,
+  SanType
+};
+
+use tokio_rustls::rustls::{
+  AllowAnyAnonymousOrAuthenticatedClient,
+  AllowAnyAuthenticatedClient,
+  NoClientAuth,
+  RootCertStore,
+  ServerConfig,
+  TLSError
+};
 
 /// Represents errors that can occur building the TlsConfig
 #[derive(Debug)]
@@ -41,6 +63,8 @@ pub enum TlsConfigError {
   EmptyKey,
   /// An error from an invalid key
   InvalidKey(TLSError),
+  /// An error generating a certificate
+  CertGenerationError(RcgenError),
 }
 
 impl std::fmt::Display for TlsConfigError {
@@ -52,16 +76,30 @@ impl std::fmt::Display for TlsConfigError {
       TlsConfigError::RsaParseError => write!(f, "rsa parse error"),
       TlsConfigError::EmptyKey => write!(f, "key contains no private key"),
       TlsConfigError::InvalidKey(err) => write!(f, "key contains an invalid key, {}", err),
+      TlsConfigError::CertGenerationError(err) => write!(f, "certificate generation error, {}", err),
     }
   }
 }
 
 impl std::error::Error for TlsConfigError {}
 
+/// Tls client authentication configuration.
+enum TlsClientAuth {
+  /// No client auth.
+  Off,
+  /// Allow any anonymous or authenticated client.
+  Optional(Box<dyn Read + Send + Sync>),
+  /// Allow any authenticated client.
+  Required(Box<dyn Read + Send + Sync>),
+}
+
 /// Builder to set the configuration for the Tls server.
 pub struct TlsConfigBuilder {
   cert: Box<dyn Read + Send + Sync>,
   key: Box<dyn Read + Send + Sync>,
+  client_auth: TlsClientAuth,
+  subject_alt_names: Option<Vec<String>>,
+  certificate_authority: Option<Arc<CertificateAuthority>>,
 }
 
 impl std::fmt::Debug for TlsConfigBuilder {
@@ -76,6 +114,9 @@ impl TlsConfigBuilder {
     TlsConfigBuilder {
       key: Box::new(io::empty()),
       cert: Box::new(io::empty()),
+      client_auth: TlsClientAuth::Off,
+      subject_alt_names: None,
+      certificate_authority: None,
     }
   }
 
@@ -109,8 +150,89 @@ impl TlsConfigBuilder {
     self
   }
 
+  /// Sets the trust anchor for optional Tls client authentication via file path.
+  ///
+  /// Anonymous and authenticated clients will be accepted. If no trust anchor is provided by any
+  /// of the `client_auth_` methods, then client authentication is disabled by default.
+  pub fn client_auth_optional_path(mut self, path: impl AsRef<Path>) -> Self {
+    let file = Box::new(LazyFile {
+      path: path.as_ref().into(),
+      file: None,
+    });
+    self.client_auth = TlsClientAuth::Optional(file);
+    self
+  }
+
+  /// Sets the trust anchor for optional Tls client authentication via bytes slice.
+  ///
+  /// Anonymous and authenticated clients will be accepted. If no trust anchor is provided by any
+  /// of the `client_auth_` methods, then client authentication is disabled by default.
+  pub fn client_auth_optional(mut self, trust_anchor: &[u8]) -> Self {
+    let cursor = Box::new(Cursor::new(Vec::from(trust_anchor)));
+    self.client_auth = TlsClientAuth::Optional(cursor);
+    self
+  }
+
+  /// Sets the trust anchor for required Tls client authentication via file path.
+  ///
+  /// Only authenticated clients will be accepted. If no trust anchor is provided by any of the
+  /// `client_auth_` methods, then client authentication is disabled by default.
+  pub fn client_auth_required_path(mut self, path: impl AsRef<Path>) -> Self {
+    let file = Box::new(LazyFile {
+      path: path.as_ref().into(),
+      file: None,
+    });
+    self.client_auth = TlsClientAuth::Required(file);
+    self
+  }
+
+  /// Sets the trust anchor for required Tls client authentication via bytes slice.
+  ///
+  /// Only authenticated clients will be accepted. If no trust anchor is provided by any of the
+  /// `client_auth_` methods, then client authentication is disabled by default.
+  pub fn client_auth_required(mut self, trust_anchor: &[u8]) -> Self {
+    let cursor = Box::new(Cursor::new(Vec::from(trust_anchor)));
+    self.client_auth = TlsClientAuth::Required(cursor);
+    self
+  }
+
+  /// Generates the server certificate in memory for the given DNS names and IP addresses, instead
+  /// of using a key and certificate. The certificate will be signed by the certificate authority
+  /// set with `certificate_authority`, otherwise a certificate authority generated for the process
+  /// is used.
+  pub fn generate_cert<S: Into<String>>(mut self, subject_alt_names: impl IntoIterator<Item = S>) -> Self {
+    self.subject_alt_names = Some(subject_alt_names.into_iter().map(|name| name.into()).collect());
+    self
+  }
+
+  /// Sets the certificate authority to sign the generated server certificate with
+  pub fn certificate_authority(mut self, ca: Arc<CertificateAuthority>) -> Self {
+    self.certificate_authority = Some(ca);
+    self
+  }
+
+  /// Returns the certificate of the certificate authority that signs the generated server
+  /// certificate in PEM format, so that clients can be configured to trust it. Returns an error
+  /// if the certificate authority for the process could not be generated.
+  pub fn ca_certificate_pem(&self) -> Result<String, TlsConfigError> {
+    match &self.certificate_authority {
+      Some(ca) => Ok(ca.certificate_pem()),
+      None => process_certificate_authority().map(|ca| ca.certificate_pem())
+    }
+  }
+
   /// Build the TLS configuration
   pub fn build(mut self) -> Result<ServerConfig, TlsConfigError> {
+    if let Some(subject_alt_names) = &self.subject_alt_names {
+      let ca = match &self.certificate_authority {
+        Some(ca) => ca.clone(),
+        None => process_certificate_authority()?
+      };
+      let (cert, key) = ca.generate_cert(subject_alt_names)?;
+      self.cert = Box::new(Cursor::new(cert.into_bytes()));
+      self.key = Box::new(Cursor::new(key.into_bytes()));
+    }
+
     let mut cert_rdr = BufReader::new(self.cert);
     let cert = tokio_rustls::rustls::internal::pemfile::certs(&mut cert_rdr)
       .map_err(|()| TlsConfigError::CertParseError)?;
@@ -147,7 +269,27 @@ impl TlsConfigBuilder {
       }
     };
 
-    let mut config = ServerConfig::new(NoClientAuth::new());
+    fn read_trust_anchor(trust_anchor: Box<dyn Read + Send + Sync>) -> Result<RootCertStore, TlsConfigError> {
+      let mut reader = BufReader::new(trust_anchor);
+      let mut store = RootCertStore::empty();
+      if let Ok((0, _)) | Err(()) = store.add_pem_file(&mut reader) {
+        Err(TlsConfigError::CertParseError)
+      } else {
+        Ok(store)
+      }
+    }
+
+    let client_auth = match self.client_auth {
+      TlsClientAuth::Off => NoClientAuth::new(),
+      TlsClientAuth::Optional(trust_anchor) => {
+        AllowAnyAnonymousOrAuthenticatedClient::new(read_trust_anchor(trust_anchor)?)
+      }
+      TlsClientAuth::Required(trust_anchor) => {
+        AllowAnyAuthenticatedClient::new(read_trust_anchor(trust_anchor)?)
+      }
+    };
+
+    let mut config = ServerConfig::new(client_auth);
     config
       .set_single_cert(cert, key)
       .map_err(|err| TlsConfigError::InvalidKey(err))?;
@@ -156,6 +298,90 @@ impl TlsConfigBuilder {
   }
 }
 
+lazy_static! {
+  static ref PROCESS_CA: Mutex<Option<Arc<CertificateAuthority>>> = Mutex::new(None);
+}
+
+/// Returns the certificate authority generated for this process, which signs the generated server
+/// certificates when no other certificate authority has been set. It is generated the first time
+/// it is needed.
+pub fn process_certificate_authority() -> Result<Arc<CertificateAuthority>, TlsConfigError> {
+  let mut process_ca = PROCESS_CA.lock().unwrap();
+  match &*process_ca {
+    Some(ca) => Ok(ca.clone()),
+    None => {
+      let ca = Arc::new(CertificateAuthority::new()?);
+      *process_ca = Some(ca.clone());
+      Ok(ca)
+    }
+  }
+}
+
+/// Certificate authority that signs the certificates generated for the TLS mock servers
+pub struct CertificateAuthority {
+  certificate: Certificate,
+  certificate_pem: String
+}
+
+impl std::fmt::Debug for CertificateAuthority {
+  fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+    f.debug_struct("CertificateAuthority").finish()
+  }
+}
+
+impl CertificateAuthority {
+  /// Generates a new certificate authority
+  pub fn new() -> Result<CertificateAuthority, TlsConfigError> {
+    let mut params = CertificateParams::default();
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params.distinguished_name = DistinguishedName::new();
+    params.distinguished_name.push(DnType::CommonName, "Pact Mock Server CA");
+    Certificate::from_params(params)
+      .map_err(TlsConfigError::CertGenerationError)
+      .and_then(CertificateAuthority::from_certificate)
+  }
+
+  /// Loads the certificate authority from its certificate and private key in PEM format
+  pub fn from_pem(cert: &str, key: &str) -> Result<CertificateAuthority, TlsConfigError> {
+    let key_pair = KeyPair::from_pem(key).map_err(TlsConfigError::CertGenerationError)?;
+    CertificateParams::from_ca_cert_pem(cert, key_pair)
+      .and_then(Certificate::from_params)
+      .map_err(TlsConfigError::CertGenerationError)
+      .and_then(CertificateAuthority::from_certificate)
+  }
+
+  fn from_certificate(certificate: Certificate) -> Result<CertificateAuthority, TlsConfigError> {
+    let certificate_pem = certificate.serialize_pem().map_err(TlsConfigError::CertGenerationError)?;
+    Ok(CertificateAuthority { certificate, certificate_pem })
+  }
+
+  /// Returns the certificate of this certificate authority in PEM format
+  pub fn certificate_pem(&self) -> String {
+    self.certificate_pem.clone()
+  }
+
+  /// Generates a certificate signed by this certificate authority for the DNS names and IP
+  /// addresses. The first name is used as the common name of the certificate. Returns the
+  /// certificate and its private key in PEM format.
+  pub fn generate_cert(&self, subject_alt_names: &[String]) -> Result<(String, String), TlsConfigError> {
+    let mut params = CertificateParams::default();
+    params.subject_alt_names = subject_alt_names.iter()
+      .map(|name| match name.parse::<IpAddr>() {
+        Ok(ip) => SanType::IpAddress(ip),
+        Err(_) => SanType::DnsName(name.clone())
+      })
+      .collect();
+    params.distinguished_name = DistinguishedName::new();
+    if let Some(name) = subject_alt_names.first() {
+      params.distinguished_name.push(DnType::CommonName, name.as_str());
+    }
+    let cert = Certificate::from_params(params).map_err(TlsConfigError::CertGenerationError)?;
+    let cert_pem = cert.serialize_pem_with_signer(&self.certificate)
+      .map_err(TlsConfigError::CertGenerationError)?;
+    Ok((cert_pem, cert.serialize_private_key_pem()))
+  }
+}
+
 struct LazyFile {
   path: PathBuf,
   file: Option<File>,
@@ -1,4 +1,5 @@
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use clap::ArgMatches;
 use log::*;
@@ -6,23 +7,120 @@ use serde_json::Value;
 use itertools::Itertools;
 
 use pact_matching::models::{ReadWritePact, Pact, RequestResponsePact};
+use pact_mock_server::load_stub_pact;
+use pact_models::query_strings::encode_query;
 
 use crate::handle_error;
 
 pub async fn create_mock_server(host: &str, port: u16, matches: &ArgMatches<'_>) -> Result<(), i32> {
-  let file = matches.value_of("file").unwrap();
+  let files = matches.values_of("file").unwrap().collect::<Vec<&str>>();
+  let file = files.iter().join(", ");
+  let stub = matches.is_present("stub");
+  if files.len() > 1 && !stub {
+    crate::display_error("Multiple pact files can only be used with --stub".to_string(), matches);
+  }
   log::info!("Creating mock server from file {}", file);
 
-  match RequestResponsePact::read_pact(&Path::new(file)) {
+  let pact = if stub {
+    let paths = files.iter().map(PathBuf::from).collect::<Vec<PathBuf>>();
+    load_stub_pact(&paths).map(|pact| pact.boxed())
+  } else {
+    RequestResponsePact::read_pact(&Path::new(files[0]))
+      .map(|pact| pact.boxed())
+      .map_err(|err| err.to_string())
+  };
+  match pact {
     Ok(ref pact) => {
       let mut args = vec![];
+      if stub {
+        info!("Setting mock server to run as a stub server");
+        args.push("stub=true".to_string());
+      }
       if matches.is_present("cors") {
         info!("Setting mock server to handle CORS pre-flight requests");
-        args.push("cors=true");
+        args.push("cors=true".to_string());
+      }
+      if let Some(socket_path) = matches.value_of("unix-socket") {
+        info!("Setting mock server to bind to Unix domain socket '{}'", socket_path);
+        args.push(format!("unixSocket={}", encode_query(socket_path)));
+      }
+      if let Some(origins) = matches.values_of("cors-origin") {
+        for origin in origins {
+          info!("Setting mock server to allow CORS requests from '{}'", origin);
+          args.push(format!("corsOrigin={}", encode_query(origin)));
+        }
+      }
+      if matches.is_present("cors-credentials") {
+        info!("Setting mock server to allow credentialed CORS requests");
+        args.push("corsCredentials=true".to_string());
+      }
+      if let Some(headers) = matches.values_of("cors-expose-header") {
+        for header in headers {
+          info!("Setting mock server to expose the '{}' header to CORS requests", header);
+          args.push(format!("corsExposeHeader={}", encode_query(header)));
+        }
+      }
+      if let Some(max_age) = matches.value_of("cors-max-age") {
+        info!("Setting mock server CORS pre-flight max age to {} seconds", max_age);
+        args.push(format!("corsMaxAge={}", max_age));
+      }
+      if matches.is_present("no-cors-headers") {
+        info!("Setting mock server to not add any CORS headers");
+        args.push("corsHeaders=false".to_string());
       }
       if matches.is_present("tls") {
         info!("Setting mock server to use TLS");
-        args.push("tls=true");
+        args.push("tls=true".to_string());
+      }
+      if let Some(names) = matches.values_of("tls-san") {
+        for name in names {
+          info!("Setting mock server to generate the TLS certificate for '{}'", name);
+          args.push(format!("tlsSan={}", encode_query(name)));
+        }
+      }
+      if let Some(client_ca) = matches.value_of("client-ca") {
+        let client_auth = if matches.is_present("require-client-cert") { "required" } else { "optional" };
+        info!("Setting mock server to authenticate TLS clients using '{}' ({})", client_ca, client_auth);
+        args.push(format!("clientCa={}", encode_query(client_ca)));
+        args.push(format!("clientAuth={}", client_auth));
+      }
+      if matches.is_present("ordered") {
+        info!("Setting mock server to require the interactions in order");
+        args.push("ordered=true".to_string());
+      }
+      if let Some(delay) = matches.value_of("delay") {
+        info!("Setting mock server to delay the responses by {}ms", delay);
+        args.push(format!("delay={}", delay));
+      }
+      if let Some(interval) = matches.value_of("drip-feed") {
+        info!("Setting mock server to send the response bodies one byte every {}ms", interval);
+        args.push(format!("dripFeed={}", interval));
+      }
+      if let Some(length) = matches.value_of("truncate-body") {
+        info!("Setting mock server to truncate the response bodies to {} bytes", length);
+        args.push(format!("truncateBody={}", length));
+      }
+      if let Some(status) = matches.value_of("fault-status") {
+        info!("Setting mock server to respond with status {}", status);
+        args.push(format!("faultStatus={}", status));
+      }
+      if matches.is_present("reset-connection") {
+        info!("Setting mock server to reset the connections instead of responding");
+        args.push("resetConnection=true".to_string());
+      }
+      if let Some(status) = matches.value_of("unmatched-status") {
+        info!("Setting mock server to respond to unmatched requests with status {}", status);
+        args.push(format!("unmatchedStatus={}", status));
+      }
+      if let Some(seed) = matches.value_of("generator-seed") {
+        info!("Setting mock server to seed the generators with {}", seed);
+        args.push(format!("generatorSeed={}", seed));
+      }
+      if matches.is_present("watch") {
+        let pact_file = fs::canonicalize(files[0]).map(|path| path.to_string_lossy().to_string())
+          .unwrap_or_else(|_| files[0].to_string());
+        info!("Setting mock server to reload the interactions when '{}' changes", pact_file);
+        args.push(format!("watchFile={}", encode_query(pact_file.as_str())));
       }
       let url = if args.is_empty() {
         format!("http://{}:{}/", host, port)
@@ -50,10 +148,25 @@ pub async fn create_mock_server(host: &str, port: u16, matches: &ArgMatches<'_>)
                 let id = mock_server.get("id")
                   .ok_or_else(|| handle_error("Invalid JSON received from master server - mockServer has no id attribute"))?
                   .as_str().ok_or_else(|| handle_error("Invalid JSON received from master server - mockServer id attribute is not a string"))?;
-                let port = mock_server.get("port")
-                  .ok_or_else(|| handle_error("Invalid JSON received from master server - mockServer has no port attribute"))?
-                  .as_u64().ok_or_else(|| handle_error("Invalid JSON received from master server - mockServer port attribute is not a number"))?;
-                println!("Mock server {} started on port {}", id, port);
+                if let Some(socket_path) = mock_server.get("unixSocket").and_then(|path| path.as_str()) {
+                  println!("Mock server {} started on Unix domain socket {}", id, socket_path);
+                } else {
+                  let port = mock_server.get("port")
+                    .ok_or_else(|| handle_error("Invalid JSON received from master server - mockServer has no port attribute"))?
+                    .as_u64().ok_or_else(|| handle_error("Invalid JSON received from master server - mockServer port attribute is not a number"))?;
+                  println!("Mock server {} started on port {}", id, port);
+                }
+                if let Some(ca_cert) = json.get("caCertificate").and_then(|ca| ca.as_str()) {
+                  match matches.value_of("ca-cert-out") {
+                    Some(path) => {
+                      fs::write(path, ca_cert).map_err(|err| {
+                        handle_error(format!("Failed to write the CA certificate to '{}' - {}", path, err).as_str())
+                      })?;
+                      println!("CA certificate written to {}", path);
+                    },
+                    None => println!("{}", ca_cert)
+                  }
+                }
                 Ok(())
               },
               Err(err) => {
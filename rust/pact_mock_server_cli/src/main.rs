@@ -21,6 +21,7 @@ use rand::Rng;
 use simplelog::{CombinedLogger, Config, SimpleLogger, TermLogger, WriteLogger};
 use uuid::Uuid;
 
+use pact_mock_server::faults::ResponseDelay;
 use pact_mock_server::server_manager::ServerManager;
 use pact_models::PactSpecification;
 
@@ -42,6 +43,7 @@ mod create_mock;
 mod list;
 mod verify;
 mod shutdown;
+mod reload;
 
 fn print_version() {
     println!("\npact mock server version  : v{}", clap::crate_version!());
@@ -114,6 +116,20 @@ fn uuid_value(v: String) -> Result<(), String> {
     Uuid::parse_str(v.as_str()).map(|_| ()).map_err(|e| format!("'{}' is not a valid UUID value: {}", v, e) )
 }
 
+fn number_value(v: String) -> Result<(), String> {
+    v.parse::<u64>().map(|_| ()).map_err(|e| format!("'{}' is not a valid number: {}", v, e) )
+}
+
+fn status_value(v: String) -> Result<(), String> {
+    v.parse::<u16>().map_err(|e| e.to_string())
+      .and_then(|status| if (100..600).contains(&status) { Ok(()) } else { Err("it is out of range".to_string()) })
+      .map_err(|e| format!("'{}' is not a valid HTTP status: {}", v, e) )
+}
+
+fn delay_value(v: String) -> Result<(), String> {
+    ResponseDelay::from_str(v.as_str()).map(|_| ())
+}
+
 #[tokio::main]
 async fn main() {
   match handle_command_args().await {
@@ -213,15 +229,120 @@ async fn handle_command_args() -> Result<(), i32> {
           .long("file")
           .takes_value(true)
           .use_delimiter(false)
+          .multiple(true)
+          .number_of_values(1)
           .required(true)
-          .help("the pact file to define the mock server"))
+          .help("the pact file to define the mock server (can be repeated with --stub, and can then also be a directory of pact files)"))
+        .arg(Arg::with_name("stub")
+          .long("stub")
+          .conflicts_with_all(&["ordered", "watch"])
+          .help("Run the mock server as a stub server, responding with the best matching interaction and never recording mismatches"))
         .arg(Arg::with_name("cors")
           .short("c")
           .long("cors-preflight")
           .help("Handle CORS pre-flight requests"))
+        .arg(Arg::with_name("cors-origin")
+          .long("cors-origin")
+          .takes_value(true)
+          .multiple(true)
+          .number_of_values(1)
+          .help("Only allow CORS requests from this origin (can be repeated, defaults to allowing any origin)"))
+        .arg(Arg::with_name("cors-credentials")
+          .long("cors-credentials")
+          .help("Allow credentialed CORS requests (the origin of the request is returned instead of the * wildcard)"))
+        .arg(Arg::with_name("cors-expose-header")
+          .long("cors-expose-header")
+          .takes_value(true)
+          .multiple(true)
+          .number_of_values(1)
+          .help("Response header that the browser is allowed to expose (can be repeated, defaults to Location and Link)"))
+        .arg(Arg::with_name("cors-max-age")
+          .long("cors-max-age")
+          .takes_value(true)
+          .use_delimiter(false)
+          .help("Number of seconds that browsers can cache the CORS pre-flight responses for")
+          .validator(number_value))
+        .arg(Arg::with_name("no-cors-headers")
+          .long("no-cors-headers")
+          .conflicts_with_all(&["cors", "cors-origin", "cors-credentials", "cors-expose-header", "cors-max-age"])
+          .help("Do not add any CORS headers to the responses"))
         .arg(Arg::with_name("tls")
           .long("tls")
           .help("Enable TLS with the mock server (will use a self-signed certificate)"))
+        .arg(Arg::with_name("unix-socket")
+          .long("unix-socket")
+          .takes_value(true)
+          .use_delimiter(false)
+          .conflicts_with("tls")
+          .help("Bind the mock server to a Unix domain socket at this path (on the master server) instead of a TCP port"))
+        .arg(Arg::with_name("tls-san")
+          .long("tls-san")
+          .takes_value(true)
+          .multiple(true)
+          .number_of_values(1)
+          .requires("tls")
+          .help("Generate the TLS certificate for this DNS name or IP address, signed by a CA generated by the master server (can be repeated)"))
+        .arg(Arg::with_name("ca-cert-out")
+          .long("ca-cert-out")
+          .takes_value(true)
+          .use_delimiter(false)
+          .requires("tls-san")
+          .help("File to write the CA certificate to that signed the generated TLS certificate (defaults to printing it out)"))
+        .arg(Arg::with_name("client-ca")
+          .long("client-ca")
+          .takes_value(true)
+          .use_delimiter(false)
+          .requires("tls")
+          .help("Path (on the master server) to a PEM file with the CA certificates to authenticate TLS client certificates with"))
+        .arg(Arg::with_name("require-client-cert")
+          .long("require-client-cert")
+          .requires("client-ca")
+          .help("Require clients to authenticate with a certificate signed by one of the client CA certificates"))
+        .arg(Arg::with_name("ordered")
+          .long("ordered")
+          .help("Require the interactions to be received in the order they are defined in the pact file"))
+        .arg(Arg::with_name("delay")
+          .long("delay")
+          .takes_value(true)
+          .use_delimiter(false)
+          .help("Delay the responses by a number of milliseconds, or a random number of milliseconds in a range (i.e. 100-500)")
+          .validator(delay_value))
+        .arg(Arg::with_name("drip-feed")
+          .long("drip-feed")
+          .takes_value(true)
+          .use_delimiter(false)
+          .help("Send the response bodies slowly, one byte every given number of milliseconds")
+          .validator(number_value))
+        .arg(Arg::with_name("truncate-body")
+          .long("truncate-body")
+          .takes_value(true)
+          .use_delimiter(false)
+          .help("Truncate the response bodies to the given number of bytes")
+          .validator(number_value))
+        .arg(Arg::with_name("fault-status")
+          .long("fault-status")
+          .takes_value(true)
+          .use_delimiter(false)
+          .help("Override the status of the responses with the given status code")
+          .validator(status_value))
+        .arg(Arg::with_name("reset-connection")
+          .long("reset-connection")
+          .help("Abruptly reset the connection instead of sending the responses"))
+        .arg(Arg::with_name("unmatched-status")
+          .long("unmatched-status")
+          .takes_value(true)
+          .use_delimiter(false)
+          .help("Status code to return for requests that do not match any interaction (defaults to 500)")
+          .validator(status_value))
+        .arg(Arg::with_name("generator-seed")
+          .long("generator-seed")
+          .takes_value(true)
+          .use_delimiter(false)
+          .help("Seed for the generators, so that the generated values in the responses are the same on every run")
+          .validator(number_value))
+        .arg(Arg::with_name("watch")
+          .long("watch")
+          .help("Reload the interactions of the mock server when the pact file changes (the file must be accessible to the master server)"))
         .setting(AppSettings::ColoredHelp))
       .subcommand(SubCommand::with_name("verify")
               .about("Verify the mock server by id or port number, and generate a pact file if all ok")
@@ -243,6 +364,33 @@ async fn handle_command_args() -> Result<(), i32> {
                   .help("the port number of the mock server")
                   .validator(integer_value))
               .setting(AppSettings::ColoredHelp))
+      .subcommand(SubCommand::with_name("reload")
+              .about("Replace the interactions of a running mock server by id or port number with the ones from a pact file")
+              .arg(Arg::with_name("mock-server-id")
+                  .short("i")
+                  .long("mock-server-id")
+                  .takes_value(true)
+                  .use_delimiter(false)
+                  .required_unless("mock-server-port")
+                  .conflicts_with("mock-server-port")
+                  .help("the ID of the mock server")
+                  .validator(uuid_value))
+              .arg(Arg::with_name("mock-server-port")
+                  .short("m")
+                  .long("mock-server-port")
+                  .takes_value(true)
+                  .use_delimiter(false)
+                  .required_unless("mock-server-host")
+                  .help("the port number of the mock server")
+                  .validator(integer_value))
+              .arg(Arg::with_name("file")
+                  .short("f")
+                  .long("file")
+                  .takes_value(true)
+                  .use_delimiter(false)
+                  .required(true)
+                  .help("the pact file with the new interactions"))
+              .setting(AppSettings::ColoredHelp))
       .subcommand(SubCommand::with_name("shutdown")
               .about("Shutdown the mock server by id or port number, releasing all its resources")
               .arg(Arg::with_name("mock-server-id")
@@ -315,6 +463,7 @@ async fn handle_command_args() -> Result<(), i32> {
             ("list", Some(sub_matches)) => list::list_mock_servers(host, p, sub_matches).await,
             ("create", Some(sub_matches)) => create_mock::create_mock_server(host, p, sub_matches).await,
             ("verify", Some(sub_matches)) => verify::verify_mock_server(host, p, sub_matches).await,
+            ("reload", Some(sub_matches)) => reload::reload_mock_server(host, p, sub_matches).await,
             ("shutdown", Some(sub_matches)) => shutdown::shutdown_mock_server(host, p, sub_matches).await,
             ("shutdown-master", Some(sub_matches)) => shutdown::shutdown_master_server(host, p, sub_matches).await,
             _ => Err(3)
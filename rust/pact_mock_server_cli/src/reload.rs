@@ -0,0 +1,49 @@
+use std::path::Path;
+
+use clap::ArgMatches;
+use http::StatusCode;
+
+use pact_matching::models::read_pact;
+
+pub async fn reload_mock_server(host: &str, port: u16, matches: &ArgMatches<'_>) -> Result<(), i32> {
+  let mock_server_id = matches.value_of("mock-server-id");
+  let mock_server_port = matches.value_of("mock-server-port");
+  let id = if let Some(id) = mock_server_id {
+    (id, "id")
+  } else {
+    (mock_server_port.unwrap(), "port")
+  };
+
+  let file = matches.value_of("file").unwrap();
+  log::info!("Reloading mock server with {} '{}' from file {}", id.1, id.0, file);
+  let json = match read_pact(Path::new(file))
+    .and_then(|pact| pact.to_json(pact.specification_version())) {
+    Ok(json) => json,
+    Err(err) => {
+      crate::display_error(format!("Failed to load pact file '{}': {}", file, err), matches);
+    }
+  };
+
+  let client = reqwest::Client::new();
+  let url = format!("http://{}:{}/mockserver/{}/interactions", host, port, id.0);
+  let resp = client.put(&url).json(&json).send().await;
+  match resp {
+    Ok(result) => {
+      if !result.status().is_success() {
+        match result.status() {
+          StatusCode::NOT_FOUND => {
+            println!("No mock server found with {} '{}', use the 'list' command to get a list of available mock servers.", id.1, id.0);
+            Err(3)
+          },
+          _ => crate::display_error(format!("Unexpected response from master mock server '{}': {}", url, result.status()), matches)
+        }
+      } else {
+        println!("Mock server with {} '{}' reloaded ok", id.1, id.0);
+        Ok(())
+      }
+    },
+    Err(err) => {
+      crate::display_error(format!("Failed to connect to the master mock server '{}': {}", url, err), matches);
+    }
+  }
+}
@@ -19,8 +19,12 @@ use webmachine_rust::*;
 use webmachine_rust::context::*;
 use webmachine_rust::headers::*;
 
-use pact_matching::models::load_pact_from_json;
+use pact_matching::models::{load_pact_from_json, Pact};
+use pact_mock_server::cors::CorsPolicy;
+use pact_mock_server::faults::{DripFeed, FaultConfig};
+use pact_mock_server::metrics::{LabelledMetrics, prometheus_text};
 use pact_mock_server::mock_server::MockServerConfig;
+use pact_mock_server::server_manager::ServerManager;
 use pact_mock_server::tls::TlsConfigBuilder;
 
 use crate::{SERVER_MANAGER, SERVER_OPTIONS, ServerOpts};
@@ -65,37 +69,76 @@ fn start_provider(context: &mut WebmachineContext, options: ServerOpts) -> Resul
           debug!("Loaded pact = {:?}", pact);
           let mock_server_id = Uuid::new_v4().to_string();
           let config = MockServerConfig {
-            cors_preflight: query_param_set(context, "cors")
+            cors_preflight: query_param_set(context, "cors"),
+            cors: cors_policy(context),
+            ordered_interactions: query_param_set(context, "ordered"),
+            faults: fault_config(context),
+            unmatched_status: query_param_value(context, "unmatchedStatus").and_then(|status| status.parse().ok()),
+            stub: query_param_set(context, "stub"),
+            generator_seed: query_param_value(context, "generatorSeed").and_then(|seed| seed.parse().ok()),
+            .. MockServerConfig::default()
           };
           debug!("Mock server config = {:?}", config);
 
           let mut guard = SERVER_MANAGER.lock().unwrap();
-          let result = if query_param_set(context, "tls") {
+          let mut ca_certificate = None;
+          let result = if let Some(socket_path) = query_param_value(context, "unixSocket") {
+            debug!("Starting mock server with id {} on Unix domain socket {}", &mock_server_id, socket_path);
+            start_unix_socket_mock_server(&mut guard, mock_server_id.clone(), pact, &socket_path, config)
+              .map(|_| json!({ "unixSocket": socket_path }))
+          } else if query_param_set(context, "tls") {
             debug!("Starting TLS mock server with id {}", &mock_server_id);
-            let key = include_str!("self-signed.key");
-            let cert = include_str!("self-signed.cert");
-            TlsConfigBuilder::new()
-              .key(key.as_bytes())
-              .cert(cert.as_bytes())
-              .build()
+            let subject_alt_names = context.request.query.get("tlsSan").cloned().unwrap_or_default();
+            let builder = if subject_alt_names.is_empty() {
+              let key = include_str!("self-signed.key");
+              let cert = include_str!("self-signed.cert");
+              TlsConfigBuilder::new()
+                .key(key.as_bytes())
+                .cert(cert.as_bytes())
+            } else {
+              debug!("Generating the TLS certificate for {:?}", subject_alt_names);
+              let builder = TlsConfigBuilder::new().generate_cert(subject_alt_names);
+              // If the certificate authority can not be generated, building the TLS config fails
+              ca_certificate = builder.ca_certificate_pem().ok();
+              builder
+            };
+            let builder = match query_param_value(context, "clientCa") {
+              Some(client_ca) => if query_param_value(context, "clientAuth").unwrap_or_default() == "required" {
+                debug!("Requiring TLS client certificates signed by the CAs in '{}'", client_ca);
+                builder.client_auth_required_path(client_ca)
+              } else {
+                debug!("Accepting TLS client certificates signed by the CAs in '{}'", client_ca);
+                builder.client_auth_optional_path(client_ca)
+              },
+              None => builder
+            };
+            builder.build()
               .map_err(|err| {
-                format!("Failed to setup TLS using self-signed certificate - {}", err)
+                format!("Failed to setup TLS - {}", err)
               })
               .and_then(|tls_config| {
                 guard.start_tls_mock_server(mock_server_id.clone(), pact, get_next_port(options.base_port), &tls_config, config)
               })
+              .map(|port| json!({ "port": port as i64 }))
           } else {
             debug!("Starting mock server with id {}", &mock_server_id);
             guard.start_mock_server(mock_server_id.clone(), pact, get_next_port(options.base_port), config)
+              .map(|port| json!({ "port": port as i64 }))
           };
           match result {
-            Ok(mock_server) => {
-              debug!("mock server started on port {}", mock_server);
-              let mock_server_json = json!({
-                "id" : json!(mock_server_id),
-                "port" : json!(mock_server as i64),
-              });
-              let json_response = json!({ "mockServer" : mock_server_json });
+            Ok(mut mock_server_json) => {
+              debug!("mock server started - {}", mock_server_json);
+              if let Some(pact_file) = query_param_value(context, "watchFile") {
+                if let Err(err) = guard.watch_mock_server_pact_file(&mock_server_id, std::path::Path::new(&pact_file),
+                  Duration::from_millis(500)) {
+                  warn!("Not reloading the interactions when the pact file changes - {}", err);
+                }
+              }
+              mock_server_json["id"] = json!(mock_server_id);
+              let mut json_response = json!({ "mockServer" : mock_server_json });
+              if let Some(ca_certificate) = ca_certificate {
+                json_response["caCertificate"] = json!(ca_certificate);
+              }
               context.response.body = Some(json_response.to_string().into_bytes());
               context.response.add_header("Location",
                 vec![HeaderValue::basic(format!("/mockserver/{}", mock_server_id).as_str())]);
@@ -122,12 +165,70 @@ fn start_provider(context: &mut WebmachineContext, options: ServerOpts) -> Resul
   }
 }
 
+#[cfg(unix)]
+fn start_unix_socket_mock_server(
+  manager: &mut ServerManager,
+  id: String,
+  pact: Box<dyn Pact>,
+  socket_path: &str,
+  config: MockServerConfig
+) -> Result<(), String> {
+  manager.start_mock_server_with_unix_socket(id, pact, std::path::Path::new(socket_path), config)
+}
+
+#[cfg(not(unix))]
+fn start_unix_socket_mock_server(
+  _manager: &mut ServerManager,
+  _id: String,
+  _pact: Box<dyn Pact>,
+  _socket_path: &str,
+  _config: MockServerConfig
+) -> Result<(), String> {
+  Err("Unix domain sockets are not supported on this platform".to_string())
+}
+
 fn query_param_set(context: &mut WebmachineContext, name: &str) -> bool {
   context.request.query.get(name)
     .unwrap_or(&vec![]).first().unwrap_or(&String::default())
     .eq("true")
 }
 
+fn query_param_value(context: &mut WebmachineContext, name: &str) -> Option<String> {
+  context.request.query.get(name).and_then(|values| values.first()).cloned()
+}
+
+fn cors_policy(context: &mut WebmachineContext) -> CorsPolicy {
+  if query_param_value(context, "corsHeaders").unwrap_or_default() == "false" {
+    return CorsPolicy::disabled();
+  }
+  let default_policy = CorsPolicy::default();
+  CorsPolicy {
+    allowed_origins: context.request.query.get("corsOrigin").cloned().unwrap_or_default(),
+    allow_credentials: query_param_set(context, "corsCredentials"),
+    exposed_headers: context.request.query.get("corsExposeHeader").cloned()
+      .unwrap_or_else(|| default_policy.exposed_headers.clone()),
+    max_age: query_param_value(context, "corsMaxAge").and_then(|max_age| max_age.parse().ok()),
+    .. default_policy
+  }
+}
+
+fn fault_config(context: &mut WebmachineContext) -> Option<FaultConfig> {
+  let faults = FaultConfig {
+    delay: query_param_value(context, "delay").and_then(|delay| delay.parse().ok()),
+    drip_feed: query_param_value(context, "dripFeed")
+      .and_then(|interval| interval.parse().ok())
+      .map(|interval| DripFeed { interval: Duration::from_millis(interval), .. DripFeed::default() }),
+    reset_connection: query_param_set(context, "resetConnection"),
+    truncate_body: query_param_value(context, "truncateBody").and_then(|length| length.parse().ok()),
+    status: query_param_value(context, "faultStatus").and_then(|status| status.parse().ok())
+  };
+  if faults == FaultConfig::default() {
+    None
+  } else {
+    Some(faults)
+  }
+}
+
 pub fn verify_mock_server_request(context: &mut WebmachineContext) -> Result<bool, u16> {
   let id = context.metadata.get("id").cloned().unwrap_or_default();
   match verify::validate_id(&id, &SERVER_MANAGER) {
@@ -156,6 +257,115 @@ pub fn verify_mock_server_request(context: &mut WebmachineContext) -> Result<boo
   }
 }
 
+fn set_provider_state_request(context: &mut WebmachineContext) -> Result<bool, u16> {
+  let id = context.metadata.get("id").cloned().unwrap_or_default();
+  let provider_state = match context.request.body {
+    Some(ref body) if !body.is_empty() => match serde_json::from_slice::<Value>(body) {
+      Ok(ref json) => match json.get("state") {
+        Some(Value::String(state)) => Ok(state.clone()),
+        _ => Err("The provider state must be supplied as a string in the 'state' attribute".to_string())
+      },
+      Err(err) => Err(format!("Failed to parse json body - {}", err))
+    },
+    _ => Err("No provider state was supplied".to_string())
+  };
+
+  match provider_state {
+    Ok(state) => {
+      info!("Setting provider state for mock server {} to '{}'", id, state);
+      if SERVER_MANAGER.lock().unwrap().set_mock_server_provider_state(&id, Some(state)) {
+        Ok(true)
+      } else {
+        Err(404)
+      }
+    },
+    Err(err) => {
+      error!("{}", err);
+      context.response.body = Some(json_error(err).into_bytes());
+      Err(422)
+    }
+  }
+}
+
+fn request_body(context: &mut WebmachineContext, what: &str) -> Result<String, u16> {
+  match context.request.body {
+    Some(ref body) if !body.is_empty() => match String::from_utf8(body.clone()) {
+      Ok(body) => Ok(body),
+      Err(err) => {
+        error!("Failed to read the {} - {}", what, err);
+        context.response.body = Some(json_error(format!("Failed to read the {} - {}", what, err)).into_bytes());
+        Err(422)
+      }
+    },
+    _ => {
+      error!("No {} was supplied", what);
+      context.response.body = Some(json_error(format!("No {} was supplied", what)).into_bytes());
+      Err(422)
+    }
+  }
+}
+
+fn upsert_interaction_request(context: &mut WebmachineContext) -> Result<bool, u16> {
+  let id = context.metadata.get("id").cloned().unwrap_or_default();
+  let body = request_body(context, "interaction json")?;
+  let result = pact_mock_server::interaction_from_json(body.as_str())
+    .and_then(|interaction| {
+      info!("Adding interaction '{}' to mock server {}", interaction.description(), id);
+      SERVER_MANAGER.lock().unwrap().upsert_mock_server_interaction(&id, interaction.as_ref())
+    });
+  match result {
+    Ok(_) => Ok(true),
+    Err(err) => {
+      error!("{}", err);
+      context.response.body = Some(json_error(err).into_bytes());
+      Err(422)
+    }
+  }
+}
+
+fn replace_interactions_request(context: &mut WebmachineContext) -> Result<bool, u16> {
+  let id = context.metadata.get("id").cloned().unwrap_or_default();
+  let body = request_body(context, "pact json")?;
+  let result = serde_json::from_str::<Value>(body.as_str())
+    .map_err(|err| format!("Failed to parse json body - {}", err))
+    .and_then(|json| load_pact_from_json(&context.request.request_path, &json)
+      .map_err(|err| format!("Failed to parse Pact JSON - {}", err)))
+    .and_then(|pact| {
+      info!("Replacing the interactions of mock server {}", id);
+      SERVER_MANAGER.lock().unwrap().replace_mock_server_interactions(&id, pact.as_ref())
+    });
+  match result {
+    Ok(_) => Ok(true),
+    Err(err) => {
+      error!("{}", err);
+      context.response.body = Some(json_error(err).into_bytes());
+      Err(422)
+    }
+  }
+}
+
+fn remove_interaction_request(context: &mut WebmachineContext) -> Result<bool, u16> {
+  let id = context.metadata.get("id").cloned().unwrap_or_default();
+  match query_param_value(context, "description") {
+    Some(description) => {
+      info!("Removing interaction '{}' from mock server {}", description, id);
+      match SERVER_MANAGER.lock().unwrap().remove_mock_server_interaction(&id, description.as_str()) {
+        Ok(true) => Ok(true),
+        Ok(false) => Err(404),
+        Err(err) => {
+          error!("{}", err);
+          context.response.body = Some(json_error(err).into_bytes());
+          Err(422)
+        }
+      }
+    },
+    None => {
+      context.response.body = Some(json_error("The description of the interaction to remove must be supplied with the 'description' query parameter".to_string()).into_bytes());
+      Err(422)
+    }
+  }
+}
+
 fn shutdown_resource<'a>() -> WebmachineResource<'a> {
   WebmachineResource {
     allowed_methods: vec!["POST"],
@@ -203,9 +413,28 @@ fn shutdown_resource<'a>() -> WebmachineResource<'a> {
   }
 }
 
+fn metrics_resource<'a>() -> WebmachineResource<'a> {
+  WebmachineResource {
+    allowed_methods: vec!["OPTIONS", "GET", "HEAD"],
+    produces: vec!["text/plain"],
+    render_response: callback(&|_, _| {
+      debug!("metrics_resource -> render_response");
+      let metrics = SERVER_MANAGER.lock().unwrap().map_mock_servers(&|ms| {
+        LabelledMetrics {
+          mock_server_id: ms.id.clone(),
+          provider: ms.pact.lock().unwrap().provider().name,
+          metrics: ms.metrics.clone()
+        }
+      });
+      Some(prometheus_text(&metrics))
+    }),
+    ..WebmachineResource::default()
+  }
+}
+
 fn mock_server_resource<'a>() -> WebmachineResource<'a> {
   WebmachineResource {
-    allowed_methods: vec!["OPTIONS", "GET", "HEAD", "POST", "DELETE"],
+    allowed_methods: vec!["OPTIONS", "GET", "HEAD", "POST", "PUT", "DELETE"],
     resource_exists: callback(&|context, _| {
       debug!("mock_server_resource -> resource_exists");
       let paths: Vec<String> = context.request.request_path
@@ -220,7 +449,8 @@ fn mock_server_resource<'a>() -> WebmachineResource<'a> {
             context.metadata.insert("port".to_string(), ms.port.unwrap_or_default().to_string());
             if paths.len() > 1 {
               context.metadata.insert("subpath".to_string(), paths[1].clone());
-              paths[1] == "verify"
+              paths[1] == "verify" || paths[1] == "requests" || paths[1] == "provider-state" ||
+                paths[1] == "interactions" || paths[1] == "har"
             } else {
               true
             }
@@ -239,6 +469,28 @@ fn mock_server_resource<'a>() -> WebmachineResource<'a> {
           SERVER_MANAGER.lock().unwrap().find_mock_server_by_id(&id, &|ms| ms.to_json())
             .map(|json| json.to_string())
         }
+        Some(subpath) if subpath == "requests" => {
+          let id = context.metadata.get("id").unwrap().clone();
+          SERVER_MANAGER.lock().unwrap().find_mock_server_by_id(&id, &|ms| {
+            let requests = ms.journal().iter()
+              .map(|entry| entry.to_json())
+              .collect::<Vec<Value>>();
+            json!({ "requests": requests })
+          })
+            .map(|json| json.to_string())
+        }
+        Some(subpath) if subpath == "provider-state" => {
+          let id = context.metadata.get("id").unwrap().clone();
+          SERVER_MANAGER.lock().unwrap().find_mock_server_by_id(&id, &|ms| {
+            json!({ "state": ms.provider_state() })
+          })
+            .map(|json| json.to_string())
+        }
+        Some(subpath) if subpath == "har" => {
+          let id = context.metadata.get("id").unwrap().clone();
+          SERVER_MANAGER.lock().unwrap().find_mock_server_by_id(&id, &|ms| ms.to_har())
+            .map(|json| json.to_string())
+        }
         Some(_) => {
           context.response.status = 405;
           None
@@ -250,10 +502,21 @@ fn mock_server_resource<'a>() -> WebmachineResource<'a> {
       let subpath = context.metadata.get("subpath").unwrap().clone();
       if subpath == "verify" {
         verify_mock_server_request(context)
+      } else if subpath == "provider-state" {
+        set_provider_state_request(context)
+      } else if subpath == "interactions" {
+        upsert_interaction_request(context)
       } else {
         Err(422)
       }
     }),
+    process_put: callback(&|context, _| {
+      debug!("mock_server_resource -> process_put");
+      match context.metadata.get("subpath") {
+        Some(subpath) if subpath == "interactions" => replace_interactions_request(context),
+        _ => Err(405)
+      }
+    }),
     delete_resource: callback(&|context, _| {
       debug!("mock_server_resource -> delete_resource");
       match context.metadata.get("subpath") {
@@ -267,6 +530,15 @@ fn mock_server_resource<'a>() -> WebmachineResource<'a> {
             }
           }).join().expect("Could not spawn thread to shut down mock server")
         }
+        Some(subpath) if subpath == "provider-state" => {
+          let id = context.metadata.get("id").unwrap().clone();
+          if SERVER_MANAGER.lock().unwrap().set_mock_server_provider_state(&id, None) {
+            Ok(true)
+          } else {
+            Err(404)
+          }
+        }
+        Some(subpath) if subpath == "interactions" => remove_interaction_request(context),
         Some(_) => Err(405)
       }
     }),
@@ -332,6 +604,7 @@ fn dispatcher() -> WebmachineDispatcher<'static>  {
         }),
         .. WebmachineResource::default()
       },
+      "/metrics" => metrics_resource(),
       "/mockserver" => mock_server_resource(),
       "/shutdown" => shutdown_resource()
     }
@@ -114,6 +114,16 @@ fn display_verification_errors(id: &str, port: u64, json: &serde_json::Value) {
         let request = mismatch.get("request").unwrap();
         println!("{} - Received a request that was not expected - {}", i, request)
       },
+      "invocation-count-mismatch" => {
+        let request = mismatch.get("request").unwrap();
+        println!("{} - Expected request was received {} times but expected {} - {}", i,
+          mismatch.get("actual").unwrap(), mismatch.get("expected").unwrap(), request)
+      },
+      "request-out-of-order" => {
+        let request = mismatch.get("request").unwrap();
+        println!("{} - Received a request out of order (expected the interaction at position {}, but was at position {}) - {}", i,
+          mismatch.get("expectedPosition").unwrap(), mismatch.get("actualPosition").unwrap(), request)
+      },
       "request-mismatch" => {
         let path = mismatch.get("path").unwrap().to_string();
         let method = mismatch.get("method").unwrap().to_string();
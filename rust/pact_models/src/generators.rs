@@ -1,6 +1,7 @@
 //! `generators` module includes all the classes to deal with V3/V4 spec generators
 
 #[cfg(test)] use std::collections::hash_map::DefaultHasher;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt::{Debug, Display, Formatter};
@@ -10,7 +11,7 @@ use std::ops::Index;
 use std::str::FromStr;
 
 use anyhow::anyhow;
-use chrono::Local;
+use chrono::{DateTime, FixedOffset, Local, TimeZone};
 #[cfg(test)] use expectest::prelude::*;
 use indextree::{Arena, NodeId};
 use itertools::Itertools;
@@ -25,10 +26,12 @@ use uuid::Uuid;
 
 use crate::bodies::OptionalBody;
 use crate::expression_parser::{contains_expressions, DataType, DataValue, MapValueResolver, parse_expression};
+use crate::http_parts::HttpPart;
 use crate::json_utils::{get_field_as_string, json_to_string, JsonToNum};
 use crate::matchingrules::{Category, MatchingRuleCategory};
 use crate::PactSpecification;
 use crate::path_exp::{parse_path_exp, PathToken};
+use crate::request::Request;
 use crate::time_utils::{parse_pattern, to_chrono_pattern};
 
 /// Trait to represent matching logic to find a matching variant for the Array Contains generator
@@ -138,6 +141,9 @@ pub enum Generator {
   ProviderStateGenerator(String, Option<DataType>),
   /// Generates a URL with the mock server as the base URL
   MockServerURL(String, String),
+  /// Generates a value that is looked up from the request received by the mock server, using a
+  /// path expression (i.e. `$.path[1]`, `$.query.page`, `$.headers['x-id']` or `$.body.orderId`)
+  RequestValue(String, Option<DataType>),
   /// List of variants which can have embedded generators
   ArrayContains(Vec<(usize, MatchingRuleCategory, HashMap<String, Generator>)>)
 }
@@ -177,6 +183,13 @@ impl Generator {
         }
       }
       Generator::MockServerURL(example, regex) => Some(json!({ "type": "MockServerURL", "example": example, "regex": regex })),
+      Generator::RequestValue(ref expression, ref data_type) => {
+        if let Some(data_type) = data_type {
+          Some(json!({"type": "RequestValue", "expression": expression, "dataType": data_type}))
+        } else {
+          Some(json!({"type": "RequestValue", "expression": expression}))
+        }
+      },
       _ => None
     }
   }
@@ -207,6 +220,9 @@ impl Generator {
           .map(|dt| DataType::from(dt.clone())))),
       "MockServerURL" => Some(Generator::MockServerURL(get_field_as_string("example", map).unwrap_or_default(),
                                                        get_field_as_string("regex", map).unwrap_or_default())),
+      "RequestValue" => map.get("expression").map(|f|
+        Generator::RequestValue(json_to_string(f), map.get("dataType")
+          .map(|dt| DataType::from(dt.clone())))),
       _ => {
         log::warn!("'{}' is not a valid generator type", gen_type);
         None
@@ -219,6 +235,7 @@ impl Generator {
     match self {
       Generator::ProviderStateGenerator(_, _) => mode == &GeneratorTestMode::Provider,
       Generator::MockServerURL(_, _) => mode == &GeneratorTestMode::Consumer,
+      Generator::RequestValue(_, _) => mode == &GeneratorTestMode::Consumer,
       _ => true
     }
   }
@@ -247,6 +264,10 @@ impl Hash for Generator {
         str1.hash(state);
         str2.hash(state);
       },
+      Generator::RequestValue(str, datatype) => {
+        str.hash(state);
+        datatype.hash(state);
+      },
       Generator::ArrayContains(variants) => {
         for (index, rules, generators) in variants {
           index.hash(state);
@@ -276,6 +297,7 @@ impl PartialEq for Generator {
       (Generator::Date(format1), Generator::Date(format2)) => format1 == format2,
       (Generator::ProviderStateGenerator(str1, data1), Generator::ProviderStateGenerator(str2, data2)) => str1 == str2 && data1 == data2,
       (Generator::MockServerURL(ex1, re1), Generator::MockServerURL(ex2, re2)) => ex1 == ex2 && re1 == re2,
+      (Generator::RequestValue(str1, data1), Generator::RequestValue(str2, data2)) => str1 == str2 && data1 == data2,
       (Generator::ArrayContains(variants1), Generator::ArrayContains(variants2)) => variants1 == variants2,
       (Generator::Uuid(format), Generator::Uuid(format2)) => format == format2,
       _ => mem::discriminant(self) == mem::discriminant(other)
@@ -741,13 +763,15 @@ impl Default for Generators {
   }
 }
 
-/// If the mode applies, invoke the callback for each of the generators
+/// If the mode applies, invoke the callback for each of the generators. The generators are
+/// invoked in the order of their keys, so that seeded generators always generate the same value
+/// for each key.
 pub fn apply_generators<F>(
   mode: &GeneratorTestMode,
   generators: &HashMap<String, Generator>,
   closure: &mut F
 ) where F: FnMut(&String, &Generator) {
-  for (key, value) in generators {
+  for (key, value) in generators.iter().sorted_by(|(a, _), (b, _)| a.cmp(b)) {
     if value.corresponds_to_mode(mode) {
       closure(&key, &value)
     }
@@ -831,59 +855,194 @@ pub fn generate_value_from_context(expression: &str, context: &HashMap<&str, Val
   data_type.clone().unwrap_or(DataType::RAW).wrap(result)
 }
 
+/// Converts the request into the form that the `RequestValue` generator looks values up in, to be
+/// added to the generator context as `request`. The path is split into its segments, header names
+/// are lower case, and JSON bodies are parsed. Query parameters and headers with a single value
+/// are stored as that value, otherwise as a list of the values.
+pub fn request_context(request: &Request) -> Value {
+  let values = |values: &Vec<String>| if values.len() == 1 {
+    json!(values[0])
+  } else {
+    json!(values)
+  };
+  let segments = request.path.split('/')
+    .filter(|segment| !segment.is_empty())
+    .collect::<Vec<&str>>();
+  let query = request.query.as_ref()
+    .map(|query| query.iter()
+      .map(|(name, vals)| (name.clone(), values(vals)))
+      .collect::<serde_json::Map<String, Value>>())
+    .unwrap_or_default();
+  let headers = request.headers.as_ref()
+    .map(|headers| headers.iter()
+      .map(|(name, vals)| (name.to_lowercase(), values(vals)))
+      .collect::<serde_json::Map<String, Value>>())
+    .unwrap_or_default();
+  let body = match &request.body {
+    OptionalBody::Present(bytes, _) => if request.content_type().map(|ct| ct.is_json()).unwrap_or(false) {
+      serde_json::from_slice(bytes).unwrap_or_else(|_| json!(request.body.str_value()))
+    } else {
+      json!(request.body.str_value())
+    },
+    _ => Value::Null
+  };
+  json!({
+    "path": segments,
+    "query": query,
+    "headers": headers,
+    "body": body
+  })
+}
+
+/// Looks up the value for the path expression in the request stored in the generator context
+/// (as `request`), converting it to the data type (defaults to the raw value)
+pub fn generate_value_from_request(expression: &str, context: &HashMap<&str, Value>, data_type: &Option<DataType>) -> anyhow::Result<DataValue> {
+  let request = context.get("request")
+    .ok_or_else(|| anyhow!("RequestValue: can not generate a value as there is no request in the test context"))?;
+  let tokens = parse_path_exp(expression)
+    .map_err(|err| anyhow!("RequestValue: '{}' is not a valid path expression - {}", expression, err))?;
+  let mut value = request;
+  for token in &tokens {
+    let next = match token {
+      PathToken::Root => Some(value),
+      PathToken::Field(name) => value.get(name.as_str()).or_else(|| value.get(name.to_lowercase().as_str())),
+      PathToken::Index(index) => value.get(*index),
+      PathToken::Star | PathToken::StarIndex => None
+    };
+    value = next.ok_or_else(|| anyhow!("RequestValue: the request has no value for '{}'", expression))?;
+  }
+  data_type.clone().unwrap_or(DataType::RAW).wrap(Ok(value.clone()))
+}
+
+thread_local! {
+  /// Random number generator used by the generators while they are seeded
+  static SEEDED_RNG: RefCell<Option<StdRng>> = RefCell::new(None);
+}
+
+/// Time used by the date and time generators while they are seeded (2000-01-01T00:00:00Z)
+const SEEDED_TIME: i64 = 946_684_800;
+
+struct SeedGuard(Option<StdRng>);
+
+impl Drop for SeedGuard {
+  fn drop(&mut self) {
+    let previous = self.0.take();
+    SEEDED_RNG.with(|rng| rng.replace(previous));
+  }
+}
+
+/// Runs the function with the generators seeded with the given seed, so that they generate the
+/// same values every time. The date and time generators also use a fixed time while seeded. If
+/// no seed is given, the function is run with the generators generating random values. The seed
+/// only applies to generators run on the current thread.
+pub fn with_generator_seed<R, F: FnOnce() -> R>(seed: Option<u64>, f: F) -> R {
+  match seed {
+    Some(seed) => {
+      let previous = SEEDED_RNG.with(|rng| rng.replace(Some(StdRng::seed_from_u64(seed))));
+      let _guard = SeedGuard(previous);
+      f()
+    },
+    None => f()
+  }
+}
+
+/// Applies the function to the seeded random number generator if the generators are seeded,
+/// otherwise to the thread random number generator
+fn with_rng<R, F: FnOnce(&mut dyn RngCore) -> R>(f: F) -> R {
+  SEEDED_RNG.with(|rng| match rng.borrow_mut().as_mut() {
+    Some(rng) => f(rng),
+    None => f(&mut rand::thread_rng())
+  })
+}
+
+fn generate_uuid() -> Uuid {
+  let bytes = with_rng(|rng| rng.gen::<[u8; 16]>());
+  uuid::Builder::from_bytes(bytes)
+    .set_variant(uuid::Variant::RFC4122)
+    .set_version(uuid::Version::Random)
+    .build()
+}
+
+/// Formats the current date and/or time for the date and time generators. While the generators
+/// are seeded, the fixed `SEEDED_TIME` in UTC is used instead of the local time.
+fn format_generator_now(generator: &Generator, pattern: Option<&str>) -> String {
+  let seeded = SEEDED_RNG.with(|rng| rng.borrow().is_some());
+  if seeded {
+    format_date_time(&FixedOffset::east(0).timestamp(SEEDED_TIME, 0), generator, pattern)
+  } else {
+    format_date_time(&Local::now(), generator, pattern)
+  }
+}
+
+fn format_date_time<Tz: TimeZone>(now: &DateTime<Tz>, generator: &Generator, pattern: Option<&str>) -> String
+  where Tz::Offset: Display {
+  match (generator, pattern) {
+    (Generator::Date(_), Some(pattern)) => now.date().format(pattern).to_string(),
+    (Generator::Date(_), None) => now.naive_local().date().to_string(),
+    (Generator::Time(_), None) => now.time().format("%H:%M:%S").to_string(),
+    (_, Some(pattern)) => now.format(pattern).to_string(),
+    (_, None) => now.format("%Y-%m-%dT%H:%M:%S.%3f%z").to_string()
+  }
+}
+
 const DIGIT_CHARSET: &str = "0123456789";
 pub fn generate_decimal(digits: usize) -> String {
-  let mut rnd = rand::thread_rng();
-  let chars: Vec<char> = DIGIT_CHARSET.chars().collect();
-  match digits {
-    0 => "".to_string(),
-    1 => chars.choose(&mut rnd).unwrap().to_string(),
-    2 => format!("{}.{}", chars.choose(&mut rnd).unwrap(), chars.choose(&mut rnd).unwrap()),
-    _ => {
-      let mut sample = String::new();
-      for _ in 0..(digits + 1) {
-        sample.push(*chars.choose(&mut rnd).unwrap());
-      }
-      if sample.starts_with("00") {
-        let chars = DIGIT_CHARSET[1..].chars();
-        sample.insert(0, chars.choose(&mut rnd).unwrap());
+  with_rng(|rnd| {
+    let chars: Vec<char> = DIGIT_CHARSET.chars().collect();
+    match digits {
+      0 => "".to_string(),
+      1 => chars.choose(rnd).unwrap().to_string(),
+      2 => format!("{}.{}", chars.choose(rnd).unwrap(), chars.choose(rnd).unwrap()),
+      _ => {
+        let mut sample = String::new();
+        for _ in 0..(digits + 1) {
+          sample.push(*chars.choose(rnd).unwrap());
+        }
+        if sample.starts_with("00") {
+          let chars = DIGIT_CHARSET[1..].chars();
+          sample.insert(0, chars.choose(rnd).unwrap());
+        }
+        let pos = rnd.gen_range(1..digits - 1);
+        let selected_digits = if pos != 1 && sample.starts_with('0') {
+          &sample[1..(digits + 1)]
+        } else {
+          &sample[..digits]
+        };
+        let generated = format!("{}.{}", &selected_digits[..pos], &selected_digits[pos..]);
+        trace!("RandomDecimalGenerator: sample_digits=[{}], pos={}, selected_digits=[{}], generated=[{}]",
+               sample, pos, selected_digits, generated);
+        generated
       }
-      let pos = rnd.gen_range(1..digits - 1);
-      let selected_digits = if pos != 1 && sample.starts_with('0') {
-        &sample[1..(digits + 1)]
-      } else {
-        &sample[..digits]
-      };
-      let generated = format!("{}.{}", &selected_digits[..pos], &selected_digits[pos..]);
-      trace!("RandomDecimalGenerator: sample_digits=[{}], pos={}, selected_digits=[{}], generated=[{}]",
-             sample, pos, selected_digits, generated);
-      generated
     }
-  }
+  })
 }
 
 const HEX_CHARSET: &str = "0123456789ABCDEF";
 pub fn generate_hexadecimal(digits: usize) -> String {
-  let mut rnd = rand::thread_rng();
-  HEX_CHARSET.chars().choose_multiple(&mut rnd, digits).iter().join("")
+  with_rng(|rnd| HEX_CHARSET.chars().choose_multiple(rnd, digits).iter().join(""))
 }
 
 impl GenerateValue<u16> for Generator {
   fn generate_value(&self, value: &u16, context: &HashMap<&str, Value>, _matcher: &Box<dyn VariantMatcher>) -> anyhow::Result<u16> {
     match self {
-      &Generator::RandomInt(min, max) => Ok(rand::thread_rng().gen_range(min as u16..(max as u16).saturating_add(1))),
+      &Generator::RandomInt(min, max) => Ok(with_rng(|rnd| rnd.gen_range(min as u16..(max as u16).saturating_add(1)))),
       &Generator::ProviderStateGenerator(ref exp, ref dt) =>
         match generate_value_from_context(exp, context, dt) {
           Ok(val) => u16::try_from(val),
           Err(err) => Err(err)
         },
+      &Generator::RequestValue(ref exp, ref dt) =>
+        match generate_value_from_request(exp, context, dt) {
+          Ok(val) => u16::try_from(val),
+          Err(err) => Err(err)
+        },
       _ => Err(anyhow!("Could not generate a u16 value from {} using {:?}", value, self))
     }
   }
 }
 
 pub fn generate_ascii_string(size: usize) -> String {
-  rand::thread_rng().sample_iter(&Alphanumeric).map(char::from).take(size).collect()
+  with_rng(|rnd| rnd.sample_iter(&Alphanumeric).map(char::from).take(size).collect())
 }
 
 fn strip_anchors(regex: &str) -> &str {
@@ -894,14 +1053,13 @@ fn strip_anchors(regex: &str) -> &str {
 
 impl GenerateValue<String> for Generator {
   fn generate_value(&self, _: &String, context: &HashMap<&str, Value>, _matcher: &Box<dyn VariantMatcher>) -> anyhow::Result<String> {
-    let mut rnd = rand::thread_rng();
     let result = match self {
-      Generator::RandomInt(min, max) => Ok(format!("{}", rnd.gen_range(*min..max.saturating_add(1)))),
+      Generator::RandomInt(min, max) => Ok(format!("{}", with_rng(|rnd| rnd.gen_range(*min..max.saturating_add(1))))),
       Generator::Uuid(format) => match format.unwrap_or_default() {
-        UuidFormat::Simple => Ok(Uuid::new_v4().to_simple().to_string()),
-        UuidFormat::LowerCaseHyphenated => Ok(Uuid::new_v4().to_hyphenated().to_string()),
-        UuidFormat::UpperCaseHyphenated => Ok(Uuid::new_v4().to_hyphenated().to_string().to_uppercase()),
-        UuidFormat::Urn => Ok(Uuid::new_v4().to_urn().to_string())
+        UuidFormat::Simple => Ok(generate_uuid().to_simple().to_string()),
+        UuidFormat::LowerCaseHyphenated => Ok(generate_uuid().to_hyphenated().to_string()),
+        UuidFormat::UpperCaseHyphenated => Ok(generate_uuid().to_hyphenated().to_string().to_uppercase()),
+        UuidFormat::Urn => Ok(generate_uuid().to_urn().to_string())
       },
       Generator::RandomDecimal(digits) => Ok(generate_decimal(*digits as usize)),
       Generator::RandomHexadecimal(digits) => Ok(generate_hexadecimal(*digits as usize)),
@@ -911,7 +1069,7 @@ impl GenerateValue<String> for Generator {
         match parser.parse(strip_anchors(regex)) {
           Ok(hir) => {
             match rand_regex::Regex::with_hir(hir, 20) {
-              Ok(gen) => Ok(rnd.sample(gen)),
+              Ok(gen) => Ok(with_rng(|rnd| rnd.sample(gen))),
               Err(err) => {
                 warn!("Failed to generate a value from regular expression - {}", err);
                 Err(anyhow!("Failed to generate a value from regular expression - {}", err))
@@ -926,37 +1084,39 @@ impl GenerateValue<String> for Generator {
       },
       Generator::Date(ref format) => match format {
         Some(pattern) => match parse_pattern(pattern) {
-          Ok(tokens) => Ok(Local::now().date().format(&to_chrono_pattern(&tokens)).to_string()),
+          Ok(tokens) => Ok(format_generator_now(self, Some(&to_chrono_pattern(&tokens)))),
           Err(err) => {
             warn!("Date format {} is not valid - {}", pattern, err);
             Err(anyhow!("Date format {} is not valid - {}", pattern, err))
           }
         },
-        None => Ok(Local::now().naive_local().date().to_string())
+        None => Ok(format_generator_now(self, None))
       },
       Generator::Time(ref format) => match format {
         Some(pattern) => match parse_pattern(pattern) {
-          Ok(tokens) => Ok(Local::now().format(&to_chrono_pattern(&tokens)).to_string()),
+          Ok(tokens) => Ok(format_generator_now(self, Some(&to_chrono_pattern(&tokens)))),
           Err(err) => {
             warn!("Time format {} is not valid - {}", pattern, err);
             Err(anyhow!("Time format {} is not valid - {}", pattern, err))
           }
         },
-        None => Ok(Local::now().time().format("%H:%M:%S").to_string())
+        None => Ok(format_generator_now(self, None))
       },
       Generator::DateTime(ref format) => match format {
         Some(pattern) => match parse_pattern(pattern) {
-          Ok(tokens) => Ok(Local::now().format(&to_chrono_pattern(&tokens)).to_string()),
+          Ok(tokens) => Ok(format_generator_now(self, Some(&to_chrono_pattern(&tokens)))),
           Err(err) => {
             warn!("DateTime format {} is not valid - {}", pattern, err);
             Err(anyhow!("DateTime format {} is not valid - {}", pattern, err))
           }
         },
-        None => Ok(Local::now().format("%Y-%m-%dT%H:%M:%S.%3f%z").to_string())
+        None => Ok(format_generator_now(self, None))
       },
-      Generator::RandomBoolean => Ok(format!("{}", rnd.gen::<bool>())),
+      Generator::RandomBoolean => Ok(format!("{}", with_rng(|rnd| rnd.gen::<bool>()))),
       Generator::ProviderStateGenerator(ref exp, ref dt) =>
         generate_value_from_context(exp, context, dt).map(|val| val.to_string()),
+      Generator::RequestValue(ref exp, ref dt) =>
+        generate_value_from_request(exp, context, dt).map(|val| val.to_string()),
       Generator::MockServerURL(example, regex) => if let Some(mock_server_details) = context.get("mockServer") {
         debug!("Generating URL from Mock Server details");
         match mock_server_details.as_object() {
@@ -994,7 +1154,7 @@ impl GenerateValue<Value> for Generator {
     debug!("Generating value from {:?} with context {:?}", self, context);
     let result = match self {
       Generator::RandomInt(min, max) => {
-        let rand_int = rand::thread_rng().gen_range(*min..max.saturating_add(1));
+        let rand_int = with_rng(|rnd| rnd.gen_range(*min..max.saturating_add(1)));
         match value {
           Value::String(_) => Ok(json!(format!("{}", rand_int))),
           Value::Number(_) => Ok(json!(rand_int)),
@@ -1003,10 +1163,10 @@ impl GenerateValue<Value> for Generator {
       },
       Generator::Uuid(format) => match value {
         Value::String(_) => match format.unwrap_or_default() {
-          UuidFormat::Simple => Ok(json!(Uuid::new_v4().to_simple().to_string())),
-          UuidFormat::LowerCaseHyphenated => Ok(json!(Uuid::new_v4().to_hyphenated().to_string())),
-          UuidFormat::UpperCaseHyphenated => Ok(json!(Uuid::new_v4().to_hyphenated().to_string().to_uppercase())),
-          UuidFormat::Urn => Ok(json!(Uuid::new_v4().to_urn().to_string()))
+          UuidFormat::Simple => Ok(json!(generate_uuid().to_simple().to_string())),
+          UuidFormat::LowerCaseHyphenated => Ok(json!(generate_uuid().to_hyphenated().to_string())),
+          UuidFormat::UpperCaseHyphenated => Ok(json!(generate_uuid().to_hyphenated().to_string().to_uppercase())),
+          UuidFormat::Urn => Ok(json!(generate_uuid().to_urn().to_string()))
         },
         _ => Err(anyhow!("Could not generate a UUID from {}", value))
       },
@@ -1031,7 +1191,7 @@ impl GenerateValue<Value> for Generator {
         match parser.parse(regex) {
           Ok(hir) => {
             let gen = rand_regex::Regex::with_hir(hir, 20).unwrap();
-            Ok(json!(rand::thread_rng().sample::<String, _>(gen)))
+            Ok(json!(with_rng(|rnd| rnd.sample::<String, _>(gen))))
           },
           Err(err) => {
             warn!("'{}' is not a valid regular expression - {}", regex, err);
@@ -1041,40 +1201,45 @@ impl GenerateValue<Value> for Generator {
       },
       Generator::Date(ref format) => match format {
         Some(pattern) => match parse_pattern(pattern) {
-          Ok(tokens) => Ok(json!(Local::now().date().format(&to_chrono_pattern(&tokens)).to_string())),
+          Ok(tokens) => Ok(json!(format_generator_now(self, Some(&to_chrono_pattern(&tokens))))),
           Err(err) => {
             warn!("Date format {} is not valid - {}", pattern, err);
             Err(anyhow!("Could not generate a random date from {} - {}", pattern, err))
           }
         },
-        None => Ok(json!(Local::now().naive_local().date().to_string()))
+        None => Ok(json!(format_generator_now(self, None)))
       },
       Generator::Time(ref format) => match format {
         Some(pattern) => match parse_pattern(pattern) {
-          Ok(tokens) => Ok(json!(Local::now().format(&to_chrono_pattern(&tokens)).to_string())),
+          Ok(tokens) => Ok(json!(format_generator_now(self, Some(&to_chrono_pattern(&tokens))))),
           Err(err) => {
             warn!("Time format {} is not valid - {}", pattern, err);
             Err(anyhow!("Could not generate a random time from {} - {}", pattern, err))
           }
         },
-        None => Ok(json!(Local::now().time().format("%H:%M:%S").to_string()))
+        None => Ok(json!(format_generator_now(self, None)))
       },
       Generator::DateTime(ref format) => match format {
         Some(pattern) => match parse_pattern(pattern) {
-          Ok(tokens) => Ok(json!(Local::now().format(&to_chrono_pattern(&tokens)).to_string())),
+          Ok(tokens) => Ok(json!(format_generator_now(self, Some(&to_chrono_pattern(&tokens))))),
           Err(err) => {
             warn!("DateTime format {} is not valid - {}", pattern, err);
             Err(anyhow!("Could not generate a random date-time from {} - {}", pattern, err))
           }
         },
-        None => Ok(json!(Local::now().format("%Y-%m-%dT%H:%M:%S.%3f%z").to_string()))
+        None => Ok(json!(format_generator_now(self, None)))
       },
-      Generator::RandomBoolean => Ok(json!(rand::thread_rng().gen::<bool>())),
+      Generator::RandomBoolean => Ok(json!(with_rng(|rnd| rnd.gen::<bool>()))),
       Generator::ProviderStateGenerator(ref exp, ref dt) =>
         match generate_value_from_context(exp, context, dt) {
           Ok(val) => val.as_json(),
           Err(err) => Err(err)
         },
+      Generator::RequestValue(ref exp, ref dt) =>
+        match generate_value_from_request(exp, context, dt) {
+          Ok(val) => val.as_json(),
+          Err(err) => Err(err)
+        },
       Generator::MockServerURL(example, regex) => {
         debug!("context = {:?}", context);
         if let Some(mock_server_details) = context.get("mockServer") {
@@ -1103,8 +1268,8 @@ impl GenerateValue<Value> for Generator {
             if let Some((variant, generators)) = matcher.find_matching_variant(value, variants) {
               debug!("Generating values for variant {} and value {}", variant, value);
               let mut handler = JsonHandler { value: value.clone() };
-              for (key, generator) in generators {
-                handler.apply_key(&key, &generator, context, matcher);
+              for (key, generator) in generators.iter().sorted_by(|(a, _), (b, _)| a.cmp(b)) {
+                handler.apply_key(&key, generator, context, matcher);
               };
               debug!("Generated value {}", handler.value);
               result[index] = handler.value.clone();
@@ -1205,7 +1370,7 @@ impl ContentTypeHandler<Value> for JsonHandler {
     context: &HashMap<&str, Value>,
     matcher: &Box<dyn VariantMatcher>
   ) -> Result<OptionalBody, String> {
-    for (key, generator) in generators {
+    for (key, generator) in generators.iter().sorted_by(|(a, _), (b, _)| a.cmp(b)) {
       if generator.corresponds_to_mode(mode) {
         debug!("Applying generator {:?} to key {}", generator, key);
         self.apply_key(key, generator, context, matcher);
@@ -1581,6 +1746,36 @@ mod tests {
     assert_that!(generated.unwrap(), matches_regex(r"^urn:uuid:[a-fA-F0-9]{8}-[a-fA-F0-9]{4}-[a-fA-F0-9]{4}-[a-fA-F0-9]{4}-[a-fA-F0-9]{12}$"));
   }
 
+  #[test]
+  fn seeded_generators_generate_the_same_values() {
+    let generators = vec![
+      Generator::RandomInt(0, 1000),
+      Generator::Uuid(None),
+      Generator::RandomString(20),
+      Generator::RandomDecimal(6),
+      Generator::DateTime(None)
+    ];
+    let generate = || generators.iter()
+      .map(|g| g.generate_value(&"".to_string(), &hashmap! {}, &NoopVariantMatcher.boxed()).unwrap())
+      .collect::<Vec<String>>();
+    let first = with_generator_seed(Some(1234), generate);
+    let second = with_generator_seed(Some(1234), generate);
+    let other = with_generator_seed(Some(4321), generate);
+    expect!(first.clone()).to(be_equal_to(second));
+    expect!(first[..4].to_vec()).to_not(be_equal_to(other[..4].to_vec()));
+    expect!(first[4].as_str()).to(be_equal_to("2000-01-01T00:00:00.000+0000"));
+    expect!(Uuid::parse_str(first[1].as_str()).unwrap().get_version_num()).to(be_equal_to(4));
+  }
+
+  #[test]
+  fn unseeded_date_time_generators_use_the_local_time() {
+    let generated = Generator::DateTime(None)
+      .generate_value(&"".to_string(), &hashmap! {}, &NoopVariantMatcher.boxed()).unwrap();
+    let offset = Local::now().format("%z").to_string();
+    expect!(generated.ends_with(offset.as_str())).to(be_true());
+    expect!(generated.as_str()).to_not(be_equal_to("2000-01-01T00:00:00.000+0000"));
+  }
+
   #[test]
   fn random_decimal_generator_test() {
     for _ in 1..10 {
@@ -1621,6 +1816,47 @@ mod tests {
     expect!(generated).to(be_err());
   }
 
+  #[test]
+  fn request_value_generator_test() {
+    let request = Request {
+      method: "POST".to_string(),
+      path: "/orders/1234".to_string(),
+      query: Some(hashmap!{ "page".to_string() => vec!["2".to_string()] }),
+      headers: Some(hashmap!{
+        "Content-Type".to_string() => vec!["application/json".to_string()],
+        "X-Id".to_string() => vec!["abc".to_string()]
+      }),
+      body: OptionalBody::Present("{\"orderId\": 100}".into(), None),
+      .. Request::default()
+    };
+    let context = hashmap!{ "request" => request_context(&request) };
+    let generate = |expression: &str| Generator::RequestValue(expression.to_string(), None)
+      .generate_value(&json!(""), &context, &NoopVariantMatcher.boxed());
+
+    expect!(generate("$.path[1]")).to(be_ok().value(json!("1234")));
+    expect!(generate("$.query.page")).to(be_ok().value(json!("2")));
+    expect!(generate("$.headers['X-Id']")).to(be_ok().value(json!("abc")));
+    expect!(generate("$.body.orderId")).to(be_ok().value(json!(100)));
+    expect!(generate("$.body.customerId")).to(be_err());
+    expect!(Generator::RequestValue("$.body.orderId".into(), Some(DataType::STRING))
+      .generate_value(&"".to_string(), &context, &NoopVariantMatcher.boxed())).to(be_ok().value("100".to_string()));
+    expect!(Generator::RequestValue("$.path[0]".into(), None)
+      .generate_value(&json!(""), &hashmap!{}, &NoopVariantMatcher.boxed())).to(be_err());
+  }
+
+  #[test]
+  fn request_value_generator_json_round_trip() {
+    let generator = Generator::RequestValue("$.body.orderId".into(), Some(DataType::INTEGER));
+    let json = generator.to_json().unwrap();
+    expect!(json.clone()).to(be_equal_to(json!({
+      "type": "RequestValue",
+      "expression": "$.body.orderId",
+      "dataType": "INTEGER"
+    })));
+    expect!(Generator::from_map("RequestValue", json.as_object().unwrap())).to(be_some().value(generator));
+    expect!(Generator::RequestValue("a".into(), None).corresponds_to_mode(&GeneratorTestMode::Provider)).to(be_false());
+  }
+
   #[test]
   fn applies_the_generator_to_a_json_map_entry() {
     let map = json!({"a": 100, "b": "B", "c": "C"});
@@ -0,0 +1,89 @@
+//! Functions for dealing with HTTP header values
+
+/// Headers whose values are defined as comma separated lists, and so can be split into their
+/// individual values. All other headers (i.e. `Date`, `Set-Cookie`, `Expires`) can contain commas
+/// as part of the value, and must be kept as is.
+pub const LIST_VALUED_HEADERS: [&str; 24] = [
+  "accept",
+  "accept-charset",
+  "accept-encoding",
+  "accept-language",
+  "accept-ranges",
+  "access-control-allow-headers",
+  "access-control-allow-methods",
+  "access-control-expose-headers",
+  "access-control-request-headers",
+  "allow",
+  "cache-control",
+  "connection",
+  "content-encoding",
+  "content-language",
+  "expect",
+  "if-match",
+  "if-none-match",
+  "pragma",
+  "te",
+  "trailer",
+  "transfer-encoding",
+  "upgrade",
+  "vary",
+  "via"
+];
+
+/// If the values of the header are a comma separated list
+pub fn is_list_valued_header(name: &str) -> bool {
+  LIST_VALUED_HEADERS.contains(&name.to_lowercase().as_str())
+}
+
+/// Parses a header value into its individual values. Only list-valued headers are split on
+/// commas (ignoring any commas in quoted strings), the values of all other headers are returned
+/// unchanged.
+pub fn parse_header(name: &str, value: &str) -> Vec<String> {
+  if is_list_valued_header(name) {
+    let mut values = vec![];
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for ch in value.chars() {
+      match ch {
+        _ if escaped => escaped = false,
+        '\\' if in_quotes => escaped = true,
+        '"' => in_quotes = !in_quotes,
+        ',' if !in_quotes => {
+          values.push(current.trim().to_string());
+          current.clear();
+          continue;
+        },
+        _ => ()
+      }
+      current.push(ch);
+    }
+    values.push(current.trim().to_string());
+    values.into_iter().filter(|value| !value.is_empty()).collect()
+  } else {
+    vec![value.trim().to_string()]
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use super::*;
+
+  #[test]
+  fn parse_header_splits_list_valued_headers() {
+    expect!(parse_header("Accept", "application/json, text/plain")).to(be_equal_to(
+      vec!["application/json".to_string(), "text/plain".to_string()]));
+    expect!(parse_header("if-none-match", r#""a,b", W/"c""#)).to(be_equal_to(
+      vec![r#""a,b""#.to_string(), r#"W/"c""#.to_string()]));
+  }
+
+  #[test]
+  fn parse_header_does_not_split_other_headers() {
+    expect!(parse_header("Date", "Wed, 21 Oct 2015 07:28:00 GMT")).to(be_equal_to(
+      vec!["Wed, 21 Oct 2015 07:28:00 GMT".to_string()]));
+    expect!(parse_header("Set-Cookie", "id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT")).to(be_equal_to(
+      vec!["id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT".to_string()]));
+  }
+}
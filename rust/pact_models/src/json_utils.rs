@@ -109,7 +109,7 @@ pub fn headers_from_json(request: &Value) -> Option<HashMap<String, Vec<String>>
     Some(v) => match *v {
       Value::Object(ref m) => Some(m.iter().map(|(key, val)| {
         match val {
-          &Value::String(ref s) => (key.clone(), s.clone().split(',').map(|v| v.trim().to_string()).collect()),
+          &Value::String(ref s) => (key.clone(), vec![s.clone()]),
           &Value::Array(ref v) => (key.clone(), v.iter().map(|val| {
             match val {
               &Value::String(ref s) => s.clone(),
@@ -236,6 +236,22 @@ mod tests {
     expect!(json_to_num(Value::from_str("100.10").ok())).to(be_some().value(100));
   }
 
+  #[test]
+  fn headers_from_json_keeps_the_header_values_as_written() {
+    let json = json!({
+      "headers": {
+        "Accept": "application/json, text/plain",
+        "Date": "Wed, 21 Oct 2015 07:28:00 GMT",
+        "Set-Cookie": ["a=1; Expires=Wed, 21 Oct 2015 07:28:00 GMT", "b=2"]
+      }
+    });
+    let headers = headers_from_json(&json).unwrap();
+    expect!(headers.get("Accept")).to(be_some().value(&vec!["application/json, text/plain".to_string()]));
+    expect!(headers.get("Date")).to(be_some().value(&vec!["Wed, 21 Oct 2015 07:28:00 GMT".to_string()]));
+    expect!(headers.get("Set-Cookie")).to(be_some().value(&vec![
+      "a=1; Expires=Wed, 21 Oct 2015 07:28:00 GMT".to_string(), "b=2".to_string()]));
+  }
+
   #[test]
   fn body_from_text_plain_type_returns_the_same_formatted_body() {
     let json : serde_json::Value = serde_json::from_str(r#"
@@ -27,6 +27,7 @@ pub mod path_exp;
 pub mod query_strings;
 pub mod http_utils;
 pub mod http_parts;
+pub mod headers;
 pub mod request;
 pub mod response;
 
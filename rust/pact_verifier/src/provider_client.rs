@@ -4,14 +4,16 @@ use std::convert::TryFrom;
 use futures::future::*;
 use http::{HeaderMap, HeaderValue, Method};
 use http::header::{HeaderName, InvalidHeaderName, InvalidHeaderValue};
-use http::header::CONTENT_TYPE;
+use http::header::{CONTENT_LENGTH, CONTENT_TYPE};
 use http::method::InvalidMethod;
 use itertools::Itertools;
 use log::*;
 use reqwest::{Client, Error, RequestBuilder};
 
+use pact_matching::content_encoding::{decode_body, encode_body};
 use pact_models::bodies::OptionalBody;
 use pact_models::content_types::ContentType;
+use pact_models::headers::parse_header;
 use pact_models::http_parts::HttpPart;
 use pact_models::request::Request;
 use pact_models::response::Response;
@@ -61,9 +63,17 @@ fn create_native_request(client: &Client, base_url: &str, request: &Request) ->
       }).collect_vec());
   }
 
+  let body = encode_body(&request.body, &request.headers)
+    .map_err(|err| ProviderClientError::RequestBodyError(format!("Failed to encode the request body: {}", err)))?;
+  // If the body has been encoded, the content length from the pact no longer applies to it
+  let body_encoded = body != request.body;
+
   if let Some(headers) = &request.headers {
     let mut header_map = HeaderMap::new();
     for (k, vals) in headers {
+      if body_encoded && k.eq_ignore_ascii_case(CONTENT_LENGTH.as_str()) {
+        continue;
+      }
       for header_value in vals {
         let header_name = HeaderName::try_from(k)
           .map_err(|err| ProviderClientError::RequestHeaderNameError(
@@ -76,7 +86,7 @@ fn create_native_request(client: &Client, base_url: &str, request: &Request) ->
     builder = builder.headers(header_map);
   }
 
-  match request.body {
+  match body {
     OptionalBody::Present(ref s, _) => builder = builder.body(s.clone()),
     OptionalBody::Null => {
       if request.content_type().unwrap_or_default().is_json() {
@@ -102,8 +112,7 @@ fn extract_headers(headers: &HeaderMap) -> Option<HashMap<String, Vec<String>>>
        (name.as_str().into(), parsed_vals.iter().cloned()
             .filter(|val| val.is_ok())
             .map(|val| val.unwrap_or_default())
-            .flat_map(|val| val.split(",").map(|v| v.to_string()).collect::<Vec<String>>())
-            .map(|val| val.trim().to_string())
+            .flat_map(|val| parse_header(name.as_str(), val.as_str()))
             .collect())
       })
       .collect();
@@ -117,6 +126,14 @@ fn extract_headers(headers: &HeaderMap) -> Option<HashMap<String, Vec<String>>>
 async fn extract_body(response: reqwest::Response, pact_response: &Response) -> Result<OptionalBody, reqwest::Error> {
   let body = response.bytes().await?;
   if !body.is_empty() {
+    let body = match decode_body(&OptionalBody::Present(body.clone(), None), &pact_response.headers) {
+      Ok(OptionalBody::Present(decoded, _)) => decoded,
+      Ok(_) => body,
+      Err(err) => {
+        warn!("Could not decode the response body, it will be compared as it was received - {}", err);
+        body
+      }
+    };
     Ok(OptionalBody::Present(body, pact_response.content_type()))
   } else {
     Ok(OptionalBody::Empty)
@@ -255,10 +272,11 @@ mod tests {
   use itertools::Itertools;
   use maplit::*;
 
+  use pact_matching::content_encoding::ContentEncoding;
   use pact_models::bodies::OptionalBody;
   use pact_models::request::Request;
 
-  use super::{create_native_request, extract_headers, join_paths};
+  use super::{create_native_request, extract_headers, join_paths, native_response_to_pact_response};
 
   #[test]
   fn extract_headers_tests() {
@@ -288,6 +306,14 @@ mod tests {
     expect!(&response["access-control-expose-headers"][2]).to(be_equal_to(&"Expires"));
   }
 
+  #[test]
+  fn extract_headers_does_not_split_values_of_headers_that_are_not_lists() {
+    let mut headers = HeaderMap::new();
+    headers.insert("Date", "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+    let response = extract_headers(&headers).unwrap();
+    expect!(&response["date"]).to(be_equal_to(&vec!["Wed, 21 Oct 2015 07:28:00 GMT".to_string()]));
+  }
+
   #[test]
   fn join_paths_test() {
     expect!(join_paths("", "")).to(be_equal_to("/"));
@@ -398,4 +424,76 @@ mod tests {
     expect!(request_builder.url().as_str()).to(be_equal_to("http://example.test:8080/"));
     expect!(request_builder.body().unwrap().as_bytes()).to(be_some().value("null".as_bytes()));
   }
+
+  #[test]
+  fn convert_request_to_native_request_encodes_the_body_with_the_content_encoding() {
+    let client = reqwest::Client::new();
+    let base_url = "http://example.test:8080".to_string();
+    let request = Request {
+      method: "POST".to_string(),
+      headers: Some(hashmap! {
+        "Content-Encoding".to_string() => vec!["gzip".to_string()]
+      }),
+      body: OptionalBody::from("body"),
+      .. Request::default()
+    };
+    let request_builder = create_native_request(&client, &base_url, &request).unwrap().build().unwrap();
+
+    let body = request_builder.body().unwrap().as_bytes().unwrap();
+    expect!(body).to_not(be_equal_to("body".as_bytes()));
+    expect!(pact_matching::content_encoding::ContentEncoding::Gzip.decode(body).unwrap())
+      .to(be_equal_to("body".as_bytes().to_vec()));
+  }
+
+  #[test]
+  fn convert_request_to_native_request_drops_the_content_length_when_the_body_is_encoded() {
+    let client = reqwest::Client::new();
+    let base_url = "http://example.test:8080".to_string();
+    let request = Request {
+      method: "POST".to_string(),
+      headers: Some(hashmap! {
+        "Content-Encoding".to_string() => vec!["gzip".to_string()],
+        "Content-Length".to_string() => vec!["4".to_string()]
+      }),
+      body: OptionalBody::from("body"),
+      .. Request::default()
+    };
+    let request_builder = create_native_request(&client, &base_url, &request).unwrap().build().unwrap();
+
+    expect!(request_builder.headers().get("content-length")).to(be_none());
+    expect!(request_builder.headers().get("content-encoding").unwrap().to_str().unwrap()).to(be_equal_to("gzip"));
+  }
+
+  #[test]
+  fn convert_request_to_native_request_keeps_the_content_length_when_the_body_is_not_encoded() {
+    let client = reqwest::Client::new();
+    let base_url = "http://example.test:8080".to_string();
+    let request = Request {
+      method: "POST".to_string(),
+      headers: Some(hashmap! {
+        "Content-Length".to_string() => vec!["4".to_string()]
+      }),
+      body: OptionalBody::from("body"),
+      .. Request::default()
+    };
+    let request_builder = create_native_request(&client, &base_url, &request).unwrap().build().unwrap();
+
+    expect!(request_builder.headers().get("content-length").unwrap().to_str().unwrap()).to(be_equal_to("4"));
+  }
+
+  #[tokio::test]
+  async fn native_response_to_pact_response_decodes_encoded_bodies() {
+    for (name, encoding) in &[("gzip", ContentEncoding::Gzip), ("br", ContentEncoding::Brotli)] {
+      let body = encoding.encode(r#"{"id":1}"#.as_bytes()).unwrap();
+      let native_response = http::Response::builder()
+        .header("Content-Type", "application/json")
+        .header("Content-Encoding", *name)
+        .body(body)
+        .unwrap();
+
+      let response = native_response_to_pact_response(reqwest::Response::from(native_response)).await.unwrap();
+
+      expect!(response.body.str_value()).to(be_equal_to(r#"{"id":1}"#));
+    }
+  }
 }